@@ -19,13 +19,17 @@ use crate::db::{DbState, EncryptedPathsState, EncryptionSetupState, VAULT_SYNC_N
 
 // ---- Company (A1.5 şirket kartı) ----
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Company {
     pub id: String,
     pub name: String,
     pub domain: Option<String>,
     pub industry: Option<String>,
     pub notes: Option<String>,
+    pub headcount: Option<i64>,
+    pub hq_location: Option<String>,
+    pub funding_stage: Option<String>,
+    pub is_pinned: bool,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -36,8 +40,27 @@ pub struct CreateCompanyInput {
     pub domain: Option<String>,
     pub industry: Option<String>,
     pub notes: Option<String>,
+    pub headcount: Option<i64>,
+    pub hq_location: Option<String>,
+    pub funding_stage: Option<String>,
 }
 
+/// Default options for companies.funding_stage; overridable via the `funding_stage_options` setting.
+const DEFAULT_FUNDING_STAGES: [&str; 8] = [
+    "Bootstrapped",
+    "Pre-seed",
+    "Seed",
+    "Series A",
+    "Series B",
+    "Series C+",
+    "Public",
+    "Acquired",
+];
+
+/// Column order matches row_to_company.
+const COMPANY_SELECT: &str = "SELECT id, name, domain, industry, notes, headcount, hq_location, funding_stage,
+        is_pinned, created_at, updated_at FROM companies";
+
 fn row_to_company(row: &Row) -> rusqlite::Result<Company> {
     Ok(Company {
         id: row.get(0)?,
@@ -45,8 +68,12 @@ fn row_to_company(row: &Row) -> rusqlite::Result<Company> {
         domain: row.get(2)?,
         industry: row.get(3)?,
         notes: row.get(4)?,
-        created_at: row.get(5)?,
-        updated_at: row.get(6)?,
+        headcount: row.get(5)?,
+        hq_location: row.get(6)?,
+        funding_stage: row.get(7)?,
+        is_pinned: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
     })
 }
 
@@ -74,6 +101,20 @@ pub struct Contact {
     pub next_touch_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Opt-out: no outreach; excluded from reminder/cold queries when requested.
+    pub do_not_contact: bool,
+    /// GDPR consent: "opt_in" | "opt_out" | "unknown" (None = never recorded).
+    pub consent_status: Option<String>,
+    pub consent_at: Option<String>,
+    /// IANA timezone name, e.g. "Europe/Istanbul".
+    pub timezone: Option<String>,
+    /// Manually confirmed; reset when the value changes in contact_update or contact_merge.
+    pub email_verified: bool,
+    pub phone_verified: bool,
+    /// Set while the contact is in the trash (soft-deleted).
+    pub deleted_at: Option<String>,
+    /// Favorite: listed first in contact_list and returned by contacts_pinned.
+    pub is_pinned: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -102,6 +143,17 @@ pub struct DedupCandidate {
     pub a: Contact,
     pub b: Contact,
     pub reasons: Vec<String>,
+    /// 0..1; candidates are returned highest first.
+    pub confidence: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompanyDedupCandidate {
+    pub a: Company,
+    pub b: Company,
+    /// "name" and/or "domain".
+    pub reasons: Vec<String>,
+    pub confidence: f32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -112,6 +164,16 @@ pub struct MergeContactInput {
     pub custom_values: Option<Vec<CustomValueInput>>,
 }
 
+/// Column order matches row_to_contact; callers append WHERE / ORDER BY.
+const CONTACT_SELECT: &str = "SELECT c.id, c.first_name, c.last_name, c.title,
+        COALESCE(co.name, c.company), c.company_id, c.city, c.country,
+        c.email, c.email_secondary, c.phone, c.phone_secondary,
+        c.linkedin_url, c.twitter_url, c.website, c.notes,
+        c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at,
+        c.do_not_contact, c.consent_status, c.consent_at, c.timezone,
+        c.email_verified, c.phone_verified, c.deleted_at, c.is_pinned
+        FROM contacts c LEFT JOIN companies co ON c.company_id = co.id";
+
 fn row_to_contact(row: &Row) -> rusqlite::Result<Contact> {
     Ok(Contact {
         id: row.get(0)?,
@@ -134,6 +196,14 @@ fn row_to_contact(row: &Row) -> rusqlite::Result<Contact> {
         next_touch_at: row.get(17)?,
         created_at: row.get(18)?,
         updated_at: row.get(19)?,
+        do_not_contact: row.get(20)?,
+        consent_status: row.get(21)?,
+        consent_at: row.get(22)?,
+        timezone: row.get(23)?,
+        email_verified: row.get(24)?,
+        phone_verified: row.get(25)?,
+        deleted_at: row.get(26)?,
+        is_pinned: row.get(27)?,
     })
 }
 
@@ -201,6 +271,41 @@ fn normalize_domain(domain: &Option<String>) -> Option<String> {
     }
 }
 
+fn funding_stage_options(conn: &rusqlite::Connection) -> Result<Vec<String>, String> {
+    if let Some(raw) = setting_get(conn, "funding_stage_options")? {
+        if let Ok(list) = serde_json::from_str::<Vec<String>>(&raw) {
+            if !list.is_empty() {
+                return Ok(list);
+            }
+        }
+    }
+    Ok(DEFAULT_FUNDING_STAGES.iter().map(|s| s.to_string()).collect())
+}
+
+/// Trims funding_stage; empty becomes None, anything else must be one of the configured options.
+fn validate_funding_stage(
+    conn: &rusqlite::Connection,
+    value: &Option<String>,
+) -> Result<Option<String>, String> {
+    let Some(v) = value else { return Ok(None); };
+    let v = v.trim();
+    if v.is_empty() {
+        return Ok(None);
+    }
+    if funding_stage_options(conn)?.iter().any(|o| o == v) {
+        Ok(Some(v.to_string()))
+    } else {
+        Err(format!("Geçersiz funding stage: {}", v))
+    }
+}
+
+fn validate_headcount(value: Option<i64>) -> Result<Option<i64>, String> {
+    match value {
+        Some(n) if n < 0 => Err(format!("Geçersiz çalışan sayısı: {}", n)),
+        _ => Ok(value),
+    }
+}
+
 fn normalize_email(value: &Option<String>) -> Option<String> {
     let Some(v) = value else { return None; };
     let v = v.trim().to_lowercase();
@@ -211,7 +316,8 @@ fn normalize_email(value: &Option<String>) -> Option<String> {
     }
 }
 
-fn normalize_phone(value: &Option<String>) -> Option<String> {
+/// Digits-only phone (>= 6 digits); stored in contacts.phone_normalized / phone_secondary_normalized.
+pub(crate) fn normalize_phone(value: &Option<String>) -> Option<String> {
     let Some(v) = value else { return None; };
     let digits: String = v.chars().filter(|c| c.is_ascii_digit()).collect();
     if digits.len() < 6 {
@@ -221,6 +327,97 @@ fn normalize_phone(value: &Option<String>) -> Option<String> {
     }
 }
 
+/// Calling codes for common `country` spellings (name, local name or ISO code, lowercase).
+const COUNTRY_DIAL_CODES: [(&[&str], &str); 16] = [
+    (&["turkey", "türkiye", "turkiye", "tr"], "90"),
+    (&["united states", "usa", "us", "abd"], "1"),
+    (&["canada", "ca"], "1"),
+    (&["united kingdom", "uk", "gb", "england", "ingiltere"], "44"),
+    (&["germany", "deutschland", "almanya", "de"], "49"),
+    (&["france", "fransa", "fr"], "33"),
+    (&["netherlands", "hollanda", "nl"], "31"),
+    (&["spain", "ispanya", "es"], "34"),
+    (&["italy", "italya", "it"], "39"),
+    (&["switzerland", "isviçre", "ch"], "41"),
+    (&["austria", "avusturya", "at"], "43"),
+    (&["sweden", "isveç", "se"], "46"),
+    (&["ireland", "irlanda", "ie"], "353"),
+    (&["united arab emirates", "uae", "bae", "ae"], "971"),
+    (&["india", "hindistan", "in"], "91"),
+    (&["singapore", "singapur", "sg"], "65"),
+];
+
+fn country_dial_code(country: &Option<String>) -> Option<&'static str> {
+    let c = country.as_ref()?.trim().to_lowercase();
+    COUNTRY_DIAL_CODES
+        .iter()
+        .find(|(names, _)| names.contains(&c.as_str()))
+        .map(|(_, code)| *code)
+}
+
+/// E.164 digits without the "+" (>= 6 digits): a "+" or "00" prefix is international, otherwise a
+/// national number is expanded with `dial_code` (trunk "0" dropped) when one is given.
+fn phone_e164_digits(value: &str, dial_code: Option<&str>) -> Option<String> {
+    let v = value.trim();
+    let digits: String = v.chars().filter(|c| c.is_ascii_digit()).collect();
+    let key = if v.starts_with('+') {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else {
+        match dial_code {
+            Some(code) if digits.starts_with(code) && digits.len() > 10 => digits,
+            Some(code) => format!("{}{}", code, digits.trim_start_matches('0')),
+            None => digits,
+        }
+    };
+    if key.len() < 6 {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+/// Canonical stored phone: E.164 ("+905321234567") when the number has a "+"/"00" prefix or the
+/// country is known, otherwise digits only. None for values that are not a valid phone.
+fn canonical_phone(value: &str, country: &Option<String>) -> Option<String> {
+    let v = value.trim();
+    if v.is_empty() || !is_valid_phone(&Some(v.to_string())) {
+        return None;
+    }
+    let code = country_dial_code(country);
+    let international = v.starts_with('+') || v.starts_with("00");
+    let key = phone_e164_digits(v, code)?;
+    if international || code.is_some() {
+        Some(format!("+{}", key))
+    } else {
+        Some(key)
+    }
+}
+
+/// Calling code (digits only) from the `default_country_code` setting, used for national numbers
+/// of contacts without a recognised country.
+fn default_country_code(conn: &rusqlite::Connection) -> Result<Option<String>, String> {
+    Ok(setting_get(conn, "default_country_code")?
+        .map(|v| v.chars().filter(|c| c.is_ascii_digit()).collect::<String>())
+        .filter(|v| !v.is_empty()))
+}
+
+/// Match keys for a contact's phones: the contact's country wins over `default_code`.
+fn contact_phone_keys(c: &Contact, default_code: Option<&str>) -> Vec<String> {
+    phone_keys(&c.country, [&c.phone, &c.phone_secondary], default_code)
+}
+
+/// E.164 digit keys of up to two phones; the country's dial code wins over `default_code`.
+fn phone_keys(country: &Option<String>, phones: [&Option<String>; 2], default_code: Option<&str>) -> Vec<String> {
+    let code = country_dial_code(country).or(default_code);
+    phones
+        .into_iter()
+        .flatten()
+        .filter_map(|p| phone_e164_digits(p, code))
+        .collect()
+}
+
 fn normalize_name(first: &str, last: &str) -> String {
     let mut s = String::with_capacity(first.len() + last.len() + 1);
     s.push_str(first);
@@ -275,6 +472,153 @@ fn name_similarity(a_first: &str, a_last: &str, b_first: &str, b_last: &str) ->
     }
 }
 
+/// Simplified Metaphone for one name word, so "Geoff"/"Jeff" and "Catherine"/"Kathryn" share a key.
+/// Vowels only count at the start; Turkish letters are folded to their ASCII base first.
+fn metaphone(word: &str) -> String {
+    let letters: Vec<char> = word
+        .chars()
+        .filter_map(|c| match c.to_lowercase().next().unwrap_or(c) {
+            'ç' => Some('C'),
+            'ş' => Some('S'),
+            'ğ' => Some('G'),
+            'ı' | 'î' => Some('I'),
+            'ö' => Some('O'),
+            'ü' | 'û' => Some('U'),
+            'â' => Some('A'),
+            c if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase()),
+            _ => None,
+        })
+        .collect();
+    let is_vowel = |c: Option<&char>| matches!(c, Some('A' | 'E' | 'I' | 'O' | 'U'));
+    let mut start = 0;
+    if letters.len() > 1 && matches!((letters[0], letters[1]), ('K' | 'G' | 'P', 'N') | ('W', 'R')) {
+        start = 1;
+    }
+    let mut key = String::new();
+    for i in start..letters.len() {
+        let c = letters[i];
+        let prev = if i > start { letters.get(i - 1) } else { None };
+        let next = letters.get(i + 1);
+        if prev == Some(&c) && c != 'C' {
+            continue;
+        }
+        let code: Option<&str> = match c {
+            'A' | 'E' | 'I' | 'O' | 'U' => (i == start).then_some("A"),
+            'B' => (!(prev == Some(&'M') && next.is_none())).then_some("B"),
+            'C' if next == Some(&'H') => Some("X"),
+            'C' if matches!(next, Some('E' | 'I' | 'Y')) => Some("S"),
+            'C' => Some("K"),
+            'D' if next == Some(&'G') && matches!(letters.get(i + 2), Some('E' | 'I' | 'Y')) => Some("J"),
+            'D' => Some("T"),
+            'G' if next == Some(&'H') && !is_vowel(letters.get(i + 2)) => None,
+            'G' if next == Some(&'N') && letters.get(i + 2).is_none() => None,
+            'G' if matches!(next, Some('E' | 'I' | 'Y')) => Some("J"),
+            'G' => Some("K"),
+            'H' if matches!(prev, Some('C' | 'S' | 'P' | 'T' | 'G')) => None,
+            'H' => is_vowel(next).then_some("H"),
+            'K' if prev == Some(&'C') => None,
+            'K' => Some("K"),
+            'P' if next == Some(&'H') => Some("F"),
+            'P' => Some("P"),
+            'Q' => Some("K"),
+            'S' if next == Some(&'H') => Some("X"),
+            'S' => Some("S"),
+            'T' if next == Some(&'H') => Some("0"),
+            'T' => Some("T"),
+            'V' => Some("F"),
+            'W' | 'Y' => is_vowel(next).then_some(if c == 'W' { "W" } else { "Y" }),
+            'X' => Some(if i == start { "S" } else { "KS" }),
+            'Z' => Some("S"),
+            'F' => Some("F"),
+            'J' => Some("J"),
+            'L' => Some("L"),
+            'M' => Some("M"),
+            'N' => Some("N"),
+            'R' => Some("R"),
+            _ => None,
+        };
+        if let Some(code) = code {
+            if !key.ends_with(code) {
+                key.push_str(code);
+            }
+        }
+    }
+    key
+}
+
+/// Phonetic key over first + last name; None unless both parts produce a code.
+fn phonetic_name_key(first: &str, last: &str) -> Option<String> {
+    let part = |s: &str| normalize_name(s, "").split_whitespace().map(metaphone).collect::<Vec<_>>().join(" ");
+    let first = part(first);
+    let last = part(last);
+    if first.trim().is_empty() || last.trim().is_empty() {
+        return None;
+    }
+    Some(format!("{}|{}", first, last))
+}
+
+/// Legal-form suffixes ignored when comparing company names.
+const COMPANY_NAME_SUFFIXES: [&str; 12] = [
+    "inc", "llc", "ltd", "limited", "corp", "corporation", "co", "gmbh", "as", "aş", "sti", "şti",
+];
+
+fn normalize_company_name(name: &str) -> String {
+    let words: Vec<String> = name
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .map(|w| w.to_string())
+        .collect();
+    let mut end = words.len();
+    while end > 1 && COMPANY_NAME_SUFFIXES.contains(&words[end - 1].as_str()) {
+        end -= 1;
+    }
+    words[..end].join(" ")
+}
+
+fn company_name_similarity(a: &str, b: &str) -> f32 {
+    let a = normalize_company_name(a);
+    let b = normalize_company_name(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let dist = levenshtein(&a, &b) as f32;
+    1.0 - dist / a.len().max(b.len()) as f32
+}
+
+/// Independent evidence combined as 1 - Π(1 - w): exact email 0.9, exact phone 0.8, name up to 0.6
+/// (scaled by Levenshtein similarity).
+fn dedup_confidence(email: bool, phone: bool, name_sim: f32) -> f32 {
+    let mut miss = 1.0_f32;
+    if email {
+        miss *= 1.0 - 0.9;
+    }
+    if phone {
+        miss *= 1.0 - 0.8;
+    }
+    miss *= 1.0 - 0.6 * name_sim.clamp(0.0, 1.0);
+    1.0 - miss
+}
+
+/// Parses stored timestamps: RFC 3339 ("...Z"), "YYYY-MM-DD HH:MM[:SS]", "YYYY-MM-DDTHH:MM[:SS]" (UTC) or a bare date.
+fn parse_datetime_utc(value: &str) -> Option<chrono::DateTime<Utc>> {
+    let v = value.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(v) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(v, fmt) {
+            return Some(naive.and_utc());
+        }
+    }
+    chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|naive| naive.and_utc())
+}
+
 fn setting_get(conn: &rusqlite::Connection, key: &str) -> Result<Option<String>, String> {
     conn.query_row(
         "SELECT value FROM app_settings WHERE key = ?1",
@@ -295,6 +639,51 @@ fn setting_set(conn: &rusqlite::Connection, key: &str, value: &str) -> Result<()
     Ok(())
 }
 
+/// UI preferences the frontend may read/write through app_setting_get/set. Everything else in
+/// app_settings (keys, paths, feature settings with their own commands) stays backend-only.
+const UI_SETTING_KEYS: [&str; 7] = [
+    "theme",
+    "locale",
+    "date_format",
+    "time_format",
+    "week_start",
+    "default_contact_sort",
+    "sidebar_collapsed",
+];
+
+fn ui_setting_key(key: &str) -> Result<&str, String> {
+    let key = key.trim();
+    if UI_SETTING_KEYS.contains(&key) {
+        Ok(key)
+    } else {
+        Err(format!("Bu ayar arayüzden değiştirilemez: {}", key))
+    }
+}
+
+#[tauri::command]
+pub fn app_setting_get(db: State<DbState>, key: String) -> Result<Option<String>, String> {
+    let key = ui_setting_key(&key)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_get(conn, key)
+}
+
+/// None removes the preference so the UI falls back to its default.
+#[tauri::command]
+pub fn app_setting_set(db: State<DbState>, key: String, value: Option<String>) -> Result<(), String> {
+    let key = ui_setting_key(&key)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    match value {
+        Some(v) => setting_set(conn, key, &v),
+        None => {
+            conn.execute("DELETE FROM app_settings WHERE key = ?1", params![key])
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
 fn attachments_dir(conn: &rusqlite::Connection) -> Result<PathBuf, String> {
     let dir = setting_get(conn, "attachments_dir")?
         .ok_or_else(|| "Attachments dir not set".to_string())?;
@@ -303,7 +692,18 @@ fn attachments_dir(conn: &rusqlite::Connection) -> Result<PathBuf, String> {
     Ok(path)
 }
 
+/// HKDF info for the attachments key when it is derived from the master key.
+const ATTACHMENTS_KEY_INFO: &[u8] = b"vaultcrm attachments key v1";
+
+/// "stored" (random key base64 in app_settings, the default) or "derived" (from the master key).
+fn attachments_key_derived(conn: &rusqlite::Connection) -> Result<bool, String> {
+    Ok(setting_get(conn, "attachments_key_source")?.as_deref() == Some("derived"))
+}
+
 fn attachments_key(conn: &rusqlite::Connection) -> Result<Vec<u8>, String> {
+    if attachments_key_derived(conn)? {
+        return crate::db::derive_subkey(ATTACHMENTS_KEY_INFO);
+    }
     if let Some(existing) = setting_get(conn, "attachments_key")? {
         if let Ok(bytes) = general_purpose::STANDARD.decode(existing.as_bytes()) {
             if bytes.len() == 32 {
@@ -354,13 +754,39 @@ fn sanitize_file_name(name: &str) -> String {
     }
 }
 
-fn is_allowed_attachment(file_name: &str) -> bool {
-    let lower = file_name.to_lowercase();
-    lower.ends_with(".pdf")
-        || lower.ends_with(".doc")
-        || lower.ends_with(".docx")
-        || lower.ends_with(".ppt")
-        || lower.ends_with(".pptx")
+/// Default attachment extensions; overridable via the `attachment_allowed_exts` setting.
+const DEFAULT_ATTACHMENT_EXTS: [&str; 11] = [
+    "pdf", "doc", "docx", "ppt", "pptx", "png", "jpg", "jpeg", "xlsx", "xls", "txt",
+];
+
+/// Executable/script extensions that are never accepted, whatever the allowlist says.
+const BLOCKED_ATTACHMENT_EXTS: [&str; 16] = [
+    "exe", "bat", "cmd", "com", "msi", "scr", "dll", "ps1", "vbs", "js", "jar", "sh", "app", "dmg",
+    "pkg", "apk",
+];
+
+/// Lowercases and strips a leading dot: ".PNG" -> "png".
+fn normalize_attachment_ext(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
+fn attachment_allowed_exts(conn: &rusqlite::Connection) -> Result<Vec<String>, String> {
+    if let Some(raw) = setting_get(conn, "attachment_allowed_exts")? {
+        if let Ok(list) = serde_json::from_str::<Vec<String>>(&raw) {
+            if !list.is_empty() {
+                return Ok(list);
+            }
+        }
+    }
+    Ok(DEFAULT_ATTACHMENT_EXTS.iter().map(|s| s.to_string()).collect())
+}
+
+fn is_allowed_attachment(file_name: &str, allowed: &[String]) -> bool {
+    let Some((_, ext)) = file_name.rsplit_once('.') else {
+        return false;
+    };
+    let ext = normalize_attachment_ext(ext);
+    !BLOCKED_ATTACHMENT_EXTS.contains(&ext.as_str()) && allowed.contains(&ext)
 }
 
 fn value_contains_option(value: &Option<String>, target: &str) -> bool {
@@ -375,36 +801,100 @@ fn value_contains_option(value: &Option<String>, target: &str) -> bool {
     v.split(',').map(|s| s.trim()).any(|s| s == target)
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct ContactListParams {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// "updated_at" (default) | "last_name" | "first_name" | "last_touched_at" | "created_at"
+    pub sort_by: Option<String>,
+    /// "asc" | "desc" (default)
+    pub sort_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactListPage {
+    pub items: Vec<Contact>,
+    pub total: i64,
+}
+
+/// ORDER BY clause from an allowlist; user input is never interpolated.
+fn contact_list_order(params: &ContactListParams) -> Result<String, String> {
+    let column = match params.sort_by.as_deref().unwrap_or("updated_at") {
+        "updated_at" => "c.updated_at",
+        "created_at" => "c.created_at",
+        "last_touched_at" => "c.last_touched_at",
+        "last_name" => "c.last_name COLLATE NOCASE",
+        "first_name" => "c.first_name COLLATE NOCASE",
+        other => return Err(format!("Invalid sort_by: {}", other)),
+    };
+    let dir = match params.sort_dir.as_deref().unwrap_or("desc") {
+        "asc" => "ASC",
+        "desc" => "DESC",
+        other => return Err(format!("Invalid sort_dir: {}", other)),
+    };
+    Ok(format!("ORDER BY c.is_pinned DESC, {} {}, c.id", column, dir))
+}
+
+/// Without params: every contact, most recently updated first (as before). Pinned contacts
+/// always come first, each group in the requested order.
 #[tauri::command]
-pub fn contact_list(db: State<DbState>) -> Result<Vec<Contact>, String> {
+pub fn contact_list(db: State<DbState>, params: Option<ContactListParams>) -> Result<ContactListPage, String> {
+    let params = params.unwrap_or_default();
+    let order = contact_list_order(&params)?;
+    let limit = params.limit.map(|l| l.clamp(1, 1000)).unwrap_or(-1);
+    let offset = params.offset.unwrap_or(0).max(0);
     let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    let sql = "SELECT c.id, c.first_name, c.last_name, c.title,
-        COALESCE(co.name, c.company), c.company_id, c.city, c.country,
-        c.email, c.email_secondary, c.phone, c.phone_secondary,
-        c.linkedin_url, c.twitter_url, c.website, c.notes,
-        c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at
-        FROM contacts c LEFT JOIN companies co ON c.company_id = co.id
-        ORDER BY c.updated_at DESC";
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM contacts WHERE deleted_at IS NULL", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    let sql = format!("{} WHERE c.deleted_at IS NULL {} LIMIT ?1 OFFSET ?2", CONTACT_SELECT, order);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([], row_to_contact)
+        .query_map(params![limit, offset], row_to_contact)
         .map_err(|e| e.to_string())?;
-    let list: Vec<Contact> = rows.filter_map(|r| r.ok()).collect();
-    Ok(list)
+    let items: Vec<Contact> = rows.filter_map(|r| r.ok()).collect();
+    Ok(ContactListPage { items, total })
+}
+
+/// Pinning does not touch updated_at.
+#[tauri::command]
+pub fn contact_set_pinned(db: State<DbState>, id: String, pinned: bool) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let changed = conn
+        .execute(
+            "UPDATE contacts SET is_pinned = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![pinned, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Contact not found".to_string());
+    }
+    Ok(())
+}
+
+/// Pinned contacts for the sidebar, by name.
+#[tauri::command]
+pub fn contacts_pinned(db: State<DbState>) -> Result<Vec<Contact>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!(
+        "{} WHERE c.is_pinned = 1 AND c.deleted_at IS NULL
+         ORDER BY c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_contact).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
 #[tauri::command]
 pub fn contact_get(db: State<DbState>, id: String) -> Result<Option<Contact>, String> {
     let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    let sql = "SELECT c.id, c.first_name, c.last_name, c.title,
-        COALESCE(co.name, c.company), c.company_id, c.city, c.country,
-        c.email, c.email_secondary, c.phone, c.phone_secondary,
-        c.linkedin_url, c.twitter_url, c.website, c.notes,
-        c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at
-        FROM contacts c LEFT JOIN companies co ON c.company_id = co.id WHERE c.id = ?1";
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     let mut rows = stmt.query(params![id]).map_err(|e| e.to_string())?;
     if let Some(row) = rows.next().map_err(|e| e.to_string())? {
         let contact = row_to_contact(&row).map_err(|e| e.to_string())?;
@@ -430,7 +920,7 @@ pub fn contact_create(db: State<DbState>, input: CreateContactInput) -> Result<C
         let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
         resolve_company_name(conn, &company_id, &mut company);
         conn.execute(
-            "INSERT INTO contacts (id, first_name, last_name, title, company, company_id, city, country, email, email_secondary, phone, phone_secondary, linkedin_url, twitter_url, website, notes, next_touch_at, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+            "INSERT INTO contacts (id, first_name, last_name, title, company, company_id, city, country, email, email_secondary, phone, phone_secondary, phone_normalized, phone_secondary_normalized, linkedin_url, twitter_url, website, notes, next_touch_at, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
             params![
                 id,
                 input.first_name,
@@ -444,6 +934,8 @@ pub fn contact_create(db: State<DbState>, input: CreateContactInput) -> Result<C
                 input.email_secondary,
                 input.phone,
                 input.phone_secondary,
+                normalize_phone(&input.phone),
+                normalize_phone(&input.phone_secondary),
                 input.linkedin_url,
                 input.twitter_url,
                 input.website,
@@ -479,7 +971,10 @@ pub fn contact_update(
         let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
         resolve_company_name(conn, &company_id, &mut company);
         conn.execute(
-            "UPDATE contacts SET first_name=?1, last_name=?2, title=?3, company=?4, company_id=?5, city=?6, country=?7, email=?8, email_secondary=?9, phone=?10, phone_secondary=?11, linkedin_url=?12, twitter_url=?13, website=?14, notes=?15, next_touch_at=?16, updated_at=?17 WHERE id=?18",
+            "UPDATE contacts SET first_name=?1, last_name=?2, title=?3, company=?4, company_id=?5, city=?6, country=?7, email=?8, email_secondary=?9, phone=?10, phone_secondary=?11, phone_normalized=?12, phone_secondary_normalized=?13, linkedin_url=?14, twitter_url=?15, website=?16, notes=?17, next_touch_at=?18, updated_at=?19,
+                email_verified = CASE WHEN email IS ?8 THEN email_verified ELSE 0 END,
+                phone_verified = CASE WHEN phone IS ?10 THEN phone_verified ELSE 0 END
+             WHERE id=?20",
             params![
                 input.first_name,
                 input.last_name,
@@ -492,6 +987,8 @@ pub fn contact_update(
                 input.email_secondary,
                 input.phone,
                 input.phone_secondary,
+                normalize_phone(&input.phone),
+                normalize_phone(&input.phone_secondary),
                 input.linkedin_url,
                 input.twitter_url,
                 input.website,
@@ -506,1529 +1003,9904 @@ pub fn contact_update(
     contact_get(db, id)?.ok_or_else(|| "Contact not found".to_string())
 }
 
+/// Sets or clears the do-not-contact (opt-out) flag.
 #[tauri::command]
-pub fn contact_delete(db: State<DbState>, id: String) -> Result<(), String> {
-    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    conn.execute("DELETE FROM contacts WHERE id = ?1", params![id]).map_err(|e| e.to_string())?;
+pub fn contact_set_dnc(db: State<DbState>, id: String, flag: bool) -> Result<(), String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let changed = conn
+        .execute(
+            "UPDATE contacts SET do_not_contact = ?1, updated_at = ?2 WHERE id = ?3",
+            params![flag, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Contact not found".to_string());
+    }
     Ok(())
 }
 
-#[tauri::command]
-pub fn company_list(db: State<DbState>) -> Result<Vec<Company>, String> {
-    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    let mut stmt = conn
-        .prepare("SELECT id, name, domain, industry, notes, created_at, updated_at FROM companies ORDER BY name")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([], row_to_company)
-        .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
-}
+const CONSENT_STATUSES: [&str; 3] = ["opt_in", "opt_out", "unknown"];
 
-#[tauri::command]
-pub fn company_get(db: State<DbState>, id: String) -> Result<Option<Company>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let mut stmt = conn
-        .prepare("SELECT id, name, domain, industry, notes, created_at, updated_at FROM companies WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let mut rows = stmt.query(params![id]).map_err(|e| e.to_string())?;
-    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let company = row_to_company(&row).map_err(|e| e.to_string())?;
-        return Ok(Some(company));
+fn validate_consent_status(status: &str) -> Result<(), String> {
+    if CONSENT_STATUSES.contains(&status) {
+        Ok(())
+    } else {
+        Err(format!("Geçersiz consent durumu: {}", status))
     }
-    Ok(None)
 }
 
+/// Records GDPR consent; `consent_at` defaults to now and must be a valid date/time.
 #[tauri::command]
-pub fn company_create(db: State<DbState>, input: CreateCompanyInput) -> Result<Company, String> {
-    let id = Uuid::new_v4().to_string();
+pub fn contact_set_consent(
+    db: State<DbState>,
+    id: String,
+    status: String,
+    consent_at: Option<String>,
+) -> Result<Contact, String> {
+    validate_consent_status(&status)?;
     let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let domain = normalize_domain(&input.domain);
+    let consent_at = match consent_at.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(v) => parse_datetime_utc(v)
+            .ok_or_else(|| "Geçersiz consent tarihi".to_string())?
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string(),
+        None => now.clone(),
+    };
     {
-        let conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-        let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
-        conn.execute(
-            "INSERT INTO companies (id, name, domain, industry, notes, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![id, input.name, domain, input.industry, input.notes, now, now],
-        )
-        .map_err(|e| e.to_string())?;
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let conn = conn.as_ref().ok_or("DB not initialized")?;
+        let changed = conn
+            .execute(
+                "UPDATE contacts SET consent_status = ?1, consent_at = ?2, updated_at = ?3 WHERE id = ?4",
+                params![status, consent_at, now, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err("Contact not found".to_string());
+        }
     }
-    company_get(db, id)?.ok_or_else(|| "Company not found after insert".to_string())
+    contact_get(db, id)?.ok_or_else(|| "Contact not found".to_string())
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UpdateCompanyInput {
-    pub name: String,
-    pub domain: Option<String>,
-    pub industry: Option<String>,
-    pub notes: Option<String>,
+/// Contacts with the given consent status; "unknown" also covers contacts never recorded.
+#[tauri::command]
+pub fn contacts_by_consent(db: State<DbState>, status: String) -> Result<Vec<Contact>, String> {
+    validate_consent_status(&status)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL AND COALESCE(c.consent_status, 'unknown') = ?1
+         ORDER BY c.last_name, c.first_name",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![status], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
+fn verified_column(field: &str) -> Result<&'static str, String> {
+    match field {
+        "email" => Ok("email_verified"),
+        "phone" => Ok("phone_verified"),
+        other => Err(format!("Invalid field: {}", other)),
+    }
+}
+
+/// Marks the contact's email or phone (`field`) as confirmed or not.
 #[tauri::command]
-pub fn company_update(
-    db: State<DbState>,
-    id: String,
-    input: UpdateCompanyInput,
-) -> Result<Company, String> {
+pub fn contact_set_verified(db: State<DbState>, id: String, field: String, verified: bool) -> Result<(), String> {
+    let column = verified_column(&field)?;
     let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let domain = normalize_domain(&input.domain);
-    {
-        let conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-        let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
-        conn.execute(
-            "UPDATE companies SET name=?1, domain=?2, industry=?3, notes=?4, updated_at=?5 WHERE id=?6",
-            params![input.name, domain, input.industry, input.notes, now, id],
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let changed = conn
+        .execute(
+            &format!("UPDATE contacts SET {} = ?1, updated_at = ?2 WHERE id = ?3", column),
+            params![verified, now, id],
         )
         .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Contact not found".to_string());
     }
-    company_get(db, id)?.ok_or_else(|| "Company not found".to_string())
+    Ok(())
 }
 
+/// Contacts that have a value in `field` ("email" | "phone") that isn't verified yet.
 #[tauri::command]
-pub fn contact_list_by_company(db: State<DbState>, company_id: String) -> Result<Vec<Contact>, String> {
+pub fn contacts_unverified(db: State<DbState>, field: String) -> Result<Vec<Contact>, String> {
+    let column = verified_column(&field)?;
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let sql = "SELECT c.id, c.first_name, c.last_name, c.title,
-        COALESCE(co.name, c.company), c.company_id, c.city, c.country,
-        c.email, c.email_secondary, c.phone, c.phone_secondary,
-        c.linkedin_url, c.twitter_url, c.website, c.notes,
-        c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at
-        FROM contacts c LEFT JOIN companies co ON c.company_id = co.id
-        WHERE c.company_id = ?1 ORDER BY c.updated_at DESC";
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![company_id], row_to_contact)
-        .map_err(|e| e.to_string())?;
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL AND TRIM(COALESCE(c.{}, '')) != '' AND c.{} = 0
+         ORDER BY c.last_name, c.first_name",
+        CONTACT_SELECT, field, column
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_contact).map_err(|e| e.to_string())?;
     Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
-// ---- Custom fields (A3) ----
+#[derive(Debug, Serialize)]
+pub struct Completeness {
+    /// 0–100: share of key fields (name, email, phone, company, title) and required custom fields filled.
+    pub score: u8,
+    /// Labels of the missing key fields and names of the missing required custom fields.
+    pub missing: Vec<String>,
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CustomField {
-    pub id: String,
-    pub name: String,
-    pub kind: String,
-    pub options: Option<String>,
-    pub sort_order: i64,
-    pub created_at: String,
+#[derive(Debug, Serialize)]
+pub struct ContactCompleteness {
+    pub contact: Contact,
+    #[serde(flatten)]
+    pub completeness: Completeness,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CustomValue {
-    pub field_id: String,
-    pub field_name: String,
-    pub kind: String,
-    pub options: Option<String>,
-    pub value: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct CreateCustomFieldInput {
-    pub name: String,
-    pub kind: String,
-    pub options: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct CustomValueInput {
-    pub field_id: String,
-    pub value: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Attachment {
-    pub id: String,
-    pub owner_type: String,
-    pub owner_id: String,
-    pub file_name: String,
-    pub mime: Option<String>,
-    pub size: Option<i64>,
-    pub storage_path: String,
-    pub created_at: String,
+/// `required_fields` are (id, name) of required contact custom fields; `filled` holds the ids
+/// this contact has a value for.
+fn contact_completeness(
+    c: &Contact,
+    required_fields: &[(String, String)],
+    filled: &std::collections::HashSet<String>,
+) -> Completeness {
+    fn present(v: Option<&str>) -> bool {
+        v.is_some_and(|s| !s.trim().is_empty())
+    }
+    let name = format!("{}{}", c.first_name, c.last_name);
+    let key_fields = [
+        ("Ad", present(Some(&name))),
+        ("E-posta", present(c.email.as_deref())),
+        ("Telefon", present(c.phone.as_deref())),
+        ("Şirket", present(c.company.as_deref())),
+        ("Ünvan", present(c.title.as_deref())),
+    ];
+    let mut missing: Vec<String> = key_fields
+        .iter()
+        .filter(|(_, ok)| !ok)
+        .map(|(label, _)| label.to_string())
+        .collect();
+    missing.extend(
+        required_fields
+            .iter()
+            .filter(|(id, _)| !filled.contains(id))
+            .map(|(_, name)| name.clone()),
+    );
+    let total = key_fields.len() + required_fields.len();
+    let score = ((total - missing.len()) * 100 + total / 2) / total;
+    Completeness {
+        score: score as u8,
+        missing,
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct AttachmentCreateInput {
-    pub owner_type: String,
-    pub owner_id: String,
-    pub file_name: String,
-    pub mime: Option<String>,
-    pub bytes: Vec<u8>,
+fn required_contact_fields(conn: &rusqlite::Connection) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name FROM custom_fields WHERE required = 1 AND scope = 'contact' ORDER BY sort_order, name")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
-#[tauri::command]
-pub fn custom_field_list(db: State<DbState>) -> Result<Vec<CustomField>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
+/// contact_id -> ids of custom fields with a non-empty value ("[]" counts as empty).
+fn filled_custom_fields(
+    conn: &rusqlite::Connection,
+    contact_id: Option<&str>,
+) -> Result<HashMap<String, std::collections::HashSet<String>>, String> {
     let mut stmt = conn
-        .prepare("SELECT id, name, kind, options, sort_order, created_at FROM custom_fields ORDER BY sort_order, name")
+        .prepare(
+            "SELECT contact_id, field_id FROM contact_custom_values
+             WHERE (?1 IS NULL OR contact_id = ?1) AND TRIM(COALESCE(value, '')) NOT IN ('', '[]')",
+        )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([], |row| {
-            Ok(CustomField {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                kind: row.get(2)?,
-                options: row.get(3)?,
-                sort_order: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
+        .query_map(params![contact_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
         .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+    let mut map: HashMap<String, std::collections::HashSet<String>> = HashMap::new();
+    for (contact, field) in rows.filter_map(|r| r.ok()) {
+        map.entry(contact).or_default().insert(field);
+    }
+    Ok(map)
 }
 
 #[tauri::command]
-pub fn custom_field_create(db: State<DbState>, input: CreateCustomFieldInput) -> Result<CustomField, String> {
-    let id = format!("cf_{}", Uuid::new_v4().to_string().replace('-', "").chars().take(12).collect::<String>());
-    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+pub fn completeness(db: State<DbState>, contact_id: String) -> Result<Completeness, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let kind = if input.kind.is_empty() { "text" } else { input.kind.as_str() };
-    conn.execute(
-        "INSERT INTO custom_fields (id, name, kind, options, sort_order, created_at) VALUES (?1, ?2, ?3, ?4, 999, ?5)",
-        params![id, input.name, kind, input.options, now],
-    )
-    .map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT id, name, kind, options, sort_order, created_at FROM custom_fields WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let row = stmt
-        .query_row(params![id], |row| {
-            Ok(CustomField {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                kind: row.get(2)?,
-                options: row.get(3)?,
-                sort_order: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-    Ok(row)
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let contact = conn
+        .query_row(&sql, params![contact_id], row_to_contact)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Contact not found")?;
+    let required = required_contact_fields(conn)?;
+    let filled = filled_custom_fields(conn, Some(&contact_id))?
+        .remove(&contact_id)
+        .unwrap_or_default();
+    Ok(contact_completeness(&contact, &required, &filled))
 }
 
+/// Contacts (not in the trash) ranked by completeness; `ascending` (default true) puts the most
+/// incomplete first.
 #[tauri::command]
-pub fn contact_custom_values_get(db: State<DbState>, contact_id: String) -> Result<Vec<CustomValue>, String> {
+pub fn contacts_by_completeness(
+    db: State<DbState>,
+    ascending: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<ContactCompleteness>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let sql = "SELECT f.id, f.name, f.kind, f.options, v.value
-        FROM custom_fields f
-        LEFT JOIN contact_custom_values v ON v.field_id = f.id AND v.contact_id = ?1
-        ORDER BY f.sort_order, f.name";
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![contact_id], |row| {
-            Ok(CustomValue {
-                field_id: row.get(0)?,
-                field_name: row.get(1)?,
-                kind: row.get(2)?,
-                options: row.get(3)?,
-                value: row.get(4)?,
-            })
+    let required = required_contact_fields(conn)?;
+    let filled = filled_custom_fields(conn, None)?;
+    let empty = std::collections::HashSet::new();
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL ORDER BY c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_contact).map_err(|e| e.to_string())?;
+    let mut list: Vec<ContactCompleteness> = rows
+        .filter_map(|r| r.ok())
+        .map(|contact| {
+            let completeness =
+                contact_completeness(&contact, &required, filled.get(&contact.id).unwrap_or(&empty));
+            ContactCompleteness { contact, completeness }
         })
-        .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+        .collect();
+    // Stable sort keeps name order within equal scores.
+    if ascending.unwrap_or(true) {
+        list.sort_by_key(|c| c.completeness.score);
+    } else {
+        list.sort_by_key(|c| std::cmp::Reverse(c.completeness.score));
+    }
+    if let Some(limit) = limit {
+        list.truncate(limit);
+    }
+    Ok(list)
 }
 
+fn parse_timezone(value: &str) -> Result<chrono_tz::Tz, String> {
+    value
+        .trim()
+        .parse::<chrono_tz::Tz>()
+        .map_err(|_| format!("Geçersiz saat dilimi: {}", value))
+}
+
+/// Sets (or clears with None/empty) the contact's IANA timezone.
 #[tauri::command]
-pub fn contact_custom_values_set(
+pub fn contact_set_timezone(
     db: State<DbState>,
-    contact_id: String,
-    values: Vec<CustomValueInput>,
+    id: String,
+    timezone: Option<String>,
 ) -> Result<(), String> {
+    let timezone = match timezone.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(tz) => Some(parse_timezone(tz)?.name().to_string()),
+        None => None,
+    };
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    for v in values {
-        conn.execute(
-            "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, ?2, ?3)
-             ON CONFLICT(contact_id, field_id) DO UPDATE SET value = excluded.value",
-            params![contact_id, v.field_id, v.value],
+    let changed = conn
+        .execute(
+            "UPDATE contacts SET timezone = ?1, updated_at = ?2 WHERE id = ?3",
+            params![timezone, now, id],
         )
         .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Contact not found".to_string());
     }
     Ok(())
 }
 
+/// Sets city and/or country on many contacts at once. A None argument leaves that field as is;
+/// an empty string clears it. Returns the number of contacts updated.
 #[tauri::command]
-pub fn contact_ids_by_custom_value(
+pub fn contacts_bulk_set_location(
     db: State<DbState>,
-    field_id: String,
-    value: String,
-) -> Result<Vec<String>, String> {
+    ids: Vec<String>,
+    city: Option<String>,
+    country: Option<String>,
+) -> Result<u64, String> {
+    if ids.is_empty() {
+        return Err("En az bir kişi seçilmeli".to_string());
+    }
+    if city.is_none() && country.is_none() {
+        return Err("Şehir veya ülke belirtilmeli".to_string());
+    }
+    let clean = |v: &Option<String>| v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let mut sets: Vec<&str> = Vec::new();
+    if city.is_some() {
+        sets.push("city = ?1");
+    }
+    if country.is_some() {
+        sets.push("country = ?2");
+    }
+    let sql = format!(
+        "UPDATE contacts SET {}, updated_at = ?3 WHERE id = ?4 AND deleted_at IS NULL",
+        sets.join(", ")
+    );
+    let (city, country) = (clean(&city), clean(&country));
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut updated = 0u64;
+    {
+        let mut stmt = tx.prepare(&sql).map_err(|e| e.to_string())?;
+        for id in &ids {
+            updated += stmt
+                .execute(params![city, country, now, id])
+                .map_err(|e| e.to_string())? as u64;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+/// Moves many contacts to the trash in one transaction, like contact_delete. Returns how many
+/// were live and got trashed.
+#[tauri::command]
+pub fn contacts_delete_bulk(db: State<DbState>, ids: Vec<String>) -> Result<u64, String> {
+    if ids.is_empty() {
+        return Err("En az bir kişi seçilmeli".to_string());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut trashed = 0u64;
+    {
+        let mut stmt = tx
+            .prepare("UPDATE contacts SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+            .map_err(|e| e.to_string())?;
+        for id in &ids {
+            trashed += stmt.execute(params![now, id]).map_err(|e| e.to_string())? as u64;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(trashed)
+}
+
+/// Points many contacts at one company (None unlinks them) and refreshes the denormalized
+/// `company` text from the company name. Returns the number of contacts updated.
+fn set_company_bulk(
+    conn: &rusqlite::Connection,
+    ids: &[String],
+    company_id: Option<String>,
+    now: &str,
+) -> Result<u64, String> {
+    let mut company = None;
+    resolve_company_name(conn, &company_id, &mut company);
+    if company_id.is_some() && company.is_none() {
+        return Err("Company not found".to_string());
+    }
+    let mut stmt = conn
+        .prepare("UPDATE contacts SET company_id = ?1, company = ?2, updated_at = ?3 WHERE id = ?4 AND deleted_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let mut updated = 0u64;
+    for id in ids {
+        updated += stmt
+            .execute(params![company_id, company, now, id])
+            .map_err(|e| e.to_string())? as u64;
+    }
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn contacts_set_company_bulk(
+    db: State<DbState>,
+    ids: Vec<String>,
+    company_id: Option<String>,
+) -> Result<u64, String> {
+    if ids.is_empty() {
+        return Err("En az bir kişi seçilmeli".to_string());
+    }
+    let company_id = company_id.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let updated = set_company_bulk(&tx, &ids, company_id, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactLocalTime {
+    pub timezone: String,
+    /// RFC 3339 with the contact's offset, e.g. "2024-05-01T17:30:00+03:00".
+    pub local_time: String,
+    pub utc_offset_minutes: i32,
+}
+
+/// Contact's current wall-clock time; None when no timezone is set.
+#[tauri::command]
+pub fn contacts_local_time(
+    db: State<DbState>,
+    contact_id: String,
+) -> Result<Option<ContactLocalTime>, String> {
+    use chrono::Offset;
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let kind: Option<String> = conn
+    let timezone: Option<String> = conn
         .query_row(
-            "SELECT kind FROM custom_fields WHERE id = ?1",
-            params![field_id],
+            "SELECT timezone FROM contacts WHERE id = ?1",
+            params![contact_id],
             |row| row.get(0),
         )
         .optional()
-        .map_err(|e| e.to_string())?;
-    if kind.as_deref() == Some("multi_select") {
-        let mut stmt = conn
-            .prepare("SELECT contact_id, value FROM contact_custom_values WHERE field_id = ?1")
-            .map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map(params![field_id], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
-            })
-            .map_err(|e| e.to_string())?;
-        let mut ids = Vec::new();
-        for row in rows {
-            if let Ok((contact_id, v)) = row {
-                if value_contains_option(&v, &value) {
-                    ids.push(contact_id);
-                }
-            }
-        }
-        Ok(ids)
-    } else {
-        let mut stmt = conn
-            .prepare("SELECT contact_id FROM contact_custom_values WHERE field_id = ?1 AND value = ?2")
-            .map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map(params![field_id, value], |row| row.get::<_, String>(0))
-            .map_err(|e| e.to_string())?;
-        Ok(rows.filter_map(|r| r.ok()).collect())
-    }
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Contact not found".to_string())?;
+    let Some(timezone) = timezone.filter(|s| !s.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let tz = parse_timezone(&timezone)?;
+    let local = Utc::now().with_timezone(&tz);
+    Ok(Some(ContactLocalTime {
+        timezone: tz.name().to_string(),
+        local_time: local.format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+        utc_offset_minutes: local.offset().fix().local_minus_utc() / 60,
+    }))
 }
 
-// ---- Notes ----
+fn vcard_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Note {
-    pub id: String,
-    pub contact_id: String,
-    pub kind: String,
-    pub title: Option<String>,
-    pub body: String,
-    pub created_at: String,
-    pub updated_at: String,
+/// vCard 3.0 (CRLF); empty/missing fields are left out. Notes are omitted to keep QR payloads small.
+/// Primary email/phone are typed WORK, secondary ones HOME; a secondary equal to the primary is skipped.
+/// A contact without a name gets FN from its company, email or phone.
+fn contact_to_vcard(c: &Contact) -> String {
+    fn present(v: &Option<String>) -> Option<&str> {
+        v.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+    let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:3.0".to_string()];
+    lines.push(format!(
+        "N:{};{};;;",
+        vcard_escape(c.last_name.trim()),
+        vcard_escape(c.first_name.trim())
+    ));
+    let full_name = format!("{} {}", c.first_name.trim(), c.last_name.trim());
+    let display_name = Some(full_name.trim())
+        .filter(|s| !s.is_empty())
+        .or_else(|| present(&c.company))
+        .or_else(|| present(&c.email))
+        .or_else(|| present(&c.phone))
+        .unwrap_or("");
+    lines.push(format!("FN:{}", vcard_escape(display_name)));
+    if let Some(org) = present(&c.company) {
+        lines.push(format!("ORG:{}", vcard_escape(org)));
+    }
+    if let Some(title) = present(&c.title) {
+        lines.push(format!("TITLE:{}", vcard_escape(title)));
+    }
+    let email = present(&c.email);
+    let email_secondary =
+        present(&c.email_secondary).filter(|s| !email.is_some_and(|p| p.eq_ignore_ascii_case(s)));
+    for (kind, value) in [("WORK", email), ("HOME", email_secondary)] {
+        if let Some(value) = value {
+            lines.push(format!("EMAIL;TYPE=INTERNET,{}:{}", kind, vcard_escape(value)));
+        }
+    }
+    let phone = present(&c.phone);
+    let phone_key = normalize_phone(&c.phone);
+    let phone_secondary = present(&c.phone_secondary)
+        .filter(|s| phone_key.is_none() || phone_key != normalize_phone(&Some(s.to_string())));
+    for (kind, value) in [("WORK", phone), ("HOME", phone_secondary)] {
+        if let Some(value) = value {
+            lines.push(format!("TEL;TYPE={}:{}", kind, vcard_escape(value)));
+        }
+    }
+    let city = present(&c.city);
+    let country = present(&c.country);
+    if city.is_some() || country.is_some() {
+        lines.push(format!(
+            "ADR;TYPE=WORK:;;;{};;;{}",
+            vcard_escape(city.unwrap_or("")),
+            vcard_escape(country.unwrap_or(""))
+        ));
+    }
+    if let Some(url) = present(&c.website) {
+        lines.push(format!("URL:{}", vcard_escape(url)));
+    }
+    for (kind, url) in [("linkedin", present(&c.linkedin_url)), ("twitter", present(&c.twitter_url))] {
+        if let Some(url) = url {
+            lines.push(format!("X-SOCIALPROFILE;TYPE={}:{}", kind, vcard_escape(url)));
+        }
+    }
+    lines.push("END:VCARD".to_string());
+    let mut out = lines.join("\r\n");
+    out.push_str("\r\n");
+    out
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateNoteInput {
-    pub contact_id: String,
-    pub kind: Option<String>,
-    pub title: Option<String>,
-    pub body: String,
+/// vCard text for a contact, for the frontend to render as a QR code.
+#[tauri::command]
+pub fn contact_qr_vcard(db: State<DbState>, id: String) -> Result<String, String> {
+    let contact = contact_get(db, id)?.ok_or_else(|| "Contact not found".to_string())?;
+    Ok(contact_to_vcard(&contact))
 }
 
+/// One VCF holding a vCard per contact, for Apple/Google Contacts or a phone; save it with
+/// write_export_file. `contact_ids` None exports every contact not in the trash.
 #[tauri::command]
-pub fn note_list(db: State<DbState>, contact_id: String) -> Result<Vec<Note>, String> {
+pub fn export_vcard(db: State<DbState>, contact_ids: Option<Vec<String>>) -> Result<String, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let mut stmt = conn
-        .prepare("SELECT id, contact_id, kind, title, body, created_at, updated_at FROM notes WHERE contact_id = ?1 ORDER BY created_at DESC")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![contact_id], |row| {
-            Ok(Note {
-                id: row.get(0)?,
-                contact_id: row.get(1)?,
-                kind: row.get(2)?,
-                title: row.get(3)?,
-                body: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+    let contacts = export_contacts(conn, contact_ids)?;
+    Ok(contacts.iter().map(contact_to_vcard).collect())
 }
 
+/// Same VCF as export_vcard; empty `ids` exports every contact not in the trash.
 #[tauri::command]
-pub fn note_create(db: State<DbState>, input: CreateNoteInput) -> Result<Note, String> {
-    let id = Uuid::new_v4().to_string();
-    let kind = input.kind.unwrap_or_else(|| "note".to_string());
-    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    conn.execute(
-        "INSERT INTO notes (id, contact_id, kind, title, body, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![id, input.contact_id, kind, input.title, input.body, now, now],
-    )
-    .map_err(|e| e.to_string())?;
-    // Update contact last_touched_at
-    let _ = conn.execute(
-        "UPDATE contacts SET last_touched_at = ?1, updated_at = ?1 WHERE id = ?2",
-        params![now, input.contact_id],
-    );
-    let mut stmt = conn
-        .prepare("SELECT id, contact_id, kind, title, body, created_at, updated_at FROM notes WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let row = stmt
-        .query_row(params![id], |row| {
-            Ok(Note {
-                id: row.get(0)?,
-                contact_id: row.get(1)?,
-                kind: row.get(2)?,
-                title: row.get(3)?,
-                body: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-    Ok(row)
+pub fn contacts_export_vcards(db: State<DbState>, ids: Vec<String>) -> Result<String, String> {
+    export_vcard(db, Some(ids).filter(|ids| !ids.is_empty()))
 }
 
-// ---- Interactions (B1: Etkileşim logu) ----
+/// Contacts for an export, by name; None means every contact not in the trash.
+fn export_contacts(conn: &rusqlite::Connection, contact_ids: Option<Vec<String>>) -> Result<Vec<Contact>, String> {
+    let order = "ORDER BY c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE";
+    let mut contacts: Vec<Contact> = Vec::new();
+    if let Some(ids) = contact_ids {
+        // Chunked to stay under SQLite's bound-parameter limit.
+        for chunk in ids.chunks(500) {
+            let placeholders: Vec<String> = (1..=chunk.len()).map(|i| format!("?{}", i)).collect();
+            let sql = format!(
+                "{} WHERE c.id IN ({}) AND c.deleted_at IS NULL {}",
+                CONTACT_SELECT,
+                placeholders.join(", "),
+                order
+            );
+            let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(rusqlite::params_from_iter(chunk.iter()), row_to_contact)
+                .map_err(|e| e.to_string())?;
+            contacts.extend(rows.filter_map(|r| r.ok()));
+        }
+    } else {
+        let sql = format!("{} WHERE c.deleted_at IS NULL {}", CONTACT_SELECT, order);
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], row_to_contact).map_err(|e| e.to_string())?;
+        contacts.extend(rows.filter_map(|r| r.ok()));
+    }
+    Ok(contacts)
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Interaction {
-    pub id: String,
-    pub contact_id: String,
-    pub kind: String,
-    pub happened_at: String,
-    pub summary: Option<String>,
-    pub created_at: String,
+/// Columns of export_csv, named like the ImportRow fields so the file imports back as is.
+const CSV_EXPORT_COLUMNS: &[&str] = &[
+    "first_name",
+    "last_name",
+    "title",
+    "company",
+    "city",
+    "country",
+    "email",
+    "email_secondary",
+    "phone",
+    "phone_secondary",
+    "linkedin_url",
+    "twitter_url",
+    "website",
+];
+
+/// RFC 4180: quote when the value has a comma, quote or line break; quotes are doubled.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateInteractionInput {
-    pub contact_id: String,
-    pub kind: String,
-    pub happened_at: String,
-    pub summary: Option<String>,
+fn contacts_to_csv(contacts: &[Contact]) -> String {
+    let mut out = CSV_EXPORT_COLUMNS.join(",");
+    out.push_str("\r\n");
+    for c in contacts {
+        let values: [&str; 13] = [
+            &c.first_name,
+            &c.last_name,
+            c.title.as_deref().unwrap_or(""),
+            c.company.as_deref().unwrap_or(""),
+            c.city.as_deref().unwrap_or(""),
+            c.country.as_deref().unwrap_or(""),
+            c.email.as_deref().unwrap_or(""),
+            c.email_secondary.as_deref().unwrap_or(""),
+            c.phone.as_deref().unwrap_or(""),
+            c.phone_secondary.as_deref().unwrap_or(""),
+            c.linkedin_url.as_deref().unwrap_or(""),
+            c.twitter_url.as_deref().unwrap_or(""),
+            c.website.as_deref().unwrap_or(""),
+        ];
+        out.push_str(&values.iter().map(|v| csv_field(v)).collect::<Vec<_>>().join(","));
+        out.push_str("\r\n");
+    }
+    out
 }
 
+/// CSV with the import column set (header row first); save it with write_export_file.
+/// `contact_ids` None exports every contact not in the trash.
 #[tauri::command]
-pub fn interaction_list(db: State<DbState>, contact_id: String) -> Result<Vec<Interaction>, String> {
+pub fn export_csv(db: State<DbState>, contact_ids: Option<Vec<String>>) -> Result<String, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let mut stmt = conn
-        .prepare("SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions WHERE contact_id = ?1 ORDER BY happened_at DESC")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![contact_id], |row| {
-            Ok(Interaction {
-                id: row.get(0)?,
-                contact_id: row.get(1)?,
-                kind: row.get(2)?,
-                happened_at: row.get(3)?,
-                summary: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+    let contacts = export_contacts(conn, contact_ids)?;
+    Ok(contacts_to_csv(&contacts))
 }
 
+/// Moves the contact to the trash; notes, reminders etc. stay until contact_purge / trash_empty.
 #[tauri::command]
-pub fn interaction_create(db: State<DbState>, input: CreateInteractionInput) -> Result<Interaction, String> {
-    let id = Uuid::new_v4().to_string();
+pub fn contact_delete(db: State<DbState>, id: String) -> Result<(), String> {
     let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    conn.execute(
-        "INSERT INTO interactions (id, contact_id, kind, happened_at, summary, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![id, input.contact_id, input.kind, input.happened_at, input.summary, now],
-    )
-    .map_err(|e| e.to_string())?;
-    // B1.2: Last touched otomatik güncelle
-    let _ = conn.execute(
-        "UPDATE contacts SET last_touched_at = ?1, updated_at = ?2 WHERE id = ?3",
-        params![input.happened_at, now, input.contact_id],
-    );
-    let mut stmt = conn
-        .prepare("SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let row = stmt
-        .query_row(params![id], |row| {
-            Ok(Interaction {
-                id: row.get(0)?,
-                contact_id: row.get(1)?,
-                kind: row.get(2)?,
-                happened_at: row.get(3)?,
-                summary: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
+    let changed = conn
+        .execute(
+            "UPDATE contacts SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
         .map_err(|e| e.to_string())?;
-    Ok(row)
-}
-
-// ---- Reminders ----
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Reminder {
-    pub id: String,
-    pub contact_id: String,
-    pub note_id: Option<String>,
-    pub title: String,
-    pub due_at: String,
-    pub snooze_until: Option<String>,
-    pub recurring_days: Option<i64>,
-    pub completed_at: Option<String>,
-    pub created_at: String,
+    if changed == 0 {
+        return Err("Contact not found".to_string());
+    }
+    Ok(())
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateReminderInput {
-    pub contact_id: String,
-    pub note_id: Option<String>,
-    pub title: String,
-    pub due_at: String,
-    pub recurring_days: Option<i64>,
+#[tauri::command]
+pub fn contact_restore(db: State<DbState>, id: String) -> Result<Contact, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let conn = conn.as_ref().ok_or("DB not initialized")?;
+        let changed = conn
+            .execute(
+                "UPDATE contacts SET deleted_at = NULL, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NOT NULL",
+                params![now, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err("Kişi çöp kutusunda değil".to_string());
+        }
+    }
+    contact_get(db, id)?.ok_or_else(|| "Contact not found".to_string())
 }
 
 #[tauri::command]
-pub fn reminder_list(db: State<DbState>) -> Result<Vec<Reminder>, String> {
+pub fn contact_list_trashed(db: State<DbState>) -> Result<Vec<Contact>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let mut stmt = conn
-        .prepare("SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, created_at FROM reminders WHERE completed_at IS NULL ORDER BY due_at ASC")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([], |row| {
-            Ok(Reminder {
-                id: row.get(0)?,
-                contact_id: row.get(1)?,
-                note_id: row.get(2)?,
-                title: row.get(3)?,
-                due_at: row.get(4)?,
-                snooze_until: row.get(5)?,
-                recurring_days: row.get(6)?,
-                completed_at: row.get(7)?,
-                created_at: row.get(8)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
+    let sql = format!("{} WHERE c.deleted_at IS NOT NULL ORDER BY c.deleted_at DESC", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_contact).map_err(|e| e.to_string())?;
     Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
-#[tauri::command]
-pub fn reminder_create(db: State<DbState>, input: CreateReminderInput) -> Result<Reminder, String> {
-    let id = Uuid::new_v4().to_string();
-    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
+/// Hard delete: the contact and everything hanging off it. foreign_keys is off, so nothing
+/// cascades on its own. Returns the attachment files to remove once the caller has committed.
+fn purge_contact(conn: &rusqlite::Connection, id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT storage_path FROM attachments
+             WHERE (owner_type = 'contact' AND owner_id = ?1)
+                OR (owner_type = 'note' AND owner_id IN (SELECT id FROM notes WHERE contact_id = ?1))
+                OR (owner_type = 'interaction' AND owner_id IN (SELECT id FROM interactions WHERE contact_id = ?1))",
+        )
+        .map_err(|e| e.to_string())?;
+    let paths: Vec<String> = stmt
+        .query_map(params![id], |r| r.get(0))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
     conn.execute(
-        "INSERT INTO reminders (id, contact_id, note_id, title, due_at, recurring_days, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![
-            id,
-            input.contact_id,
-            input.note_id,
-            input.title,
-            input.due_at,
-            input.recurring_days,
-            now,
-        ],
+        "DELETE FROM attachments WHERE (owner_type = 'contact' AND owner_id = ?1)
+            OR (owner_type = 'note' AND owner_id IN (SELECT id FROM notes WHERE contact_id = ?1))
+            OR (owner_type = 'interaction' AND owner_id IN (SELECT id FROM interactions WHERE contact_id = ?1))",
+        params![id],
     )
     .map_err(|e| e.to_string())?;
-    // Update contact next_touch_at
-    let _ = conn.execute(
-        "UPDATE contacts SET next_touch_at = ?1, updated_at = ?1 WHERE id = ?2",
-        params![input.due_at, now, input.contact_id],
-    );
-    let mut stmt = conn
-        .prepare("SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, created_at FROM reminders WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let row = stmt
-        .query_row(params![id], |row| {
-            Ok(Reminder {
-                id: row.get(0)?,
-                contact_id: row.get(1)?,
-                note_id: row.get(2)?,
-                title: row.get(3)?,
-                due_at: row.get(4)?,
-                snooze_until: row.get(5)?,
-                recurring_days: row.get(6)?,
-                completed_at: row.get(7)?,
-                created_at: row.get(8)?,
-            })
-        })
+    for table in [
+        "notes",
+        "reminders",
+        "interactions",
+        "contact_custom_values",
+        "contact_tags",
+        "contact_snapshots",
+    ] {
+        conn.execute(&format!("DELETE FROM {} WHERE contact_id = ?1", table), params![id])
+            .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "DELETE FROM dedup_ignored_pairs WHERE a_id = ?1 OR b_id = ?1",
+        params![id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM contacts WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
-    Ok(row)
+    Ok(paths)
 }
 
-#[tauri::command]
-pub fn reminder_complete(db: State<DbState>, id: String) -> Result<(), String> {
-    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    // Get reminder for recurring and contact_id (D2.3: update contact last_touched_at / next_touch_at)
-    let row = conn
-        .query_row(
-            "SELECT contact_id, note_id, title, recurring_days FROM reminders WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, Option<String>>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, Option<i64>>(3)?,
-                ))
-            },
-        )
-        .optional()
-        .map_err(|e| e.to_string())?;
-
-    let contact_id: Option<String> = row.as_ref().map(|r| r.0.clone());
-
-    conn.execute("UPDATE reminders SET completed_at = ?1 WHERE id = ?2", params![now, id])
-        .map_err(|e| e.to_string())?;
-
-    // D2.3: Action tamamlandı → Last touched güncellenir
-    if let Some(ref cid) = contact_id {
-        conn.execute(
-            "UPDATE contacts SET last_touched_at = ?1, updated_at = ?1 WHERE id = ?2",
-            params![now, cid],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    // D1.4: "Her X günde bir" — create next reminder if recurring_days set
-    let next_due_at: Option<String> = if let Some((contact_id, note_id, title, Some(recurring_days))) = row {
-        if recurring_days > 0 {
-            let next_id = Uuid::new_v4().to_string();
-            let mut due = Utc::now();
-            due = due + chrono::Duration::days(recurring_days);
-            let due_at = due.format("%Y-%m-%dT%H:%M:%SZ").to_string();
-            let _ = conn.execute(
-                "INSERT INTO reminders (id, contact_id, note_id, title, due_at, recurring_days, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                params![next_id, contact_id, note_id, title, due_at, recurring_days, now],
-            );
-            Some(due_at)
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    // D2.3: next action temizlenir veya yeni tarih (recurring ise next_touch_at = yeni due_at)
-    if let Some(ref cid) = contact_id {
-        let next_touch: Option<&str> = next_due_at.as_deref();
-        conn.execute(
-            "UPDATE contacts SET next_touch_at = ?1, updated_at = ?2 WHERE id = ?3",
-            params![next_touch, now, cid],
-        )
-        .map_err(|e| e.to_string())?;
+/// Best-effort removal of attachment blobs; only called after the rows pointing at them are
+/// committed, so a rolled-back delete never leaves rows without files.
+fn remove_attachment_files(paths: &[String]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
     }
-
-    Ok(())
 }
 
+/// Permanently deletes a contact (trashed or not). Cannot be undone.
 #[tauri::command]
-pub fn reminder_snooze(db: State<DbState>, id: String, until: String) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    conn.execute("UPDATE reminders SET snooze_until = ?1 WHERE id = ?2", params![until, id])
-        .map_err(|e| e.to_string())?;
+pub fn contact_purge(db: State<DbState>, id: String) -> Result<(), String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let files = purge_contact(&tx, &id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    remove_attachment_files(&files);
     Ok(())
 }
 
-// ---- Attachments (A6) ----
+#[derive(Debug, Deserialize)]
+pub struct ClearHistoryInclude {
+    #[serde(default)]
+    pub notes: bool,
+    #[serde(default)]
+    pub interactions: bool,
+    #[serde(default)]
+    pub reminders: bool,
+}
 
-#[tauri::command]
-pub fn attachments_dir_get(db: State<DbState>) -> Result<String, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    setting_get(conn, "attachments_dir")?
-        .ok_or_else(|| "Attachments dir not set".to_string())
+#[derive(Debug, Serialize)]
+pub struct ClearHistoryResult {
+    pub notes: usize,
+    pub interactions: usize,
+    pub reminders: usize,
 }
 
+/// Deletes the selected notes/interactions/reminders of a contact but keeps the contact, then
+/// recomputes last_touched_at / next_touch_at from what is left. Their attachments are deleted too.
 #[tauri::command]
-pub fn attachments_dir_set(db: State<DbState>, path: String) -> Result<(), String> {
-    let path = path.trim();
-    if path.is_empty() {
-        return Err("Path is empty".to_string());
-    }
-    let dir = PathBuf::from(path);
-    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    setting_set(conn, "attachments_dir", path)
+pub fn contact_clear_history(
+    db: State<DbState>,
+    contact_id: String,
+    include: ClearHistoryInclude,
+) -> Result<ClearHistoryResult, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let (result, files) = clear_contact_history(&tx, &contact_id, &include, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    remove_attachment_files(&files);
+    Ok(result)
 }
 
-// ---- F3 Backup (F3.1 auto versioned, F3.2 user folder) ----
-
-const BACKUP_KEEP_COUNT: usize = 7;
-const BACKUP_PREFIX: &str = "vault-backup-";
-const BACKUP_SUFFIX: &str = ".encrypted";
-
-/// F3.1: Create versioned backup; F3.2: also copy to user backup_dir if set. Call after flush on window close.
-pub fn run_backup(
-    app: &tauri::AppHandle,
+/// Also returns the attachment files to remove after commit.
+fn clear_contact_history(
     conn: &rusqlite::Connection,
-    encrypted_path: &Path,
-) -> Result<(), String> {
-    let app_data = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    std::fs::create_dir_all(&app_data).map_err(|e| e.to_string())?;
-    let backups_dir = app_data.join("backups");
-    std::fs::create_dir_all(&backups_dir).map_err(|e| e.to_string())?;
-
-    let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
-    let name = format!("{}{}{}", BACKUP_PREFIX, timestamp, BACKUP_SUFFIX);
-    let dest = backups_dir.join(&name);
-    std::fs::copy(encrypted_path, &dest).map_err(|e| e.to_string())?;
-
-    prune_backups_in_dir(&backups_dir, BACKUP_KEEP_COUNT)?;
-
-    if let Some(extra) = setting_get(conn, "backup_dir")? {
-        let extra_path = PathBuf::from(extra.trim());
-        if !extra_path.as_os_str().is_empty() {
-            let _ = std::fs::create_dir_all(&extra_path);
-            let dest_extra = extra_path.join(&name);
-            let _ = std::fs::copy(encrypted_path, &dest_extra);
-            prune_backups_in_dir(&extra_path, BACKUP_KEEP_COUNT).ok();
+    contact_id: &str,
+    include: &ClearHistoryInclude,
+    now: &str,
+) -> Result<(ClearHistoryResult, Vec<String>), String> {
+    let exists: bool = conn
+        .query_row("SELECT 1 FROM contacts WHERE id = ?1", params![contact_id], |_| Ok(true))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if !exists {
+        return Err("Contact not found".to_string());
+    }
+    let mut result = ClearHistoryResult {
+        notes: 0,
+        interactions: 0,
+        reminders: 0,
+    };
+    let mut files = Vec::new();
+    if include.notes {
+        let ids: Vec<String> = {
+            let mut stmt = conn
+                .prepare("SELECT id FROM notes WHERE contact_id = ?1")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![contact_id], |r| r.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+        for id in ids.iter() {
+            files.extend(delete_note(conn, id)?);
         }
+        result.notes = ids.len();
     }
-    // G1.2: Write encrypted DB to sync folder (fixed name; format documented).
-    if let Some(sync_dir) = setting_get(conn, "sync_folder")? {
-        let sync_path = PathBuf::from(sync_dir.trim());
-        if !sync_path.as_os_str().is_empty() {
-            let _ = std::fs::create_dir_all(&sync_path);
-            let dest_sync = sync_path.join(VAULT_SYNC_NAME);
-            let _ = std::fs::copy(encrypted_path, &dest_sync);
+    if include.interactions {
+        let ids: Vec<String> = {
+            let mut stmt = conn
+                .prepare("SELECT id FROM interactions WHERE contact_id = ?1")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![contact_id], |r| r.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+        for id in ids.iter() {
+            files.extend(delete_owner_attachments(conn, "interaction", id)?);
         }
+        result.interactions = conn
+            .execute("DELETE FROM interactions WHERE contact_id = ?1", params![contact_id])
+            .map_err(|e| e.to_string())?;
     }
-    Ok(())
+    if include.reminders {
+        result.reminders = conn
+            .execute("DELETE FROM reminders WHERE contact_id = ?1", params![contact_id])
+            .map_err(|e| e.to_string())?;
+    }
+    refresh_last_touched(conn, contact_id, now)?;
+    refresh_next_touch(conn, contact_id, now)?;
+    Ok((result, files))
 }
 
-fn prune_backups_in_dir(dir: &Path, keep: usize) -> Result<(), String> {
-    let mut entries: Vec<_> = std::fs::read_dir(dir)
-        .map_err(|e| e.to_string())?
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.path()
-                .file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.starts_with(BACKUP_PREFIX) && n.ends_with(BACKUP_SUFFIX))
-                .unwrap_or(false)
-        })
-        .collect();
-    entries.sort_by(|a, b| {
-        b.path()
-            .metadata()
-            .and_then(|m| m.modified())
-            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-            .cmp(
-                &a.path()
-                    .metadata()
-                    .and_then(|m| m.modified())
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
+/// Purges contacts trashed at least `older_than_days` ago (None or 0 = the whole trash); returns the count.
+#[tauri::command]
+pub fn trash_empty(db: State<DbState>, older_than_days: Option<i64>) -> Result<usize, String> {
+    let days = older_than_days.unwrap_or(0).max(0);
+    let modifier = format!("-{} days", days);
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let ids: Vec<String> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id FROM contacts
+                 WHERE deleted_at IS NOT NULL AND datetime(deleted_at) <= datetime('now', ?1)",
             )
-    });
-    for e in entries.into_iter().skip(keep) {
-        let _ = std::fs::remove_file(e.path());
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![modifier], |r| r.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    let mut files = Vec::new();
+    for id in &ids {
+        files.extend(purge_contact(&tx, id)?);
     }
-    Ok(())
+    tx.commit().map_err(|e| e.to_string())?;
+    remove_attachment_files(&files);
+    Ok(ids.len())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarContact {
+    pub contact: Contact,
+    pub shared_tags: i64,
+    pub same_company: bool,
 }
 
+/// "People like this one": other contacts ranked by shared tag count, then same company.
 #[tauri::command]
-pub fn backup_dir_get(db: State<DbState>) -> Result<String, String> {
+pub fn contacts_similar_to(
+    db: State<DbState>,
+    contact_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<SimilarContact>, String> {
+    let limit = limit.unwrap_or(10).clamp(1, 100);
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    Ok(setting_get(conn, "backup_dir")?.unwrap_or_default())
+    let mut stmt = conn
+        .prepare(
+            "SELECT o.id,
+                (SELECT COUNT(*) FROM contact_tags mine
+                 JOIN contact_tags other ON other.tag_id = mine.tag_id
+                 WHERE mine.contact_id = me.id AND other.contact_id = o.id) AS shared,
+                COALESCE(me.company_id = o.company_id, 0) AS same_company
+             FROM contacts me JOIN contacts o ON o.id != me.id AND o.deleted_at IS NULL
+             WHERE me.id = ?1
+               AND (shared > 0 OR same_company)
+             ORDER BY shared DESC, same_company DESC, o.updated_at DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let ranked: Vec<(String, i64, bool)> = stmt
+        .query_map(params![contact_id, limit], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut out = Vec::with_capacity(ranked.len());
+    for (id, shared_tags, same_company) in ranked {
+        if let Some(contact) = stmt
+            .query_row(params![id], row_to_contact)
+            .optional()
+            .map_err(|e| e.to_string())?
+        {
+            out.push(SimilarContact {
+                contact,
+                shared_tags,
+                same_company,
+            });
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactNoteVolume {
+    pub contact: Contact,
+    pub note_count: i64,
+    pub total_chars: i64,
+    pub latest_note_snippet: Option<String>,
 }
 
+/// Handoff helper: best-documented contacts first (total note length, then note count).
 #[tauri::command]
-pub fn backup_dir_set(db: State<DbState>, path: String) -> Result<(), String> {
+pub fn contacts_by_note_volume(
+    db: State<DbState>,
+    limit: Option<i64>,
+) -> Result<Vec<ContactNoteVolume>, String> {
+    let limit = limit.unwrap_or(20).clamp(1, 200);
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    setting_set(conn, "backup_dir", path.trim())
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.contact_id, COUNT(*) AS cnt, COALESCE(SUM(LENGTH(n.body)), 0) AS total,
+                (SELECT body FROM notes latest WHERE latest.contact_id = n.contact_id
+                 ORDER BY latest.created_at DESC LIMIT 1)
+             FROM notes n
+             JOIN contacts c ON c.id = n.contact_id AND c.deleted_at IS NULL
+             GROUP BY n.contact_id
+             ORDER BY total DESC, cnt DESC
+             LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let ranked: Vec<(String, i64, i64, Option<String>)> = stmt
+        .query_map(params![limit], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut out = Vec::with_capacity(ranked.len());
+    for (id, note_count, total_chars, latest) in ranked {
+        if let Some(contact) = stmt
+            .query_row(params![id], row_to_contact)
+            .optional()
+            .map_err(|e| e.to_string())?
+        {
+            let latest_note_snippet = latest.map(|body| text_snippet(&body, 120));
+            out.push(ContactNoteVolume {
+                contact,
+                note_count,
+                total_chars,
+                latest_note_snippet,
+            });
+        }
+    }
+    Ok(out)
 }
 
-// ---- G1 Folder Sync (G1.1 folder, G1.2 write to sync, G1.3 open from sync) ----
-
+/// Caller-ID style reverse lookup: contacts whose phone or secondary phone contains the given digits,
+/// whatever the formatting on either side (e.g. "(212) 555-").
 #[tauri::command]
-pub fn sync_folder_get(db: State<DbState>) -> Result<String, String> {
+pub fn contacts_find_by_phone_partial(db: State<DbState>, digits: String) -> Result<Vec<Contact>, String> {
+    let digits: String = digits.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 3 {
+        return Ok(vec![]);
+    }
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    Ok(setting_get(conn, "sync_folder")?.unwrap_or_default())
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL
+           AND (c.phone_normalized LIKE ?1 OR c.phone_secondary_normalized LIKE ?1)
+         ORDER BY c.updated_at DESC LIMIT 50",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![format!("%{}%", digits)], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
+/// Exact phone match after E.164 normalization (see phone_e164_digits), ignoring formatting and
+/// "+"/"00" prefixes; national numbers use the contact's country or `default_country_code`.
 #[tauri::command]
-pub fn sync_folder_set(db: State<DbState>, path: String) -> Result<(), String> {
+pub fn contacts_find_by_phone(db: State<DbState>, phone: String) -> Result<Vec<Contact>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    setting_set(conn, "sync_folder", path.trim())
+    contacts_with_phone(conn, &phone)
 }
 
-/// G1.3: Copy vault-sync.encrypted from folder to app_data, derive key from passphrase, store key. Call encryption_setup_open_db after.
-#[tauri::command]
-pub fn open_from_sync_folder(app: tauri::AppHandle, folder_path: String, passphrase: String) -> Result<(), String> {
-    crate::db::open_from_sync_folder(&app, &folder_path, &passphrase)
+/// Live contacts whose phone or secondary phone has the same E.164 key as `phone`.
+fn contacts_with_phone(conn: &rusqlite::Connection, phone: &str) -> Result<Vec<Contact>, String> {
+    let default_code = default_country_code(conn)?;
+    let Some(key) = phone_e164_digits(phone, default_code.as_deref()) else { return Ok(vec![]); };
+    // The subscriber number's last digits survive every format, so they narrow the scan.
+    let tail = &key[key.len().saturating_sub(7)..];
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL
+           AND (c.phone_normalized LIKE ?1 OR c.phone_secondary_normalized LIKE ?1)
+         ORDER BY c.updated_at DESC",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![format!("%{}", tail)], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .filter_map(|r| r.ok())
+        .filter(|c| contact_phone_keys(c, default_code.as_deref()).contains(&key))
+        .collect())
+}
+
+/// Active contacts whose email or secondary email equals `email` after normalize_email.
+fn contacts_with_email(conn: &rusqlite::Connection, email: &str) -> Result<Vec<Contact>, String> {
+    let Some(key) = normalize_email(&Some(email.to_string())) else { return Ok(vec![]); };
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL
+           AND (LOWER(TRIM(c.email)) = ?1 OR LOWER(TRIM(c.email_secondary)) = ?1)
+         ORDER BY c.updated_at DESC",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![key], row_to_contact).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
+/// Exact, case-insensitive email lookup over both email columns (indexed).
 #[tauri::command]
-pub fn attachment_list(
-    db: State<DbState>,
-    owner_type: String,
-    owner_id: String,
-) -> Result<Vec<Attachment>, String> {
+pub fn contact_by_email(db: State<DbState>, email: String) -> Result<Vec<Contact>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, owner_type, owner_id, file_name, mime, size, storage_path, created_at
-             FROM attachments WHERE owner_type = ?1 AND owner_id = ?2 ORDER BY created_at DESC",
-        )
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![owner_type, owner_id], |row| {
-            Ok(Attachment {
-                id: row.get(0)?,
-                owner_type: row.get(1)?,
-                owner_id: row.get(2)?,
-                file_name: row.get(3)?,
-                mime: row.get(4)?,
-                size: row.get(5)?,
-                storage_path: row.get(6)?,
-                created_at: row.get(7)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+    contacts_with_email(conn, &email)
 }
 
+/// Incoming-call lookup over both phone columns; same matching as contacts_find_by_phone, so
+/// "+90 532 …" finds a contact stored as "0532 …" (country or default_country_code).
 #[tauri::command]
-pub fn attachment_add(db: State<DbState>, input: AttachmentCreateInput) -> Result<Attachment, String> {
-    if input.owner_type != "contact" && input.owner_type != "company" {
-        return Err("Invalid owner_type".to_string());
-    }
-    let file_name = sanitize_file_name(&input.file_name);
-    if !is_allowed_attachment(&file_name) {
-        return Err("Desteklenmeyen dosya formatı".to_string());
-    }
-    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    let key = attachments_key(conn)?;
-    let dir = attachments_dir(conn)?;
-    let id = Uuid::new_v4().to_string();
-    let encrypted = encrypt_bytes(&key, &input.bytes)?;
-    let path = dir.join(format!("{}.bin", id));
-    std::fs::write(&path, encrypted).map_err(|e| e.to_string())?;
-    let size = input.bytes.len() as i64;
-    conn.execute(
-        "INSERT INTO attachments (id, owner_type, owner_id, file_name, mime, size, storage_path, encrypted, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8)",
-        params![
-            id,
-            input.owner_type,
-            input.owner_id,
-            file_name,
-            input.mime,
-            size,
-            path.to_string_lossy().to_string(),
-            now,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(Attachment {
-        id,
-        owner_type: input.owner_type,
-        owner_id: input.owner_id,
-        file_name,
-        mime: input.mime,
-        size: Some(size),
-        storage_path: path.to_string_lossy().to_string(),
-        created_at: now,
-    })
+pub fn contact_by_phone(db: State<DbState>, phone: String) -> Result<Vec<Contact>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    contacts_with_phone(conn, &phone)
 }
 
 #[tauri::command]
-pub fn attachment_delete(db: State<DbState>, id: String) -> Result<(), String> {
+pub fn default_country_code_get(db: State<DbState>) -> Result<Option<String>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let row: Option<(String,)> = conn
-        .query_row(
-            "SELECT storage_path FROM attachments WHERE id = ?1",
-            params![id],
-            |r| Ok((r.get(0)?,)),
-        )
-        .optional()
-        .map_err(|e| e.to_string())?;
-    if let Some((path,)) = row {
-        let _ = std::fs::remove_file(path);
+    default_country_code(conn)
+}
+
+/// Calling code such as "90" or "+1" for national numbers; empty clears it.
+#[tauri::command]
+pub fn default_country_code_set(db: State<DbState>, code: String) -> Result<(), String> {
+    let code = code.trim().trim_start_matches('+');
+    if !code.is_empty() && (code.len() > 3 || !code.chars().all(|c| c.is_ascii_digit())) {
+        return Err("Geçersiz ülke kodu".to_string());
     }
-    conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, "default_country_code", code)
+}
+
+#[derive(Debug, Serialize)]
+pub struct CanonicalizeResult {
+    pub scanned: u64,
+    pub changed: u64,
 }
 
+/// A5.1: One-shot cleanup — lowercase/trim emails and rewrite phones to canonical form. Idempotent.
 #[tauri::command]
-pub fn attachment_open(db: State<DbState>, id: String) -> Result<String, String> {
+pub fn contacts_canonicalize(db: State<DbState>) -> Result<CanonicalizeResult, String> {
     let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-    let row: Option<(String, String)> = conn
-        .query_row(
-            "SELECT storage_path, file_name FROM attachments WHERE id = ?1",
-            params![id],
-            |r| Ok((r.get(0)?, r.get(1)?)),
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let rows: Vec<[Option<String>; 6]> = {
+        let mut stmt = tx
+            .prepare("SELECT id, country, email, email_secondary, phone, phone_secondary FROM contacts")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok([r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?]))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    let mut result = CanonicalizeResult {
+        scanned: rows.len() as u64,
+        changed: 0,
+    };
+    for [id, country, email, email_secondary, phone, phone_secondary] in rows {
+        let email_new = email.as_ref().map(|e| e.trim().to_lowercase());
+        let email_secondary_new = email_secondary.as_ref().map(|e| e.trim().to_lowercase());
+        let phone_new = phone
+            .as_ref()
+            .map(|p| canonical_phone(p, &country).unwrap_or_else(|| p.clone()));
+        let phone_secondary_new = phone_secondary
+            .as_ref()
+            .map(|p| canonical_phone(p, &country).unwrap_or_else(|| p.clone()));
+        if email_new == email
+            && email_secondary_new == email_secondary
+            && phone_new == phone
+            && phone_secondary_new == phone_secondary
+        {
+            continue;
+        }
+        tx.execute(
+            "UPDATE contacts SET email = ?1, email_secondary = ?2, phone = ?3, phone_secondary = ?4,
+                phone_normalized = ?5, phone_secondary_normalized = ?6 WHERE id = ?7",
+            params![
+                email_new,
+                email_secondary_new,
+                phone_new,
+                phone_secondary_new,
+                normalize_phone(&phone_new),
+                normalize_phone(&phone_secondary_new),
+                id,
+            ],
         )
-        .optional()
         .map_err(|e| e.to_string())?;
-    let (path, file_name) = row.ok_or_else(|| "Attachment not found".to_string())?;
-    let encrypted = std::fs::read(path).map_err(|e| e.to_string())?;
-    let key = attachments_key(conn)?;
-    let decrypted = decrypt_bytes(&key, &encrypted)?;
-    let app_data = setting_get(conn, "app_data_dir")?
-        .ok_or_else(|| "app_data_dir not set".to_string())?;
-    let tmp_dir = Path::new(&app_data).join("tmp");
-    std::fs::create_dir_all(&tmp_dir).map_err(|e| e.to_string())?;
-    let safe_name = sanitize_file_name(&file_name);
-    let out_path = tmp_dir.join(format!("{}_{}", id, safe_name));
-    std::fs::write(&out_path, decrypted).map_err(|e| e.to_string())?;
-    Ok(out_path.to_string_lossy().to_string())
+        result.changed += 1;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
 }
 
-// ---- Import (CSV) ----
-// Frontend sends parsed rows; we create contacts. Dedup/merge can be added later.
-
-#[derive(Debug, Deserialize)]
-pub struct ImportRow {
-    pub first_name: Option<String>,
-    pub last_name: Option<String>,
-    pub title: Option<String>,
-    pub company: Option<String>,
-    pub city: Option<String>,
-    pub country: Option<String>,
-    pub email: Option<String>,
-    pub phone: Option<String>,
-    pub linkedin_url: Option<String>,
-    pub website: Option<String>,
+/// Sets the cached `company` name to the linked company's current name wherever it differs.
+fn refresh_company_names(conn: &rusqlite::Connection, now: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE contacts SET
+            company = (SELECT co.name FROM companies co WHERE co.id = contacts.company_id),
+            updated_at = ?1
+         WHERE company_id IS NOT NULL
+           AND EXISTS (SELECT 1 FROM companies co
+                       WHERE co.id = contacts.company_id AND co.name != COALESCE(contacts.company, ''))",
+        params![now],
+    )
 }
 
+/// Bulk version of resolve_company_name (e.g. after imports or company renames); returns contacts updated.
 #[tauri::command]
-pub fn import_contacts(db: State<DbState>, rows: Vec<ImportRow>) -> Result<u64, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
+pub fn contacts_resolve_company_names(db: State<DbState>) -> Result<usize, String> {
     let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    let mut count = 0u64;
-    for row in rows {
-        let first = row.first_name.unwrap_or_default();
-        let last = row.last_name.unwrap_or_default();
-        if first.is_empty() && last.is_empty() {
-            continue;
-        }
-        let id = Uuid::new_v4().to_string();
-        conn.execute(
-            "INSERT INTO contacts (id, first_name, last_name, title, company, city, country, email, phone, linkedin_url, website, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-            params![
-                id,
-                first,
-                last,
-                row.title,
-                row.company,
-                row.city,
-                row.country,
-                row.email,
-                row.phone,
-                row.linkedin_url,
-                row.website,
-                now,
-                now,
-            ],
-        )
-        .map_err(|e| e.to_string())?;
-        count += 1;
-    }
-    Ok(count)
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let updated = refresh_company_names(&tx, &now).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
 }
 
-// ---- Search (FTS) ----
-
 #[tauri::command]
-pub fn search_contacts(db: State<DbState>, q: String) -> Result<Vec<String>, String> {
-    if q.trim().is_empty() {
-        return Ok(vec![]);
-    }
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    // FTS5: content table is 'contacts', so we query contacts_fts and join to get id
-    let query = format!("{}*", q.trim().replace(' ', "* "));
+pub fn company_list(db: State<DbState>) -> Result<Vec<Company>, String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
     let mut stmt = conn
-        .prepare("SELECT rowid FROM contacts_fts WHERE contacts_fts MATCH ?1 LIMIT 50")
+        .prepare(&format!("{} ORDER BY is_pinned DESC, name", COMPANY_SELECT))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![query], |row| row.get::<_, i64>(0))
+        .query_map([], row_to_company)
         .map_err(|e| e.to_string())?;
-    let mut ids = Vec::new();
-    for row in rows {
-        if let Ok(rowid) = row {
-            let mut get_id = conn
-                .prepare("SELECT id FROM contacts WHERE rowid = ?1")
-                .map_err(|e| e.to_string())?;
-            if let Ok(Some(id)) = get_id.query_row(params![rowid], |r| r.get::<_, String>(0)).optional() {
-                ids.push(id);
-            }
-        }
-    }
-    Ok(ids)
-}
-
-// C2.1 — Global hızlı arama: kişi, şirket, not içeriği
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GlobalSearchNoteHit {
-    pub note_id: String,
-    pub contact_id: String,
-    pub contact_name: String,
-    pub body_snippet: String,
-    pub created_at: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GlobalSearchResult {
-    pub contacts: Vec<Contact>,
-    pub companies: Vec<Company>,
-    pub note_hits: Vec<GlobalSearchNoteHit>,
+    Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
 #[tauri::command]
-pub fn global_search(db: State<DbState>, q: String) -> Result<GlobalSearchResult, String> {
-    let q_trim = q.trim();
-    if q_trim.is_empty() {
-        return Ok(GlobalSearchResult {
-            contacts: vec![],
-            companies: vec![],
-            note_hits: vec![],
-        });
+pub fn company_get(db: State<DbState>, id: String) -> Result<Option<Company>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(&format!("{} WHERE id = ?1", COMPANY_SELECT))
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params![id]).map_err(|e| e.to_string())?;
+    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let company = row_to_company(&row).map_err(|e| e.to_string())?;
+        return Ok(Some(company));
     }
-    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
-
-    // Contacts: use FTS
-    let contact_ids: Vec<String> = {
-        let query = format!("{}*", q_trim.replace(' ', "* "));
-        let mut stmt = conn
-            .prepare("SELECT rowid FROM contacts_fts WHERE contacts_fts MATCH ?1 LIMIT 20")
-            .map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map(params![query], |row| row.get::<_, i64>(0))
-            .map_err(|e| e.to_string())?;
-        let mut ids = Vec::new();
-        for row in rows {
-            if let Ok(rowid) = row {
-                if let Ok(Some(id)) =
-                    conn.query_row("SELECT id FROM contacts WHERE rowid = ?1", params![rowid], |r| r.get::<_, String>(0)).optional()
-                {
-                    ids.push(id);
-                }
-            }
-        }
-        ids
-    };
-    let contacts: Vec<Contact> = if contact_ids.is_empty() {
-        vec![]
-    } else {
-        let placeholders = contact_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        let sql = format!(
-            "SELECT c.id, c.first_name, c.last_name, c.title,
-                COALESCE(co.name, c.company), c.company_id, c.city, c.country,
-                c.email, c.email_secondary, c.phone, c.phone_secondary,
-                c.linkedin_url, c.twitter_url, c.website, c.notes,
-                c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at
-                FROM contacts c LEFT JOIN companies co ON c.company_id = co.id
-                WHERE c.id IN ({})",
-            placeholders
-        );
-        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map(rusqlite::params_from_iter(contact_ids.iter()), row_to_contact)
-            .map_err(|e| e.to_string())?;
-        rows.filter_map(|r| r.ok()).collect()
-    };
-
-    // Companies: LIKE name
-    let companies: Vec<Company> = {
-        let pattern = format!("%{}%", q_trim.replace('%', "\\%").replace('_', "\\_"));
-        let mut stmt = conn
-            .prepare("SELECT id, name, domain, industry, notes, created_at, updated_at FROM companies WHERE name LIKE ?1 ESCAPE '\\' LIMIT 20")
-            .map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map(params![pattern], |row| {
-                Ok(Company {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    domain: row.get(2)?,
-                    industry: row.get(3)?,
-                    notes: row.get(4)?,
-                    created_at: row.get(5)?,
-                    updated_at: row.get(6)?,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-        rows.filter_map(|r| r.ok()).collect()
-    };
+    Ok(None)
+}
 
-    // Notes: LIKE body, snippet
-    let note_hits: Vec<GlobalSearchNoteHit> = {
-        let pattern = format!("%{}%", q_trim.replace('%', "\\%").replace('_', "\\_"));
-        let mut stmt = conn
-            .prepare(
-                "SELECT n.id, n.contact_id, n.body, n.created_at, c.first_name, c.last_name
-                 FROM notes n JOIN contacts c ON n.contact_id = c.id
-                 WHERE n.body LIKE ?1 ESCAPE '\\'
-                 ORDER BY n.created_at DESC LIMIT 20",
-            )
-            .map_err(|e| e.to_string())?;
-        let rows = stmt
-            .query_map(params![pattern], |row| {
-                let note_id: String = row.get(0)?;
-                let contact_id: String = row.get(1)?;
-                let body: String = row.get(2)?;
-                let created_at: String = row.get(3)?;
-                let first_name: String = row.get(4)?;
-                let last_name: String = row.get(5)?;
-                let snippet_len = 120;
-                let body_snippet = if body.len() <= snippet_len {
-                    body
-                } else {
-                    format!("{}…", body.chars().take(snippet_len).collect::<String>())
-                };
-                Ok(GlobalSearchNoteHit {
-                    note_id,
-                    contact_id,
-                    contact_name: format!("{} {}", first_name, last_name),
-                    body_snippet,
-                    created_at,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-        rows.filter_map(|r| r.ok()).collect()
-    };
+#[derive(Debug, Serialize)]
+pub struct CompanySuggestion {
+    pub company: Company,
+    pub distance: usize,
+}
 
-    Ok(GlobalSearchResult {
-        contacts,
-        companies,
-        note_hits,
-    })
+fn company_name_key(name: &str) -> String {
+    name.trim().to_lowercase().split_whitespace().collect::<Vec<&str>>().join(" ")
 }
 
-// C2.3 — Notlarda #etiket: bu hashtag geçen notları olan contact_id listesi
+/// "Did you mean ...?" for the company field: existing companies within `max_distance` edits, closest first.
 #[tauri::command]
-pub fn contact_ids_with_hashtag(db: State<DbState>, hashtag: String) -> Result<Vec<String>, String> {
-    let tag = hashtag.trim();
-    if tag.is_empty() {
+pub fn company_did_you_mean(
+    db: State<DbState>,
+    name: String,
+    max_distance: Option<usize>,
+) -> Result<Vec<CompanySuggestion>, String> {
+    let key = company_name_key(&name);
+    if key.is_empty() {
         return Ok(vec![]);
     }
+    let max_distance = max_distance.unwrap_or(3);
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let pattern = format!("%#{}%", tag.replace('%', "\\%").replace('_', "\\_"));
     let mut stmt = conn
-        .prepare(
-            "SELECT DISTINCT contact_id FROM notes WHERE body LIKE ?1 ESCAPE '\\'",
-        )
+        .prepare(&format!("{} ORDER BY name", COMPANY_SELECT))
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![pattern], |row| row.get::<_, String>(0))
+        .query_map([], row_to_company)
         .map_err(|e| e.to_string())?;
-    Ok(rows.filter_map(|r| r.ok()).collect())
+    let mut out: Vec<CompanySuggestion> = rows
+        .filter_map(|r| r.ok())
+        .filter_map(|company| {
+            let distance = levenshtein(&key, &company_name_key(&company.name));
+            (distance <= max_distance).then_some(CompanySuggestion { company, distance })
+        })
+        .collect();
+    out.sort_by_key(|s| s.distance);
+    Ok(out)
 }
 
 #[tauri::command]
-pub fn dedup_candidates(db: State<DbState>) -> Result<Vec<DedupCandidate>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = conn.as_ref().ok_or("DB not initialized")?;
-    let sql = "SELECT c.id, c.first_name, c.last_name, c.title,
-        COALESCE(co.name, c.company), c.company_id, c.city, c.country,
-        c.email, c.email_secondary, c.phone, c.phone_secondary,
-        c.linkedin_url, c.twitter_url, c.website, c.notes,
-        c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at
-        FROM contacts c LEFT JOIN companies co ON c.company_id = co.id
-        ORDER BY c.updated_at DESC";
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([], row_to_contact)
+pub fn company_create(db: State<DbState>, input: CreateCompanyInput) -> Result<Company, String> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let domain = normalize_domain(&input.domain);
+    let headcount = validate_headcount(input.headcount)?;
+    {
+        let conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+        let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
+        let funding_stage = validate_funding_stage(conn, &input.funding_stage)?;
+        conn.execute(
+            "INSERT INTO companies (id, name, domain, industry, notes, headcount, hq_location, funding_stage, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                input.name,
+                domain,
+                input.industry,
+                input.notes,
+                headcount,
+                input.hq_location,
+                funding_stage,
+                now,
+                now,
+            ],
+        )
         .map_err(|e| e.to_string())?;
-    let contacts: Vec<Contact> = rows.filter_map(|r| r.ok()).collect();
+    }
+    company_get(db, id)?.ok_or_else(|| "Company not found after insert".to_string())
+}
 
-    let mut by_id: HashMap<String, Contact> = HashMap::new();
-    for c in contacts.iter() {
-        by_id.insert(c.id.clone(), c.clone());
+#[derive(Debug, Deserialize)]
+pub struct UpdateCompanyInput {
+    pub name: String,
+    pub domain: Option<String>,
+    pub industry: Option<String>,
+    pub notes: Option<String>,
+    pub headcount: Option<i64>,
+    pub hq_location: Option<String>,
+    pub funding_stage: Option<String>,
+}
+
+#[tauri::command]
+pub fn company_update(
+    db: State<DbState>,
+    id: String,
+    input: UpdateCompanyInput,
+) -> Result<Company, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let domain = normalize_domain(&input.domain);
+    let headcount = validate_headcount(input.headcount)?;
+    {
+        let conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+        let conn = conn_guard.as_ref().ok_or("DB not initialized")?;
+        let funding_stage = validate_funding_stage(conn, &input.funding_stage)?;
+        conn.execute(
+            "UPDATE companies SET name=?1, domain=?2, industry=?3, notes=?4, headcount=?5, hq_location=?6, funding_stage=?7, updated_at=?8 WHERE id=?9",
+            params![
+                input.name,
+                domain,
+                input.industry,
+                input.notes,
+                headcount,
+                input.hq_location,
+                funding_stage,
+                now,
+                id,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
     }
+    company_get(db, id)?.ok_or_else(|| "Company not found".to_string())
+}
 
-    #[derive(Default)]
-    struct ReasonFlags {
-        email: bool,
-        phone: bool,
-        name: bool,
+/// Merges `secondary_id` into `primary_id` in one transaction: the primary gets the `merged` fields,
+/// contacts, custom values the primary lacks and attachments move over, and the secondary is deleted.
+#[tauri::command]
+pub fn company_merge(
+    db: State<DbState>,
+    primary_id: String,
+    secondary_id: String,
+    merged: UpdateCompanyInput,
+) -> Result<Company, String> {
+    {
+        let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+        let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+        merge_companies(conn, &primary_id, &secondary_id, &merged)?;
     }
+    company_get(db, primary_id)?.ok_or_else(|| "Company not found".to_string())
+}
 
-    let mut pair_reasons: HashMap<(String, String), ReasonFlags> = HashMap::new();
+fn merge_companies(
+    conn: &mut rusqlite::Connection,
+    primary_id: &str,
+    secondary_id: &str,
+    merged: &UpdateCompanyInput,
+) -> Result<(), String> {
+    if primary_id == secondary_id {
+        return Err("Bir şirket kendisiyle birleştirilemez".to_string());
+    }
+    let name = merged.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Şirket adı boş olamaz".to_string());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let domain = normalize_domain(&merged.domain);
+    let headcount = validate_headcount(merged.headcount)?;
+    let funding_stage = validate_funding_stage(conn, &merged.funding_stage)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let existing: i64 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM companies WHERE id IN (?1, ?2)",
+            params![primary_id, secondary_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if existing != 2 {
+        return Err("Company not found".to_string());
+    }
+    tx.execute(
+        "UPDATE companies SET name=?1, domain=?2, industry=?3, notes=?4, headcount=?5, hq_location=?6, funding_stage=?7, updated_at=?8 WHERE id=?9",
+        params![
+            name,
+            domain,
+            merged.industry,
+            merged.notes,
+            headcount,
+            merged.hq_location,
+            funding_stage,
+            now,
+            primary_id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    // Contacts of both companies carry the (possibly renamed) company name.
+    tx.execute(
+        "UPDATE contacts SET company_id = ?1, company = ?2, updated_at = ?3 WHERE company_id IN (?1, ?4)",
+        params![primary_id, name, now, secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT OR IGNORE INTO company_custom_values (company_id, field_id, value)
+         SELECT ?1, field_id, value FROM company_custom_values WHERE company_id = ?2",
+        params![primary_id, secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    // foreign_keys is off: the secondary's values don't cascade.
+    tx.execute(
+        "DELETE FROM company_custom_values WHERE company_id = ?1",
+        params![secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE attachments SET owner_id = ?1 WHERE owner_type = 'company' AND owner_id = ?2",
+        params![primary_id, secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM companies WHERE id = ?1", params![secondary_id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Pinned companies are listed first in company_list.
+#[tauri::command]
+pub fn company_set_pinned(db: State<DbState>, id: String, pinned: bool) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let changed = conn
+        .execute(
+            "UPDATE companies SET is_pinned = ?1 WHERE id = ?2",
+            params![pinned, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Company not found".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn funding_stage_options_get(db: State<DbState>) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    funding_stage_options(conn)
+}
+
+/// Replaces the allowed funding_stage values; an empty list restores the defaults.
+#[tauri::command]
+pub fn funding_stage_options_set(db: State<DbState>, options: Vec<String>) -> Result<(), String> {
+    let options: Vec<String> = options
+        .iter()
+        .map(|o| o.trim().to_string())
+        .filter(|o| !o.is_empty())
+        .collect();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let encoded = serde_json::to_string(&options).map_err(|e| e.to_string())?;
+    setting_set(conn, "funding_stage_options", &encoded)
+}
+
+#[tauri::command]
+pub fn contact_list_by_company(db: State<DbState>, company_id: String) -> Result<Vec<Contact>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!(
+        "{} WHERE c.company_id = ?1 AND c.deleted_at IS NULL ORDER BY c.updated_at DESC",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![company_id], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Parent-org view: contacts of several companies in one call, grouped by company (each contact's
+/// `company` / `company_id` is the label), then by name.
+#[tauri::command]
+pub fn contact_list_by_companies(db: State<DbState>, company_ids: Vec<String>) -> Result<Vec<Contact>, String> {
+    if company_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    if company_ids.len() > 500 {
+        return Err("En fazla 500 şirket seçilebilir".to_string());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let placeholders: Vec<String> = (1..=company_ids.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!(
+        "{} WHERE c.company_id IN ({}) AND c.deleted_at IS NULL ORDER BY co.name COLLATE NOCASE, c.company_id, c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE",
+        CONTACT_SELECT,
+        placeholders.join(", ")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(company_ids.iter()), row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// ---- Tags (A4.1, A4.3) ----
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: String,
+}
+
+fn row_to_tag(row: &Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
+fn tag_get(conn: &rusqlite::Connection, id: &str) -> Result<Tag, String> {
+    conn.query_row(
+        "SELECT id, name, color, created_at FROM tags WHERE id = ?1",
+        params![id],
+        row_to_tag,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Tag not found".to_string())
+}
+
+/// Tag names are UNIQUE; check up front (case-insensitive) for a readable error.
+fn ensure_tag_name_free(conn: &rusqlite::Connection, name: &str, except_id: Option<&str>) -> Result<(), String> {
+    let taken: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM tags WHERE name = ?1 COLLATE NOCASE AND id != COALESCE(?2, ''))",
+            params![name, except_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if taken {
+        return Err(format!("Bu isimde bir etiket zaten var: {}", name));
+    }
+    Ok(())
+}
+
+fn clean_tag_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Etiket adı boş olamaz".to_string());
+    }
+    Ok(name.to_string())
+}
+
+#[tauri::command]
+pub fn tag_list(db: State<DbState>) -> Result<Vec<Tag>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, color, created_at FROM tags ORDER BY name COLLATE NOCASE")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_tag).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn tag_create(db: State<DbState>, name: String, color: Option<String>) -> Result<Tag, String> {
+    let name = clean_tag_name(&name)?;
+    let color = color.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    ensure_tag_name_free(conn, &name, None)?;
+    conn.execute(
+        "INSERT INTO tags (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, name, color, now],
+    )
+    .map_err(|e| e.to_string())?;
+    tag_get(conn, &id)
+}
+
+/// Rename and/or recolor; `None` leaves the field unchanged, an empty color clears it.
+#[tauri::command]
+pub fn tag_update(
+    db: State<DbState>,
+    id: String,
+    name: Option<String>,
+    color: Option<String>,
+) -> Result<Tag, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let current = tag_get(conn, &id)?;
+    let name = match name {
+        Some(n) => clean_tag_name(&n)?,
+        None => current.name,
+    };
+    ensure_tag_name_free(conn, &name, Some(&id))?;
+    let color = match color {
+        Some(c) => Some(c.trim().to_string()).filter(|c| !c.is_empty()),
+        None => current.color,
+    };
+    conn.execute(
+        "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+        params![name, color, id],
+    )
+    .map_err(|e| e.to_string())?;
+    tag_get(conn, &id)
+}
+
+#[tauri::command]
+pub fn tag_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    // contact_tags has ON DELETE CASCADE, but foreign_keys is not enabled on the connection.
+    tx.execute("DELETE FROM contact_tags WHERE tag_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM tags WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn contact_tags_get(db: State<DbState>, contact_id: String) -> Result<Vec<Tag>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color, t.created_at
+             FROM tags t JOIN contact_tags ct ON ct.tag_id = t.id
+             WHERE ct.contact_id = ?1
+             ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], row_to_tag)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Adding a tag the contact already has is a no-op.
+#[tauri::command]
+pub fn contact_tag_add(db: State<DbState>, contact_id: String, tag_id: String) -> Result<(), String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    tag_get(conn, &tag_id)?;
+    let changed = conn
+        .execute(
+            "UPDATE contacts SET updated_at = ?1 WHERE id = ?2",
+            params![now, contact_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Contact not found".to_string());
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO contact_tags (contact_id, tag_id) VALUES (?1, ?2)",
+        params![contact_id, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn contact_tag_remove(db: State<DbState>, contact_id: String, tag_id: String) -> Result<(), String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute(
+        "DELETE FROM contact_tags WHERE contact_id = ?1 AND tag_id = ?2",
+        params![contact_id, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE contacts SET updated_at = ?1 WHERE id = ?2",
+        params![now, contact_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Adds or removes one tag on many live contacts; only contacts whose tags actually changed get a
+/// new updated_at. Returns that count.
+fn tag_contacts_bulk(
+    conn: &rusqlite::Connection,
+    contact_ids: &[String],
+    tag_id: &str,
+    add: bool,
+    now: &str,
+) -> Result<u64, String> {
+    tag_get(conn, tag_id)?;
+    let sql = if add {
+        "INSERT OR IGNORE INTO contact_tags (contact_id, tag_id)
+         SELECT id, ?2 FROM contacts WHERE id = ?1 AND deleted_at IS NULL"
+    } else {
+        "DELETE FROM contact_tags WHERE contact_id = ?1 AND tag_id = ?2"
+    };
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let mut touch = conn
+        .prepare("UPDATE contacts SET updated_at = ?1 WHERE id = ?2")
+        .map_err(|e| e.to_string())?;
+    let mut affected = 0u64;
+    for id in contact_ids {
+        if stmt.execute(params![id, tag_id]).map_err(|e| e.to_string())? > 0 {
+            touch.execute(params![now, id]).map_err(|e| e.to_string())?;
+            affected += 1;
+        }
+    }
+    Ok(affected)
+}
+
+/// `mode`: "add" | "remove". One transaction for the whole selection.
+#[tauri::command]
+pub fn contacts_tag_bulk(
+    db: State<DbState>,
+    contact_ids: Vec<String>,
+    tag_id: String,
+    mode: String,
+) -> Result<u64, String> {
+    let add = match mode.as_str() {
+        "add" => true,
+        "remove" => false,
+        other => return Err(format!("Invalid mode: {}", other)),
+    };
+    if contact_ids.is_empty() {
+        return Err("En az bir kişi seçilmeli".to_string());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let affected = tag_contacts_bulk(&tx, &contact_ids, &tag_id, add, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(affected)
+}
+
+/// E1.2: contact ids carrying the tag, for filtering the contact list.
+#[tauri::command]
+pub fn contact_ids_by_tag(db: State<DbState>, tag_id: String) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare("SELECT contact_id FROM contact_tags WHERE tag_id = ?1 ORDER BY contact_id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![tag_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// ---- Custom fields (A3) ----
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomField {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub options: Option<String>,
+    pub sort_order: i64,
+    /// "contact" | "company"
+    pub scope: String,
+    pub created_at: String,
+    /// Counts towards contact completeness.
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomValue {
+    pub field_id: String,
+    pub field_name: String,
+    pub kind: String,
+    pub options: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomFieldInput {
+    pub name: String,
+    pub kind: String,
+    pub options: Option<String>,
+    /// Defaults to "contact".
+    pub scope: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+fn validate_custom_field_scope(scope: &str) -> Result<(), String> {
+    if scope == "contact" || scope == "company" {
+        Ok(())
+    } else {
+        Err("Invalid scope".to_string())
+    }
+}
+
+fn row_to_custom_field(row: &Row) -> rusqlite::Result<CustomField> {
+    Ok(CustomField {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        options: row.get(3)?,
+        sort_order: row.get(4)?,
+        scope: row.get(5)?,
+        created_at: row.get(6)?,
+        required: row.get(7)?,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomValueInput {
+    pub field_id: String,
+    pub value: Option<String>,
+}
+
+/// Checks a value against its field's kind. Empty values always pass so a field can be
+/// cleared; select fields without options accept anything.
+fn validate_custom_value(name: &str, kind: &str, options: Option<&str>, value: &str) -> Result<(), String> {
+    let v = value.trim();
+    if v.is_empty() {
+        return Ok(());
+    }
+    let options: Option<Vec<String>> = options.and_then(|o| serde_json::from_str(o).ok());
+    match kind {
+        "number" => {
+            v.parse::<f64>()
+                .map_err(|_| format!("{}: geçerli bir sayı olmalı", name))?;
+        }
+        "date" if v.len() != 10 || chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d").is_err() => {
+            return Err(format!("{}: tarih YYYY-MM-DD biçiminde olmalı", name));
+        }
+        "single_select" => {
+            if let Some(opts) = options.filter(|o| !o.is_empty()) {
+                if !opts.iter().any(|o| o == v) {
+                    return Err(format!("{}: '{}' geçerli bir seçenek değil", name, v));
+                }
+            }
+        }
+        "multi_select" => {
+            let selected: Vec<String> = serde_json::from_str(v)
+                .map_err(|_| format!("{}: değer JSON dizi olmalı", name))?;
+            if let Some(opts) = options.filter(|o| !o.is_empty()) {
+                if let Some(bad) = selected.iter().find(|s| !opts.contains(s)) {
+                    return Err(format!("{}: '{}' geçerli bir seçenek değil", name, bad));
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub owner_type: String,
+    pub owner_id: String,
+    pub file_name: String,
+    pub mime: Option<String>,
+    pub size: Option<i64>,
+    pub storage_path: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentCreateInput {
+    pub owner_type: String,
+    pub owner_id: String,
+    pub file_name: String,
+    pub mime: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+/// Lists custom field definitions; `scope` ("contact" | "company") narrows to one entity type.
+#[tauri::command]
+pub fn custom_field_list(db: State<DbState>, scope: Option<String>) -> Result<Vec<CustomField>, String> {
+    if let Some(ref s) = scope {
+        validate_custom_field_scope(s)?;
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, kind, options, sort_order, scope, created_at, required FROM custom_fields
+             WHERE ?1 IS NULL OR scope = ?1 ORDER BY sort_order, name",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![scope], row_to_custom_field)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn custom_field_create(db: State<DbState>, input: CreateCustomFieldInput) -> Result<CustomField, String> {
+    let id = format!("cf_{}", Uuid::new_v4().to_string().replace('-', "").chars().take(12).collect::<String>());
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let kind = if input.kind.is_empty() { "text" } else { input.kind.as_str() };
+    let scope = input.scope.as_deref().unwrap_or("contact");
+    validate_custom_field_scope(scope)?;
+    // Append after the current last field.
+    conn.execute(
+        "INSERT INTO custom_fields (id, name, kind, options, sort_order, scope, created_at, required)
+         VALUES (?1, ?2, ?3, ?4, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM custom_fields), ?5, ?6, ?7)",
+        params![id, input.name, kind, input.options, scope, now, input.required],
+    )
+    .map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, kind, options, sort_order, scope, created_at, required FROM custom_fields WHERE id = ?1")
+        .map_err(|e| e.to_string())?;
+    let row = stmt
+        .query_row(params![id], row_to_custom_field)
+        .map_err(|e| e.to_string())?;
+    Ok(row)
+}
+
+/// Renames a field and/or replaces its options (JSON array of strings, e.g. `["A","B"]`).
+#[tauri::command]
+pub fn custom_field_update(
+    db: State<DbState>,
+    id: String,
+    name: String,
+    options: Option<String>,
+) -> Result<CustomField, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Alan adı boş olamaz".to_string());
+    }
+    let options = options.filter(|o| !o.trim().is_empty());
+    if let Some(ref o) = options {
+        serde_json::from_str::<Vec<String>>(o).map_err(|_| "Seçenekler JSON dizi olmalı".to_string())?;
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let changed = conn
+        .execute(
+            "UPDATE custom_fields SET name = ?1, options = ?2 WHERE id = ?3",
+            params![name, options, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Custom field not found".to_string());
+    }
+    conn.query_row(
+        "SELECT id, name, kind, options, sort_order, scope, created_at, required FROM custom_fields WHERE id = ?1",
+        params![id],
+        row_to_custom_field,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Marks a contact field as required for contact completeness.
+#[tauri::command]
+pub fn custom_field_set_required(db: State<DbState>, id: String, required: bool) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let changed = conn
+        .execute(
+            "UPDATE custom_fields SET required = ?1 WHERE id = ?2",
+            params![required, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Custom field not found".to_string());
+    }
+    Ok(())
+}
+
+/// Deletes the field and its values. foreign_keys is off, so the ON DELETE CASCADE on
+/// contact/company_custom_values doesn't fire; the values are removed here instead.
+#[tauri::command]
+pub fn custom_field_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM contact_custom_values WHERE field_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM company_custom_values WHERE field_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    let changed = tx
+        .execute("DELETE FROM custom_fields WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Custom field not found".to_string());
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomFieldUsage {
+    pub field: CustomField,
+    /// Contacts (or companies, for company-scope fields) with a non-empty value.
+    pub used_count: i64,
+    /// Contacts / companies the field could apply to; trashed contacts are not counted.
+    pub total_count: i64,
+}
+
+/// Field health: how many records actually use each custom field, least used first.
+#[tauri::command]
+pub fn custom_field_usage(db: State<DbState>) -> Result<Vec<CustomFieldUsage>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let contacts_total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM contacts WHERE deleted_at IS NULL", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    let companies_total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM companies", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    // An empty multi_select is stored as "[]".
+    let mut stmt = conn
+        .prepare(
+            "SELECT f.id, f.name, f.kind, f.options, f.sort_order, f.scope, f.created_at, f.required,
+                CASE f.scope
+                    WHEN 'company' THEN (SELECT COUNT(*) FROM company_custom_values v
+                        WHERE v.field_id = f.id AND TRIM(COALESCE(v.value, '')) NOT IN ('', '[]'))
+                    ELSE (SELECT COUNT(*) FROM contact_custom_values v
+                        JOIN contacts c ON c.id = v.contact_id AND c.deleted_at IS NULL
+                        WHERE v.field_id = f.id AND TRIM(COALESCE(v.value, '')) NOT IN ('', '[]'))
+                END AS used
+             FROM custom_fields f
+             ORDER BY used ASC, f.sort_order, f.name",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let field = row_to_custom_field(row)?;
+            let total_count = if field.scope == "company" { companies_total } else { contacts_total };
+            Ok(CustomFieldUsage {
+                field,
+                used_count: row.get(8)?,
+                total_count,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Rewrites sort_order to each id's index in `ordered_ids`; fields not listed keep their order.
+#[tauri::command]
+pub fn custom_field_reorder(db: State<DbState>, ordered_ids: Vec<String>) -> Result<(), String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (index, id) in ordered_ids.iter().enumerate() {
+        let changed = tx
+            .execute(
+                "UPDATE custom_fields SET sort_order = ?1 WHERE id = ?2",
+                params![index as i64, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("Custom field not found: {}", id));
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn contact_custom_values_get(db: State<DbState>, contact_id: String) -> Result<Vec<CustomValue>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = "SELECT f.id, f.name, f.kind, f.options, v.value
+        FROM custom_fields f
+        LEFT JOIN contact_custom_values v ON v.field_id = f.id AND v.contact_id = ?1
+        WHERE f.scope = 'contact'
+        ORDER BY f.sort_order, f.name";
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |row| {
+            Ok(CustomValue {
+                field_id: row.get(0)?,
+                field_name: row.get(1)?,
+                kind: row.get(2)?,
+                options: row.get(3)?,
+                value: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn contact_custom_values_set(
+    db: State<DbState>,
+    contact_id: String,
+    values: Vec<CustomValueInput>,
+) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_mut().ok_or("DB not initialized")?;
+    set_custom_values(conn, "contact", &contact_id, &values)
+}
+
+/// Upserts `values` for a contact or company (`scope`). Every field must exist with that scope and
+/// every value must pass validate_custom_value before anything is written, so a bad entry doesn't
+/// leave the others half-saved.
+fn set_custom_values(
+    conn: &mut rusqlite::Connection,
+    scope: &str,
+    owner_id: &str,
+    values: &[CustomValueInput],
+) -> Result<(), String> {
+    for v in values {
+        let field: Option<(String, String, Option<String>, String)> = conn
+            .query_row(
+                "SELECT name, kind, options, scope FROM custom_fields WHERE id = ?1",
+                params![v.field_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let (name, kind, options, field_scope) = field.ok_or("Custom field not found")?;
+        if field_scope != scope {
+            return Err(format!("Custom field {} is not a {} field", v.field_id, scope));
+        }
+        if let Some(ref value) = v.value {
+            validate_custom_value(&name, &kind, options.as_deref(), value)?;
+        }
+    }
+    let (table, owner_column) = match scope {
+        "contact" => ("contact_custom_values", "contact_id"),
+        "company" => ("company_custom_values", "company_id"),
+        other => return Err(format!("Invalid scope: {}", other)),
+    };
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for v in values {
+        tx.execute(
+            &format!(
+                "INSERT INTO {table} ({owner}, field_id, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT({owner}, field_id) DO UPDATE SET value = excluded.value",
+                table = table,
+                owner = owner_column
+            ),
+            params![owner_id, v.field_id, v.value],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn contact_ids_by_custom_value(
+    db: State<DbState>,
+    field_id: String,
+    value: String,
+) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let kind: Option<String> = conn
+        .query_row(
+            "SELECT kind FROM custom_fields WHERE id = ?1",
+            params![field_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if kind.as_deref() == Some("multi_select") {
+        let mut stmt = conn
+            .prepare("SELECT contact_id, value FROM contact_custom_values WHERE field_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![field_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            if let Ok((contact_id, v)) = row {
+                if value_contains_option(&v, &value) {
+                    ids.push(contact_id);
+                }
+            }
+        }
+        Ok(ids)
+    } else {
+        let mut stmt = conn
+            .prepare("SELECT contact_id FROM contact_custom_values WHERE field_id = ?1 AND value = ?2")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![field_id, value], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        Ok(rows.filter_map(|r| r.ok()).collect())
+    }
+}
+
+/// Seeded custom field holding where a contact came from.
+const SOURCE_FIELD_ID: &str = "cf_source";
+
+#[derive(Debug, Serialize)]
+pub struct SourceCount {
+    pub source: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceContacts {
+    pub contacts: Vec<Contact>,
+    /// Live contacts per Source value, largest first.
+    pub counts: Vec<SourceCount>,
+}
+
+#[tauri::command]
+pub fn contacts_by_source(db: State<DbState>, source: String) -> Result<SourceContacts, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!(
+        "{} JOIN contact_custom_values v ON v.contact_id = c.id AND v.field_id = ?1
+         WHERE v.value = ?2 AND c.deleted_at IS NULL
+         ORDER BY c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let contacts = stmt
+        .query_map(params![SOURCE_FIELD_ID, source.trim()], row_to_contact)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    let mut stmt = conn
+        .prepare(
+            "SELECT v.value, COUNT(*) FROM contact_custom_values v
+             JOIN contacts c ON c.id = v.contact_id
+             WHERE v.field_id = ?1 AND c.deleted_at IS NULL AND v.value IS NOT NULL AND v.value != ''
+             GROUP BY v.value ORDER BY COUNT(*) DESC, v.value",
+        )
+        .map_err(|e| e.to_string())?;
+    let counts = stmt
+        .query_map(params![SOURCE_FIELD_ID], |row| {
+            Ok(SourceCount {
+                source: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(SourceContacts { contacts, counts })
+}
+
+#[derive(Debug, Serialize)]
+pub struct PipelineEntry {
+    pub contact: Contact,
+    /// Earliest open reminder title/due date, falling back to next_touch_at.
+    pub next_action: Option<String>,
+    pub next_action_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PipelineStage {
+    pub stage: String,
+    pub contacts: Vec<PipelineEntry>,
+}
+
+const PIPELINE_STAGE_FIELD: &str = "cf_stage";
+
+/// Stage-by-stage contact lists for a pipeline review (seeded Stage field), in option order.
+#[tauri::command]
+pub fn pipeline_export(db: State<DbState>, stage: Option<String>) -> Result<Vec<PipelineStage>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let stage = stage.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let options: Option<String> = conn
+        .query_row(
+            "SELECT options FROM custom_fields WHERE id = ?1",
+            params![PIPELINE_STAGE_FIELD],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .flatten();
+    let mut stage_order: Vec<String> = options
+        .and_then(|o| serde_json::from_str::<Vec<String>>(&o).ok())
+        .unwrap_or_default();
+
+    let mut stage_by_contact: HashMap<String, String> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT contact_id, value FROM contact_custom_values
+                 WHERE field_id = ?1 AND value IS NOT NULL AND TRIM(value) != ''",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![PIPELINE_STAGE_FIELD], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for (contact_id, value) in rows.filter_map(|r| r.ok()) {
+            if stage.is_none() || stage.as_deref() == Some(value.as_str()) {
+                stage_by_contact.insert(contact_id, value);
+            }
+        }
+    }
+
+    let mut next_by_contact: HashMap<String, (String, String)> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT contact_id, title, due_at FROM reminders
+                 WHERE completed_at IS NULL AND contact_id IS NOT NULL
+                 ORDER BY datetime(due_at)",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })
+            .map_err(|e| e.to_string())?;
+        for (contact_id, title, due_at) in rows.filter_map(|r| r.ok()) {
+            next_by_contact.entry(contact_id).or_insert((title, due_at));
+        }
+    }
+
+    let mut groups: HashMap<String, Vec<PipelineEntry>> = HashMap::new();
+    let sql = format!("{} WHERE c.deleted_at IS NULL ORDER BY c.last_name, c.first_name", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    for contact in rows.filter_map(|r| r.ok()) {
+        let Some(value) = stage_by_contact.get(&contact.id) else {
+            continue;
+        };
+        let (next_action, next_action_at) = match next_by_contact.remove(&contact.id) {
+            Some((title, due_at)) => (Some(title), Some(due_at)),
+            None => (None, contact.next_touch_at.clone()),
+        };
+        groups.entry(value.clone()).or_default().push(PipelineEntry {
+            contact,
+            next_action,
+            next_action_at,
+        });
+    }
+
+    let mut extra: Vec<String> = groups
+        .keys()
+        .filter(|k| !stage_order.contains(k))
+        .cloned()
+        .collect();
+    extra.sort();
+    stage_order.extend(extra);
+    Ok(stage_order
+        .into_iter()
+        .filter(|s| stage.is_none() || stage.as_ref() == Some(s))
+        .map(|s| PipelineStage {
+            contacts: groups.remove(&s).unwrap_or_default(),
+            stage: s,
+        })
+        .collect())
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PipelineCount {
+    /// None = contacts without a value for the field.
+    pub option: Option<String>,
+    pub count: i64,
+}
+
+/// Option order first (zero counts included), then values no longer among the options, then the
+/// empty bucket.
+fn order_pipeline_counts(options: &[String], counts: Vec<(Option<String>, i64)>) -> Vec<PipelineCount> {
+    let mut by_value: HashMap<Option<String>, i64> = counts.into_iter().collect();
+    let mut report: Vec<PipelineCount> = options
+        .iter()
+        .map(|o| PipelineCount {
+            count: by_value.remove(&Some(o.clone())).unwrap_or(0),
+            option: Some(o.clone()),
+        })
+        .collect();
+    let empty = by_value.remove(&None).unwrap_or(0);
+    let mut extra: Vec<(Option<String>, i64)> = by_value.into_iter().collect();
+    extra.sort();
+    report.extend(extra.into_iter().map(|(option, count)| PipelineCount { option, count }));
+    report.push(PipelineCount { option: None, count: empty });
+    report
+}
+
+/// Live contacts per option of a single-select contact field (default: the seeded Stage field).
+#[tauri::command]
+pub fn pipeline_report(db: State<DbState>, field_id: Option<String>) -> Result<Vec<PipelineCount>, String> {
+    let field_id = field_id
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| PIPELINE_STAGE_FIELD.to_string());
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let (kind, options): (String, Option<String>) = conn
+        .query_row(
+            "SELECT kind, options FROM custom_fields WHERE id = ?1 AND scope = 'contact'",
+            params![field_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Custom field not found")?;
+    if kind != "single_select" {
+        return Err("Alan tek seçimli (single_select) olmalı".to_string());
+    }
+    let options: Vec<String> = options
+        .and_then(|o| serde_json::from_str(&o).ok())
+        .unwrap_or_default();
+    let mut stmt = conn
+        .prepare(
+            "SELECT NULLIF(TRIM(v.value), ''), COUNT(*) FROM contacts c
+             LEFT JOIN contact_custom_values v ON v.contact_id = c.id AND v.field_id = ?1
+             WHERE c.deleted_at IS NULL
+             GROUP BY NULLIF(TRIM(v.value), '')",
+        )
+        .map_err(|e| e.to_string())?;
+    let counts = stmt
+        .query_map(params![field_id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(order_pipeline_counts(&options, counts))
+}
+
+#[tauri::command]
+pub fn company_custom_values_get(db: State<DbState>, company_id: String) -> Result<Vec<CustomValue>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = "SELECT f.id, f.name, f.kind, f.options, v.value
+        FROM custom_fields f
+        LEFT JOIN company_custom_values v ON v.field_id = f.id AND v.company_id = ?1
+        WHERE f.scope = 'company'
+        ORDER BY f.sort_order, f.name";
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![company_id], |row| {
+            Ok(CustomValue {
+                field_id: row.get(0)?,
+                field_name: row.get(1)?,
+                kind: row.get(2)?,
+                options: row.get(3)?,
+                value: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn company_custom_values_set(
+    db: State<DbState>,
+    company_id: String,
+    values: Vec<CustomValueInput>,
+) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_mut().ok_or("DB not initialized")?;
+    set_custom_values(conn, "company", &company_id, &values)
+}
+
+// ---- Notes ----
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Note {
+    pub id: String,
+    pub contact_id: String,
+    pub kind: String,
+    pub title: Option<String>,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateNoteInput {
+    pub contact_id: String,
+    pub kind: Option<String>,
+    pub title: Option<String>,
+    pub body: String,
+}
+
+#[tauri::command]
+pub fn note_list(db: State<DbState>, contact_id: String) -> Result<Vec<Note>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare("SELECT id, contact_id, kind, title, body, created_at, updated_at FROM notes WHERE contact_id = ?1 ORDER BY created_at DESC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |row| {
+            Ok(Note {
+                id: row.get(0)?,
+                contact_id: row.get(1)?,
+                kind: row.get(2)?,
+                title: row.get(3)?,
+                body: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn note_create(db: State<DbState>, input: CreateNoteInput) -> Result<Note, String> {
+    let id = Uuid::new_v4().to_string();
+    let kind = input.kind.unwrap_or_else(|| "note".to_string());
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute(
+        "INSERT INTO notes (id, contact_id, kind, title, body, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![id, input.contact_id, kind, input.title, input.body, now, now],
+    )
+    .map_err(|e| e.to_string())?;
+    // Update contact last_touched_at
+    let _ = conn.execute(
+        "UPDATE contacts SET last_touched_at = ?1, updated_at = ?1 WHERE id = ?2",
+        params![now, input.contact_id],
+    );
+    let mut stmt = conn
+        .prepare("SELECT id, contact_id, kind, title, body, created_at, updated_at FROM notes WHERE id = ?1")
+        .map_err(|e| e.to_string())?;
+    let row = stmt
+        .query_row(params![id], |row| {
+            Ok(Note {
+                id: row.get(0)?,
+                contact_id: row.get(1)?,
+                kind: row.get(2)?,
+                title: row.get(3)?,
+                body: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(row)
+}
+
+fn note_by_id(conn: &rusqlite::Connection, id: &str) -> Result<Option<Note>, String> {
+    conn.query_row(
+        "SELECT id, contact_id, kind, title, body, created_at, updated_at FROM notes WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Note {
+                id: row.get(0)?,
+                contact_id: row.get(1)?,
+                kind: row.get(2)?,
+                title: row.get(3)?,
+                body: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Rewrites a note; `kind` None keeps the current kind. The contact's last_touched_at only moves
+/// when the body actually changed (fixing a title is not a touch).
+#[tauri::command]
+pub fn note_update(
+    db: State<DbState>,
+    id: String,
+    title: Option<String>,
+    body: String,
+    kind: Option<String>,
+) -> Result<Note, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let current = note_by_id(conn, &id)?.ok_or_else(|| "Note not found".to_string())?;
+    let kind = kind
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .unwrap_or(current.kind);
+    conn.execute(
+        "UPDATE notes SET kind = ?1, title = ?2, body = ?3, updated_at = ?4 WHERE id = ?5",
+        params![kind, title, body, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    if body != current.body {
+        conn.execute(
+            "UPDATE contacts SET last_touched_at = ?1, updated_at = ?1 WHERE id = ?2",
+            params![now, current.contact_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    note_by_id(conn, &id)?.ok_or_else(|| "Note not found".to_string())
+}
+
+/// Deletes the attachment rows of one owner; returns their files for remove_attachment_files.
+fn delete_owner_attachments(
+    conn: &rusqlite::Connection,
+    owner_type: &str,
+    owner_id: &str,
+) -> Result<Vec<String>, String> {
+    let paths: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT storage_path FROM attachments WHERE owner_type = ?1 AND owner_id = ?2")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![owner_type, owner_id], |r| r.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    conn.execute(
+        "DELETE FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+        params![owner_type, owner_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(paths)
+}
+
+/// Reminders keep existing with note_id cleared (the ON DELETE SET NULL is not enforced because
+/// foreign_keys is off); attachments of the note are deleted with it and their files returned.
+fn delete_note(conn: &rusqlite::Connection, id: &str) -> Result<Vec<String>, String> {
+    if note_by_id(conn, id)?.is_none() {
+        return Ok(Vec::new());
+    }
+    conn.execute("UPDATE reminders SET note_id = NULL WHERE note_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    let files = delete_owner_attachments(conn, "note", id)?;
+    conn.execute("DELETE FROM notes WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(files)
+}
+
+#[tauri::command]
+pub fn note_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let files = delete_note(&tx, &id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    remove_attachment_files(&files);
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteWithAttachments {
+    pub note: Note,
+    pub attachment_names: Vec<String>,
+}
+
+/// A6: notes that have files attached (owner_type = 'note'), newest first; optionally for one contact.
+#[tauri::command]
+pub fn notes_with_attachments(
+    db: State<DbState>,
+    contact_id: Option<String>,
+) -> Result<Vec<NoteWithAttachments>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.contact_id, n.kind, n.title, n.body, n.created_at, n.updated_at, a.file_name
+             FROM notes n JOIN attachments a ON a.owner_type = 'note' AND a.owner_id = n.id
+             WHERE ?1 IS NULL OR n.contact_id = ?1
+             ORDER BY n.created_at DESC, n.id, a.created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |row| {
+            Ok((
+                Note {
+                    id: row.get(0)?,
+                    contact_id: row.get(1)?,
+                    kind: row.get(2)?,
+                    title: row.get(3)?,
+                    body: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                },
+                row.get::<_, String>(7)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut out: Vec<NoteWithAttachments> = Vec::new();
+    for (note, file_name) in rows.filter_map(|r| r.ok()) {
+        match out.last_mut() {
+            Some(last) if last.note.id == note.id => last.attachment_names.push(file_name),
+            _ => out.push(NoteWithAttachments {
+                note,
+                attachment_names: vec![file_name],
+            }),
+        }
+    }
+    Ok(out)
+}
+
+// ---- Interactions (B1: Etkileşim logu) ----
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Interaction {
+    pub id: String,
+    pub contact_id: String,
+    pub kind: String,
+    pub happened_at: String,
+    pub summary: Option<String>,
+    pub created_at: String,
+}
+
+fn row_to_interaction(row: &Row) -> rusqlite::Result<Interaction> {
+    Ok(Interaction {
+        id: row.get(0)?,
+        contact_id: row.get(1)?,
+        kind: row.get(2)?,
+        happened_at: row.get(3)?,
+        summary: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateInteractionInput {
+    pub contact_id: String,
+    pub kind: String,
+    pub happened_at: String,
+    pub summary: Option<String>,
+}
+
+#[tauri::command]
+pub fn interaction_list(db: State<DbState>, contact_id: String) -> Result<Vec<Interaction>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare("SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions WHERE contact_id = ?1 ORDER BY happened_at DESC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |row| {
+            Ok(Interaction {
+                id: row.get(0)?,
+                contact_id: row.get(1)?,
+                kind: row.get(2)?,
+                happened_at: row.get(3)?,
+                summary: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn interaction_create(db: State<DbState>, input: CreateInteractionInput) -> Result<Interaction, String> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute(
+        "INSERT INTO interactions (id, contact_id, kind, happened_at, summary, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+        params![id, input.contact_id, input.kind, input.happened_at, input.summary, now],
+    )
+    .map_err(|e| e.to_string())?;
+    // B1.2: Last touched otomatik güncelle
+    let _ = conn.execute(
+        "UPDATE contacts SET last_touched_at = ?1, updated_at = ?2 WHERE id = ?3",
+        params![input.happened_at, now, input.contact_id],
+    );
+    let mut stmt = conn
+        .prepare("SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions WHERE id = ?1")
+        .map_err(|e| e.to_string())?;
+    let row = stmt
+        .query_row(params![id], |row| {
+            Ok(Interaction {
+                id: row.get(0)?,
+                contact_id: row.get(1)?,
+                kind: row.get(2)?,
+                happened_at: row.get(3)?,
+                summary: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(row)
+}
+
+/// B1.2: last_touched_at = latest of the contact's interactions (happened_at), notes (created_at or
+/// updated_at) and completed reminders (completed_at) — the same events that bump it on create and
+/// note_update. NULL when none remain.
+fn refresh_last_touched(conn: &rusqlite::Connection, contact_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE contacts SET last_touched_at = (
+            SELECT at FROM (
+                SELECT happened_at AS at FROM interactions WHERE contact_id = ?1
+                UNION ALL SELECT created_at FROM notes WHERE contact_id = ?1
+                UNION ALL SELECT updated_at FROM notes WHERE contact_id = ?1 AND updated_at IS NOT NULL
+                UNION ALL SELECT completed_at FROM reminders WHERE contact_id = ?1 AND completed_at IS NOT NULL
+            ) ORDER BY datetime(at) DESC LIMIT 1
+         ), updated_at = ?2 WHERE id = ?1",
+        params![contact_id, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn interaction_update(
+    db: State<DbState>,
+    id: String,
+    kind: String,
+    happened_at: String,
+    summary: Option<String>,
+) -> Result<Interaction, String> {
+    let kind = kind.trim().to_string();
+    if kind.is_empty() {
+        return Err("Tür boş olamaz".to_string());
+    }
+    if parse_datetime_utc(&happened_at).is_none() {
+        return Err("Geçersiz tarih".to_string());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let contact_id: String = conn
+        .query_row("SELECT contact_id FROM interactions WHERE id = ?1", params![id], |r| r.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Interaction not found")?;
+    conn.execute(
+        "UPDATE interactions SET kind = ?1, happened_at = ?2, summary = ?3, updated_at = ?4 WHERE id = ?5",
+        params![kind, happened_at, summary, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    refresh_last_touched(conn, &contact_id, &now)?;
+    conn.query_row(
+        "SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions WHERE id = ?1",
+        params![id],
+        row_to_interaction,
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn interaction_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let contact_id: String = tx
+        .query_row("SELECT contact_id FROM interactions WHERE id = ?1", params![id], |r| r.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Interaction not found")?;
+    let files = delete_owner_attachments(&tx, "interaction", &id)?;
+    tx.execute("DELETE FROM interactions WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    refresh_last_touched(&tx, &contact_id, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    remove_attachment_files(&files);
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct InteractionStatsBucket {
+    /// "YYYY-MM-DD" (day; Monday of the week for week) or "YYYY-MM" (month).
+    pub bucket: String,
+    pub total: i64,
+    pub by_kind: std::collections::BTreeMap<String, i64>,
+}
+
+/// Interaction counts per time bucket and kind between two dates (inclusive), grouped in SQLite.
+/// Interactions of trashed contacts are left out.
+fn interaction_stats_rows(
+    conn: &rusqlite::Connection,
+    from: &str,
+    to: &str,
+    bucket: &str,
+) -> Result<Vec<InteractionStatsBucket>, String> {
+    let bucket_expr = match bucket {
+        "day" => "date(i.happened_at)",
+        "week" => "date(i.happened_at, 'weekday 0', '-6 days')",
+        "month" => "strftime('%Y-%m', i.happened_at)",
+        other => return Err(format!("Invalid bucket: {}", other)),
+    };
+    let from = parse_datetime_utc(from.trim()).ok_or("Geçersiz başlangıç tarihi")?;
+    let to = parse_datetime_utc(to.trim()).ok_or("Geçersiz bitiş tarihi")?;
+    let sql = format!(
+        "SELECT {} AS bucket, i.kind, COUNT(*) FROM interactions i
+         JOIN contacts c ON c.id = i.contact_id
+         WHERE c.deleted_at IS NULL AND date(i.happened_at) BETWEEN date(?1) AND date(?2)
+         GROUP BY bucket, i.kind
+         ORDER BY bucket, i.kind",
+        bucket_expr
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string()],
+            |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut buckets: Vec<InteractionStatsBucket> = Vec::new();
+    for (bucket, kind, count) in rows.filter_map(|r| r.ok()) {
+        if buckets.last().map(|b| b.bucket != bucket).unwrap_or(true) {
+            buckets.push(InteractionStatsBucket {
+                bucket,
+                total: 0,
+                by_kind: std::collections::BTreeMap::new(),
+            });
+        }
+        let last = buckets.last_mut().expect("bucket pushed above");
+        last.total += count;
+        last.by_kind.insert(kind, count);
+    }
+    Ok(buckets)
+}
+
+/// `bucket`: "day" | "week" | "month". Buckets without interactions are omitted.
+#[tauri::command]
+pub fn interaction_stats(
+    db: State<DbState>,
+    from: String,
+    to: String,
+    bucket: String,
+) -> Result<Vec<InteractionStatsBucket>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    interaction_stats_rows(conn, &from, &to, &bucket)
+}
+
+// ---- Contact timeline (notes + interactions + completed reminders) ----
+
+#[derive(Debug, Serialize)]
+pub struct TimelineEntry {
+    /// "note" | "interaction" | "reminder"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+    /// Sort key: note created_at, interaction happened_at, reminder completed_at.
+    pub at: String,
+    pub title: String,
+    pub snippet: Option<String>,
+}
+
+const TIMELINE_SNIPPET_LEN: usize = 120;
+
+/// First `max_chars` characters of `text`, with an ellipsis when cut.
+fn text_snippet(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max_chars).collect::<String>())
+    }
+}
+
+/// Newest first (ties by id, descending); `before` is the `(at, id)` of the last entry of the
+/// previous page and keeps only entries sorting after it, so same-timestamp events aren't skipped.
+/// Timestamps are compared as UTC instants since stored formats are mixed.
+fn page_timeline(mut entries: Vec<TimelineEntry>, limit: usize, before: Option<(&str, &str)>) -> Vec<TimelineEntry> {
+    let cursor = before.map(|(at, id)| (parse_datetime_utc(at), id));
+    entries.sort_by_cached_key(|e| std::cmp::Reverse((parse_datetime_utc(&e.at), e.id.clone())));
+    entries
+        .into_iter()
+        .filter(|e| cursor.map(|c| (parse_datetime_utc(&e.at), e.id.as_str()) < c).unwrap_or(true))
+        .take(limit)
+        .collect()
+}
+
+/// Cursor filter and newest-first order for one timeline source (?1 contact, ?2/?3 cursor at/id,
+/// ?4 limit), so each source returns at most one page before the merge in page_timeline.
+fn timeline_page_sql(at: &str) -> String {
+    format!(
+        " AND (?2 IS NULL OR datetime({at}) < datetime(?2) OR (datetime({at}) = datetime(?2) AND id < ?3))
+         ORDER BY datetime({at}) DESC, id DESC LIMIT ?4",
+        at = at
+    )
+}
+
+fn timeline_rows(
+    conn: &rusqlite::Connection,
+    contact_id: &str,
+    limit: usize,
+    before: Option<(&str, &str)>,
+) -> Result<Vec<TimelineEntry>, String> {
+    let (before_at, before_id) = match before {
+        Some((at, id)) => (Some(at), id),
+        None => (None, ""),
+    };
+    let args = params![contact_id, before_at, before_id, limit as i64];
+    let mut entries: Vec<TimelineEntry> = Vec::new();
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, kind, title, body, created_at FROM notes WHERE contact_id = ?1{}",
+            timeline_page_sql("created_at")
+        ))
+        .map_err(|e| e.to_string())?;
+    let notes = stmt
+        .query_map(args, |row| {
+            let kind: String = row.get(1)?;
+            let title: Option<String> = row.get(2)?;
+            let body: String = row.get(3)?;
+            Ok(TimelineEntry {
+                kind: "note".to_string(),
+                id: row.get(0)?,
+                at: row.get(4)?,
+                title: title.filter(|t| !t.trim().is_empty()).unwrap_or(kind),
+                snippet: Some(text_snippet(&body, TIMELINE_SNIPPET_LEN)).filter(|s| !s.is_empty()),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    entries.extend(notes.filter_map(|r| r.ok()));
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions WHERE contact_id = ?1{}",
+            timeline_page_sql("happened_at")
+        ))
+        .map_err(|e| e.to_string())?;
+    let interactions = stmt.query_map(args, row_to_interaction).map_err(|e| e.to_string())?;
+    for interaction in interactions.filter_map(|r| r.ok()) {
+        entries.push(TimelineEntry {
+            kind: "interaction".to_string(),
+            snippet: interaction
+                .summary
+                .as_deref()
+                .map(|s| text_snippet(s, TIMELINE_SNIPPET_LEN))
+                .filter(|s| !s.is_empty()),
+            id: interaction.id,
+            at: interaction.happened_at,
+            title: interaction.kind,
+        });
+    }
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, notify_before_minutes, created_at
+             FROM reminders WHERE contact_id = ?1 AND completed_at IS NOT NULL{}",
+            timeline_page_sql("completed_at")
+        ))
+        .map_err(|e| e.to_string())?;
+    let reminders = stmt.query_map(args, row_to_reminder).map_err(|e| e.to_string())?;
+    for reminder in reminders.filter_map(|r| r.ok()) {
+        entries.push(TimelineEntry {
+            kind: "reminder".to_string(),
+            at: reminder.completed_at.unwrap_or_default(),
+            id: reminder.id,
+            title: reminder.title,
+            snippet: None,
+        });
+    }
+
+    Ok(page_timeline(entries, limit, before))
+}
+
+/// Unified contact timeline: notes, interactions and completed reminders, newest first, `limit`
+/// (default 50) per page. Open reminders are not events yet and are left out. Pass the last
+/// entry's `at` and `id` as `before` / `before_id` for the next page; without `before_id` only
+/// entries strictly older than `before` are returned.
+#[tauri::command]
+pub fn contact_timeline(
+    db: State<DbState>,
+    contact_id: String,
+    limit: Option<i64>,
+    before: Option<String>,
+    before_id: Option<String>,
+) -> Result<Vec<TimelineEntry>, String> {
+    let limit = limit.unwrap_or(50).clamp(1, 500) as usize;
+    let before = before.map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+    let before_id = before_id.unwrap_or_default();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let exists: bool = conn
+        .query_row("SELECT 1 FROM contacts WHERE id = ?1", params![contact_id], |_| Ok(true))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if !exists {
+        return Err("Contact not found".to_string());
+    }
+    timeline_rows(conn, &contact_id, limit, before.as_deref().map(|b| (b, before_id.as_str())))
+}
+
+// ---- Contact snapshots (periodic review: what changed since) ----
+
+/// (table, contact filter, row key, fingerprint). A row counts as changed when its fingerprint differs.
+const SNAPSHOT_RELATED: [(&str, &str, &str, &str); 6] = [
+    ("notes", "contact_id = ?1", "id", "updated_at"),
+    ("interactions", "contact_id = ?1", "id", "happened_at || '|' || COALESCE(summary, '')"),
+    (
+        "reminders",
+        "contact_id = ?1",
+        "id",
+        "due_at || '|' || COALESCE(snooze_until, '') || '|' || COALESCE(completed_at, '')",
+    ),
+    ("attachments", "owner_type = 'contact' AND owner_id = ?1", "id", "''"),
+    ("contact_custom_values", "contact_id = ?1", "field_id", "COALESCE(value, '')"),
+    ("contact_tags", "contact_id = ?1", "tag_id", "''"),
+];
+
+type RelatedRows = std::collections::BTreeMap<String, std::collections::BTreeMap<String, String>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotData {
+    contact: serde_json::Value,
+    related: RelatedRows,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactSnapshotInfo {
+    pub id: String,
+    pub contact_id: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelatedChange {
+    pub table: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactDiff {
+    pub snapshot_id: String,
+    pub snapshot_at: String,
+    pub fields: Vec<FieldChange>,
+    pub related: Vec<RelatedChange>,
+}
+
+fn snapshot_data(conn: &rusqlite::Connection, contact_id: &str) -> Result<SnapshotData, String> {
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let contact = conn
+        .query_row(&sql, params![contact_id], row_to_contact)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Contact not found")?;
+    let mut related = RelatedRows::new();
+    for (table, filter, key, fingerprint) in SNAPSHOT_RELATED {
+        let mut stmt = conn
+            .prepare(&format!("SELECT {}, {} FROM {} WHERE {}", key, fingerprint, table, filter))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![contact_id], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        related.insert(table.to_string(), rows);
+    }
+    Ok(SnapshotData {
+        contact: serde_json::to_value(&contact).map_err(|e| e.to_string())?,
+        related,
+    })
+}
+
+/// Field changes (updated_at is implied and skipped) and related rows added/removed/changed.
+fn diff_snapshots(before: &SnapshotData, after: &SnapshotData) -> (Vec<FieldChange>, Vec<RelatedChange>) {
+    let empty = serde_json::Map::new();
+    let old_fields = before.contact.as_object().unwrap_or(&empty);
+    let new_fields = after.contact.as_object().unwrap_or(&empty);
+    let mut fields = Vec::new();
+    for (field, after_value) in new_fields {
+        if field == "updated_at" {
+            continue;
+        }
+        let before_value = old_fields.get(field).cloned().unwrap_or(serde_json::Value::Null);
+        if &before_value != after_value {
+            fields.push(FieldChange {
+                field: field.clone(),
+                before: before_value,
+                after: after_value.clone(),
+            });
+        }
+    }
+    let no_rows = std::collections::BTreeMap::new();
+    let mut related = Vec::new();
+    for (table, _, _, _) in SNAPSHOT_RELATED {
+        let old_rows = before.related.get(table).unwrap_or(&no_rows);
+        let new_rows = after.related.get(table).unwrap_or(&no_rows);
+        let added: Vec<String> = new_rows.keys().filter(|k| !old_rows.contains_key(*k)).cloned().collect();
+        let removed: Vec<String> = old_rows.keys().filter(|k| !new_rows.contains_key(*k)).cloned().collect();
+        let changed: Vec<String> = new_rows
+            .iter()
+            .filter(|(k, v)| old_rows.get(*k).is_some_and(|old| old != *v))
+            .map(|(k, _)| k.clone())
+            .collect();
+        if !added.is_empty() || !removed.is_empty() || !changed.is_empty() {
+            related.push(RelatedChange {
+                table: table.to_string(),
+                added,
+                removed,
+                changed,
+            });
+        }
+    }
+    (fields, related)
+}
+
+/// Stores the contact's current state (fields + related row keys) for a later contact_diff_since.
+#[tauri::command]
+pub fn contact_snapshot(db: State<DbState>, contact_id: String) -> Result<ContactSnapshotInfo, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let data = snapshot_data(conn, &contact_id)?;
+    let json = serde_json::to_string(&data).map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    conn.execute(
+        "INSERT INTO contact_snapshots (id, contact_id, data, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, contact_id, json, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(ContactSnapshotInfo {
+        id,
+        contact_id,
+        created_at: now,
+    })
+}
+
+#[tauri::command]
+pub fn contact_snapshots_list(db: State<DbState>, contact_id: String) -> Result<Vec<ContactSnapshotInfo>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare("SELECT id, contact_id, created_at FROM contact_snapshots WHERE contact_id = ?1 ORDER BY created_at DESC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |r| {
+            Ok(ContactSnapshotInfo {
+                id: r.get(0)?,
+                contact_id: r.get(1)?,
+                created_at: r.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn contact_diff_since(db: State<DbState>, contact_id: String, snapshot_id: String) -> Result<ContactDiff, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let (json, snapshot_at): (String, String) = conn
+        .query_row(
+            "SELECT data, created_at FROM contact_snapshots WHERE id = ?1 AND contact_id = ?2",
+            params![snapshot_id, contact_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Snapshot not found")?;
+    let before: SnapshotData = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    let after = snapshot_data(conn, &contact_id)?;
+    let (fields, related) = diff_snapshots(&before, &after);
+    Ok(ContactDiff {
+        snapshot_id,
+        snapshot_at,
+        fields,
+        related,
+    })
+}
+
+// ---- Reminders ----
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: String,
+    pub contact_id: String,
+    pub note_id: Option<String>,
+    pub title: String,
+    pub due_at: String,
+    pub snooze_until: Option<String>,
+    pub recurring_days: Option<i64>,
+    pub completed_at: Option<String>,
+    /// D1.1: Notify this many minutes before due_at (0 = at due time).
+    pub notify_before_minutes: i64,
+    pub created_at: String,
+    /// Only set by reminder_create (e.g. contact is marked do-not-contact).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+fn row_to_reminder(row: &Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        contact_id: row.get(1)?,
+        note_id: row.get(2)?,
+        title: row.get(3)?,
+        due_at: row.get(4)?,
+        snooze_until: row.get(5)?,
+        recurring_days: row.get(6)?,
+        completed_at: row.get(7)?,
+        notify_before_minutes: row.get(8)?,
+        created_at: row.get(9)?,
+        warning: None,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReminderInput {
+    pub contact_id: String,
+    pub note_id: Option<String>,
+    pub title: String,
+    pub due_at: String,
+    pub recurring_days: Option<i64>,
+    pub notify_before_minutes: Option<i64>,
+}
+
+fn validate_notify_before(minutes: i64) -> Result<i64, String> {
+    // Up to 30 days ahead.
+    if (0..=43_200).contains(&minutes) {
+        Ok(minutes)
+    } else {
+        Err("notify_before_minutes must be between 0 and 43200".to_string())
+    }
+}
+
+#[tauri::command]
+pub fn reminder_list(db: State<DbState>, exclude_dnc: Option<bool>) -> Result<Vec<Reminder>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, notify_before_minutes, created_at
+             FROM reminders WHERE completed_at IS NULL
+               AND contact_id NOT IN (SELECT id FROM contacts WHERE deleted_at IS NOT NULL)
+               AND (?1 = 0 OR contact_id NOT IN (SELECT id FROM contacts WHERE do_not_contact = 1))
+             ORDER BY due_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![exclude_dnc.unwrap_or(false)], row_to_reminder)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn reminder_create(db: State<DbState>, input: CreateReminderInput) -> Result<Reminder, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    insert_reminder(conn, input)
+}
+
+fn insert_reminder(conn: &rusqlite::Connection, input: CreateReminderInput) -> Result<Reminder, String> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let notify_before_minutes = validate_notify_before(input.notify_before_minutes.unwrap_or(0))?;
+    conn.execute(
+        "INSERT INTO reminders (id, contact_id, note_id, title, due_at, recurring_days, notify_before_minutes, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+        params![
+            id,
+            input.contact_id,
+            input.note_id,
+            input.title,
+            input.due_at,
+            input.recurring_days,
+            notify_before_minutes,
+            now,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    refresh_next_touch(conn, &input.contact_id, &now)?;
+    let mut stmt = conn
+        .prepare("SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, notify_before_minutes, created_at FROM reminders WHERE id = ?1")
+        .map_err(|e| e.to_string())?;
+    let mut row = stmt
+        .query_row(params![id], row_to_reminder)
+        .map_err(|e| e.to_string())?;
+    let dnc: bool = conn
+        .query_row(
+            "SELECT do_not_contact FROM contacts WHERE id = ?1",
+            params![row.contact_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if dnc {
+        row.warning = Some("Bu kişi \"do not contact\" olarak işaretli".to_string());
+    }
+    Ok(row)
+}
+
+/// Dates (YYYY-MM-DD) skipped by reminder_create_business_days, from the `reminder_holidays` setting.
+fn reminder_holidays(conn: &rusqlite::Connection) -> Result<Vec<chrono::NaiveDate>, String> {
+    let Some(raw) = setting_get(conn, "reminder_holidays")? else { return Ok(Vec::new()); };
+    let list = serde_json::from_str::<Vec<String>>(&raw).unwrap_or_default();
+    Ok(list
+        .iter()
+        .filter_map(|d| chrono::NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok())
+        .collect())
+}
+
+/// Moves forward `days` working days, skipping weekends and `holidays`.
+fn add_business_days(
+    start: chrono::NaiveDate,
+    days: u32,
+    holidays: &[chrono::NaiveDate],
+) -> chrono::NaiveDate {
+    use chrono::{Datelike, Weekday};
+    let mut date = start;
+    let mut left = days;
+    while left > 0 {
+        date = date.succ_opt().unwrap_or(date);
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        if !weekend && !holidays.contains(&date) {
+            left -= 1;
+        }
+    }
+    date
+}
+
+#[tauri::command]
+pub fn reminder_holidays_get(db: State<DbState>) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    Ok(reminder_holidays(conn)?
+        .iter()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect())
+}
+
+/// Replaces the holiday list; each entry must be YYYY-MM-DD.
+#[tauri::command]
+pub fn reminder_holidays_set(db: State<DbState>, dates: Vec<String>) -> Result<(), String> {
+    let mut parsed = Vec::new();
+    for d in dates.iter().map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        let date = chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| format!("Geçersiz tarih: {}", d))?;
+        parsed.push(date.format("%Y-%m-%d").to_string());
+    }
+    parsed.sort();
+    parsed.dedup();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let encoded = serde_json::to_string(&parsed).map_err(|e| e.to_string())?;
+    setting_set(conn, "reminder_holidays", &encoded)
+}
+
+/// Creates a reminder `business_days` working days from now (weekends and reminder_holidays skipped).
+#[tauri::command]
+pub fn reminder_create_business_days(
+    db: State<DbState>,
+    contact_id: String,
+    title: String,
+    business_days: i64,
+) -> Result<Reminder, String> {
+    if business_days < 1 {
+        return Err("İş günü sayısı pozitif olmalı".to_string());
+    }
+    let days = u32::try_from(business_days).map_err(|_| "İş günü sayısı çok büyük".to_string())?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let now = Utc::now();
+    let due_date = add_business_days(now.date_naive(), days, &reminder_holidays(conn)?);
+    let due_at = due_date.and_time(now.time()).and_utc().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    insert_reminder(
+        conn,
+        CreateReminderInput {
+            contact_id,
+            note_id: None,
+            title,
+            due_at,
+            recurring_days: None,
+            notify_before_minutes: None,
+        },
+    )
+}
+
+/// `completed_at` (optional) backdates the completion; it may not be in the future.
+#[tauri::command]
+pub fn reminder_complete(
+    db: State<DbState>,
+    id: String,
+    completed_at: Option<String>,
+) -> Result<(), String> {
+    let now_dt = Utc::now();
+    let now = now_dt.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let done_dt = match completed_at.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(v) => {
+            let dt = parse_datetime_utc(v).ok_or_else(|| "Geçersiz tamamlanma tarihi".to_string())?;
+            if dt > now_dt {
+                return Err("Tamamlanma tarihi gelecekte olamaz".to_string());
+            }
+            dt
+        }
+        None => now_dt,
+    };
+    let done = done_dt.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    // Get reminder for recurring and contact_id (D2.3: update contact last_touched_at / next_touch_at)
+    let row = conn
+        .query_row(
+            "SELECT contact_id, note_id, title, recurring_days, notify_before_minutes FROM reminders WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let contact_id: Option<String> = row.as_ref().map(|r| r.0.clone());
+
+    conn.execute(
+        "UPDATE reminders SET completed_at = ?1, updated_at = ?2 WHERE id = ?3",
+        params![done, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // D2.3: Action tamamlandı → Last touched güncellenir (a backdated completion never moves it back).
+    // Compared as datetimes: stored values mix offsets, 'Z' and 'YYYY-MM-DD HH:MM:SS'.
+    if let Some(ref cid) = contact_id {
+        conn.execute(
+            "UPDATE contacts SET last_touched_at = CASE
+                WHEN datetime(last_touched_at) IS NULL OR datetime(last_touched_at) < datetime(?1) THEN ?1
+                ELSE last_touched_at END,
+             updated_at = ?2 WHERE id = ?3",
+            params![done, now, cid],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // D1.4: "Her X günde bir" — create next reminder if recurring_days set
+    let next_due_at: Option<String> = if let Some((contact_id, note_id, title, Some(recurring_days), notify_before)) = row {
+        if recurring_days > 0 {
+            let next_id = Uuid::new_v4().to_string();
+            let due = done_dt + chrono::Duration::days(recurring_days);
+            let due_at = due.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+            let _ = conn.execute(
+                "INSERT INTO reminders (id, contact_id, note_id, title, due_at, recurring_days, notify_before_minutes, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+                params![next_id, contact_id, note_id, title, due_at, recurring_days, notify_before, now],
+            );
+            Some(due_at)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    // D2.3: next action temizlenir veya yeni tarih (recurring ise next_touch_at = yeni due_at)
+    if let Some(ref cid) = contact_id {
+        let next_touch: Option<&str> = next_due_at.as_deref();
+        conn.execute(
+            "UPDATE contacts SET next_touch_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![next_touch, now, cid],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// D2.3: next_touch_at = the earliest open reminder (snooze-aware), or NULL when none is left.
+fn refresh_next_touch(conn: &rusqlite::Connection, contact_id: &str, now: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE contacts SET next_touch_at = (
+            SELECT due_at FROM reminders
+            WHERE contact_id = ?1 AND completed_at IS NULL
+            ORDER BY datetime(COALESCE(snooze_until, due_at)) LIMIT 1
+         ), updated_at = ?2 WHERE id = ?1",
+        params![contact_id, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves all open reminders from one contact to another; returns how many were moved.
+#[tauri::command]
+pub fn reminders_reassign(
+    db: State<DbState>,
+    from_contact_id: String,
+    to_contact_id: String,
+) -> Result<usize, String> {
+    if from_contact_id == to_contact_id {
+        return Ok(0);
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for id in [&from_contact_id, &to_contact_id] {
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM contacts WHERE id = ?1)",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("Contact not found: {}", id));
+        }
+    }
+    let moved = tx
+        .execute(
+            "UPDATE reminders SET contact_id = ?1, updated_at = ?3 WHERE contact_id = ?2 AND completed_at IS NULL",
+            params![to_contact_id, from_contact_id, now],
+        )
+        .map_err(|e| e.to_string())?;
+    refresh_next_touch(&tx, &from_contact_id, &now)?;
+    refresh_next_touch(&tx, &to_contact_id, &now)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(moved)
+}
+
+#[tauri::command]
+pub fn reminder_snooze(db: State<DbState>, id: String, until: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    conn.execute(
+        "UPDATE reminders SET snooze_until = ?1, updated_at = ?2 WHERE id = ?3",
+        params![until, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// D1.1: Change how long before due_at the OS notification fires.
+#[tauri::command]
+pub fn reminder_set_notify_before(db: State<DbState>, id: String, minutes: i64) -> Result<(), String> {
+    let minutes = validate_notify_before(minutes)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let changed = conn
+        .execute(
+            "UPDATE reminders SET notify_before_minutes = ?1, updated_at = ?2 WHERE id = ?3",
+            params![minutes, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Reminder not found".to_string());
+    }
+    Ok(())
+}
+
+/// `recurring_days` None makes it one-off. The contact's next_touch_at is recomputed from all
+/// pending reminders, since the edited one may no longer be the earliest.
+#[tauri::command]
+pub fn reminder_update(
+    db: State<DbState>,
+    id: String,
+    title: String,
+    due_at: String,
+    recurring_days: Option<i64>,
+) -> Result<Reminder, String> {
+    let title = title.trim().to_string();
+    if title.is_empty() {
+        return Err("Başlık boş olamaz".to_string());
+    }
+    if parse_datetime_utc(&due_at).is_none() {
+        return Err("Geçersiz tarih".to_string());
+    }
+    if recurring_days.is_some_and(|d| d <= 0) {
+        return Err("recurring_days must be positive".to_string());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let (contact_id, old_due_at): (String, String) = conn
+        .query_row(
+            "SELECT contact_id, due_at FROM reminders WHERE id = ?1",
+            params![id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Reminder not found")?;
+    conn.execute(
+        "UPDATE reminders SET title = ?1, due_at = ?2, recurring_days = ?3, updated_at = ?4 WHERE id = ?5",
+        params![title, due_at, recurring_days, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    if old_due_at != due_at {
+        refresh_next_touch(conn, &contact_id, &now)?;
+    }
+    conn.query_row(
+        "SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, notify_before_minutes, created_at FROM reminders WHERE id = ?1",
+        params![id],
+        row_to_reminder,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Deletes the reminder and recomputes (or clears) the contact's next_touch_at.
+#[tauri::command]
+pub fn reminder_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let contact_id: String = conn
+        .query_row("SELECT contact_id FROM reminders WHERE id = ?1", params![id], |r| r.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Reminder not found")?;
+    conn.execute("DELETE FROM reminders WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    refresh_next_touch(conn, &contact_id, &now)
+}
+
+/// "overdue" | "today" | "this_week" (today + 6 days) or an explicit `{ from, to }` range.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ReminderWindow {
+    Named(String),
+    Range { from: String, to: String },
+}
+
+type WindowBounds = (Option<chrono::DateTime<Utc>>, Option<chrono::DateTime<Utc>>);
+
+/// [from, to) in UTC; None is unbounded on that side.
+fn reminder_window_bounds(window: &ReminderWindow, now: chrono::DateTime<Utc>) -> Result<WindowBounds, String> {
+    let today = now.date_naive().and_hms_opt(0, 0, 0).map(|d| d.and_utc()).unwrap_or(now);
+    match window {
+        ReminderWindow::Named(name) => match name.as_str() {
+            "overdue" => Ok((None, Some(now))),
+            "today" => Ok((Some(today), Some(today + chrono::Duration::days(1)))),
+            "this_week" => Ok((Some(today), Some(today + chrono::Duration::days(7)))),
+            other => Err(format!("Invalid window: {}", other)),
+        },
+        ReminderWindow::Range { from, to } => {
+            let from = parse_datetime_utc(from).ok_or_else(|| "Geçersiz tarih".to_string())?;
+            let to = parse_datetime_utc(to).ok_or_else(|| "Geçersiz tarih".to_string())?;
+            if to <= from {
+                return Err("Bitiş tarihi başlangıçtan sonra olmalı".to_string());
+            }
+            Ok((Some(from), Some(to)))
+        }
+    }
+}
+
+/// Open reminders whose effective due date (snooze_until when snoozed, else due_at) is in `window`.
+#[tauri::command]
+pub fn reminders_due(db: State<DbState>, window: ReminderWindow) -> Result<Vec<Reminder>, String> {
+    let (from, to) = reminder_window_bounds(&window, Utc::now())?;
+    let fmt = |dt: chrono::DateTime<Utc>| dt.format("%Y-%m-%d %H:%M:%S").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, notify_before_minutes, created_at
+             FROM reminders
+             WHERE completed_at IS NULL
+               AND contact_id NOT IN (SELECT id FROM contacts WHERE deleted_at IS NOT NULL)
+               AND (?1 IS NULL OR datetime(COALESCE(snooze_until, due_at)) >= ?1)
+               AND (?2 IS NULL OR datetime(COALESCE(snooze_until, due_at)) < ?2)
+             ORDER BY datetime(COALESCE(snooze_until, due_at)) ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from.map(fmt), to.map(fmt)], row_to_reminder)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecurringOccurrence {
+    pub reminder_id: String,
+    pub contact_id: String,
+    pub title: String,
+    pub due_at: String,
+    pub recurring_days: i64,
+}
+
+/// Projected (not inserted) dates for a recurring reminder up to `horizon`. Mirrors reminder_complete:
+/// each occurrence is `recurring_days` after the previous one is done, assuming it is done on time
+/// (or now, when the current one is already overdue).
+fn project_recurring(
+    due_at: chrono::DateTime<Utc>,
+    recurring_days: i64,
+    now: chrono::DateTime<Utc>,
+    horizon: chrono::DateTime<Utc>,
+) -> Vec<chrono::DateTime<Utc>> {
+    let mut out = Vec::new();
+    if recurring_days <= 0 {
+        return out;
+    }
+    let mut next = due_at.max(now) + chrono::Duration::days(recurring_days);
+    while next <= horizon {
+        out.push(next);
+        next += chrono::Duration::days(recurring_days);
+    }
+    out
+}
+
+/// D1.4: Virtual future occurrences of every open recurring reminder over the next `days` days.
+#[tauri::command]
+pub fn recurring_forecast(db: State<DbState>, days: i64) -> Result<Vec<RecurringOccurrence>, String> {
+    if !(1..=3660).contains(&days) {
+        return Err("days must be between 1 and 3660".to_string());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    recurring_forecast_rows(conn, Utc::now(), days)
+}
+
+fn recurring_forecast_rows(
+    conn: &rusqlite::Connection,
+    now: chrono::DateTime<Utc>,
+    days: i64,
+) -> Result<Vec<RecurringOccurrence>, String> {
+    let horizon = now + chrono::Duration::days(days);
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.contact_id, r.note_id, r.title, r.due_at, r.snooze_until, r.recurring_days, r.completed_at,
+                    r.notify_before_minutes, r.created_at
+             FROM reminders r JOIN contacts c ON c.id = r.contact_id AND c.deleted_at IS NULL
+             WHERE r.completed_at IS NULL AND r.recurring_days > 0",
+        )
+        .map_err(|e| e.to_string())?;
+    let reminders: Vec<Reminder> = stmt
+        .query_map([], row_to_reminder)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    let mut out = Vec::new();
+    for r in reminders {
+        let (Some(due), Some(recurring_days)) = (parse_datetime_utc(&r.due_at), r.recurring_days) else {
+            continue;
+        };
+        for at in project_recurring(due, recurring_days, now, horizon) {
+            out.push(RecurringOccurrence {
+                reminder_id: r.id.clone(),
+                contact_id: r.contact_id.clone(),
+                title: r.title.clone(),
+                due_at: at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                recurring_days,
+            });
+        }
+    }
+    out.sort_by(|a, b| a.due_at.cmp(&b.due_at));
+    Ok(out)
+}
+
+/// D2.2: Uncompleted reminders due more than `days` days ago — the "ancient overdue" backlog for bulk cleanup.
+#[tauri::command]
+pub fn reminders_stale(db: State<DbState>, days: i64) -> Result<Vec<Reminder>, String> {
+    if days < 0 {
+        return Err("days must be >= 0".to_string());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    reminders_stale_rows(conn, days)
+}
+
+fn reminders_stale_rows(conn: &rusqlite::Connection, days: i64) -> Result<Vec<Reminder>, String> {
+    let modifier = format!("-{} days", days);
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.contact_id, r.note_id, r.title, r.due_at, r.snooze_until, r.recurring_days, r.completed_at,
+                    r.notify_before_minutes, r.created_at
+             FROM reminders r JOIN contacts c ON c.id = r.contact_id AND c.deleted_at IS NULL
+             WHERE r.completed_at IS NULL AND datetime(r.due_at) < datetime('now', ?1)
+             ORDER BY r.due_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![modifier], row_to_reminder)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// ---- D2.2 Weekly review digest ----
+
+#[derive(Debug, Serialize)]
+pub struct UpcomingDate {
+    pub contact_id: String,
+    pub contact_name: String,
+    pub field_id: String,
+    pub field_name: String,
+    /// Stored value (YYYY-MM-DD).
+    pub value: String,
+    /// Next anniversary of `value` (YYYY-MM-DD).
+    pub next_occurrence: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeeklyDigest {
+    pub overdue_reminders: Vec<Reminder>,
+    pub going_cold: Vec<Contact>,
+    pub upcoming_dates: Vec<UpcomingDate>,
+    pub new_contacts: Vec<Contact>,
+    pub recent_interactions: Vec<Interaction>,
+}
+
+/// Next yearly occurrence of a YYYY-MM-DD date on or after `today`; Feb 29 falls back to Feb 28.
+fn next_annual_occurrence(value: &str, today: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+    use chrono::Datelike;
+    let date = chrono::NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+    let in_year = |year: i32| {
+        chrono::NaiveDate::from_ymd_opt(year, date.month(), date.day())
+            .or_else(|| chrono::NaiveDate::from_ymd_opt(year, date.month(), date.day() - 1))
+    };
+    let this_year = in_year(today.year())?;
+    if this_year >= today {
+        Some(this_year)
+    } else {
+        in_year(today.year() + 1)
+    }
+}
+
+/// D2.2: "Monday review" payload — overdue reminders, contacts crossing `cold_days` (default 30) within
+/// the next week, date custom fields recurring in the next 7 days, contacts added and interactions
+/// logged in the past 7 days.
+#[tauri::command]
+pub fn weekly_digest(
+    db: State<DbState>,
+    cold_days: Option<i64>,
+    exclude_dnc: Option<bool>,
+) -> Result<WeeklyDigest, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    build_weekly_digest(conn, cold_days, exclude_dnc)
+}
+
+fn build_weekly_digest(
+    conn: &rusqlite::Connection,
+    cold_days: Option<i64>,
+    exclude_dnc: Option<bool>,
+) -> Result<WeeklyDigest, String> {
+    let cold_days = cold_days.unwrap_or(30).max(7);
+    let exclude_dnc = exclude_dnc.unwrap_or(false);
+
+    let overdue_reminders: Vec<Reminder> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, contact_id, note_id, title, due_at, snooze_until, recurring_days, completed_at, notify_before_minutes, created_at
+                 FROM reminders
+                 WHERE completed_at IS NULL AND datetime(COALESCE(NULLIF(snooze_until, ''), due_at)) < datetime('now')
+                   AND contact_id NOT IN (SELECT id FROM contacts WHERE deleted_at IS NOT NULL)
+                   AND (?1 = 0 OR contact_id NOT IN (SELECT id FROM contacts WHERE do_not_contact = 1))
+                 ORDER BY due_at ASC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![exclude_dnc], row_to_reminder)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    // Last touched between cold_days and cold_days - 7 days ago: turns cold during the coming week.
+    let going_cold: Vec<Contact> = {
+        let sql = format!(
+            "{} WHERE c.deleted_at IS NULL
+               AND datetime(c.last_touched_at) <= datetime('now', ?1)
+               AND datetime(c.last_touched_at) > datetime('now', ?2)
+               AND (?3 = 0 OR c.do_not_contact = 0)
+             ORDER BY c.last_touched_at ASC",
+            CONTACT_SELECT
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![
+                    format!("-{} days", cold_days - 7),
+                    format!("-{} days", cold_days),
+                    exclude_dnc
+                ],
+                row_to_contact,
+            )
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    let upcoming_dates: Vec<UpcomingDate> = {
+        let today = Utc::now().date_naive();
+        let horizon = today + chrono::Duration::days(7);
+        let mut stmt = conn
+            .prepare(
+                "SELECT v.contact_id, c.first_name, c.last_name, f.id, f.name, v.value
+                 FROM contact_custom_values v
+                 JOIN custom_fields f ON f.id = v.field_id
+                 JOIN contacts c ON c.id = v.contact_id AND c.deleted_at IS NULL
+                 WHERE f.kind = 'date' AND f.scope = 'contact' AND v.value IS NOT NULL AND v.value != ''",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut list: Vec<UpcomingDate> = rows
+            .filter_map(|r| r.ok())
+            .filter_map(|(contact_id, first, last, field_id, field_name, value)| {
+                let next = next_annual_occurrence(&value, today)?;
+                if next > horizon {
+                    return None;
+                }
+                Some(UpcomingDate {
+                    contact_id,
+                    contact_name: format!("{} {}", first, last).trim().to_string(),
+                    field_id,
+                    field_name,
+                    value,
+                    next_occurrence: next.format("%Y-%m-%d").to_string(),
+                })
+            })
+            .collect();
+        list.sort_by(|a, b| a.next_occurrence.cmp(&b.next_occurrence));
+        list
+    };
+
+    let new_contacts: Vec<Contact> = {
+        let sql = format!(
+            "{} WHERE c.deleted_at IS NULL AND datetime(c.created_at) >= datetime('now', '-7 days')
+             ORDER BY c.created_at DESC",
+            CONTACT_SELECT
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], row_to_contact)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    let recent_interactions: Vec<Interaction> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, contact_id, kind, happened_at, summary, created_at FROM interactions
+                 WHERE datetime(happened_at) >= datetime('now', '-7 days')
+                   AND datetime(happened_at) <= datetime('now')
+                   AND contact_id NOT IN (SELECT id FROM contacts WHERE deleted_at IS NOT NULL)
+                 ORDER BY happened_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], row_to_interaction)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    Ok(WeeklyDigest {
+        overdue_reminders,
+        going_cold,
+        upcoming_dates,
+        new_contacts,
+        recent_interactions,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct StaleContact {
+    #[serde(flatten)]
+    pub contact: Contact,
+    /// None when the contact was never touched.
+    pub days_since_touch: Option<i64>,
+}
+
+/// Whole days between `last_touched_at` and `now`; None for missing or unparseable values.
+fn days_since_touch(last_touched_at: Option<&str>, now: chrono::DateTime<Utc>) -> Option<i64> {
+    let touched = parse_datetime_utc(last_touched_at?.trim())?;
+    Some((now - touched).num_days().max(0))
+}
+
+/// "Reconnect" list: live contacts not touched for more than `days` days (or never), never-touched
+/// first and then oldest first; optionally only contacts carrying `tag_id`.
+#[tauri::command]
+pub fn contacts_stale(db: State<DbState>, days: i64, tag_id: Option<String>) -> Result<Vec<StaleContact>, String> {
+    if days < 0 {
+        return Err("days must be >= 0".to_string());
+    }
+    let now = Utc::now();
+    let cutoff = (now - chrono::Duration::days(days)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!(
+        "{} WHERE c.deleted_at IS NULL
+           AND (NULLIF(c.last_touched_at, '') IS NULL OR datetime(c.last_touched_at) < datetime(?1))
+           AND (?2 IS NULL OR c.id IN (SELECT contact_id FROM contact_tags WHERE tag_id = ?2))
+         ORDER BY NULLIF(c.last_touched_at, '') IS NOT NULL, datetime(c.last_touched_at) ASC, c.id",
+        CONTACT_SELECT
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![cutoff, tag_id], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .filter_map(|r| r.ok())
+        .map(|contact| StaleContact {
+            days_since_touch: days_since_touch(contact.last_touched_at.as_deref(), now),
+            contact,
+        })
+        .collect())
+}
+
+// ---- Periodic review ----
+
+const REVIEW_DEFAULT_CADENCE_DAYS: i64 = 7;
+
+#[derive(Debug, Serialize)]
+pub struct ReviewStatus {
+    /// None until the first review is marked done.
+    pub next_review_at: Option<String>,
+    pub cadence_days: i64,
+    /// True when `next_review_at` has passed or no review has been scheduled yet.
+    pub due: bool,
+    pub digest: WeeklyDigest,
+}
+
+fn review_cadence_days(conn: &rusqlite::Connection) -> Result<i64, String> {
+    Ok(setting_get(conn, "review_cadence_days")?
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|d| *d > 0)
+        .unwrap_or(REVIEW_DEFAULT_CADENCE_DAYS))
+}
+
+/// Advances `previous` by at least one cadence and until it lies after `now`, keeping the
+/// review on its usual weekday; without a previous date the next review is `now + cadence`.
+fn next_review_after(
+    previous: Option<chrono::DateTime<Utc>>,
+    cadence_days: i64,
+    now: chrono::DateTime<Utc>,
+) -> chrono::DateTime<Utc> {
+    let step = chrono::Duration::days(cadence_days.max(1));
+    match previous {
+        Some(previous) => {
+            let mut next = previous + step;
+            while next <= now {
+                next += step;
+            }
+            next
+        }
+        None => now + step,
+    }
+}
+
+/// Whether the periodic review is due, along with the weekly digest to review.
+#[tauri::command]
+pub fn review_due(
+    db: State<DbState>,
+    cold_days: Option<i64>,
+    exclude_dnc: Option<bool>,
+) -> Result<ReviewStatus, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let next_review_at = setting_get(conn, "next_review_at")?.filter(|v| !v.is_empty());
+    let due = match next_review_at.as_deref().and_then(parse_datetime_utc) {
+        Some(at) => at <= Utc::now(),
+        None => true,
+    };
+    Ok(ReviewStatus {
+        next_review_at,
+        cadence_days: review_cadence_days(conn)?,
+        due,
+        digest: build_weekly_digest(conn, cold_days, exclude_dnc)?,
+    })
+}
+
+/// Marks the review done and schedules the next one. `cadence_days`, when given, is saved
+/// as the new cadence. Returns the new `next_review_at`.
+#[tauri::command]
+pub fn review_mark_done(db: State<DbState>, cadence_days: Option<i64>) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    if let Some(days) = cadence_days {
+        if days < 1 {
+            return Err("Gözden geçirme aralığı en az 1 gün olmalı".to_string());
+        }
+        setting_set(conn, "review_cadence_days", &days.to_string())?;
+    }
+    let cadence = review_cadence_days(conn)?;
+    let previous = setting_get(conn, "next_review_at")?
+        .as_deref()
+        .and_then(parse_datetime_utc);
+    let next = next_review_after(previous, cadence, Utc::now())
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    setting_set(conn, "next_review_at", &next)?;
+    Ok(next)
+}
+
+// ---- Attachments (A6) ----
+
+#[tauri::command]
+pub fn attachments_dir_get(db: State<DbState>) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_get(conn, "attachments_dir")?
+        .ok_or_else(|| "Attachments dir not set".to_string())
+}
+
+#[tauri::command]
+pub fn attachments_dir_set(db: State<DbState>, path: String) -> Result<(), String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Path is empty".to_string());
+    }
+    let dir = PathBuf::from(path);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, "attachments_dir", path)
+}
+
+// ---- F3 Backup (F3.1 auto versioned, F3.2 user folder) ----
+
+/// Default retention; overridden by the backup_keep_count setting.
+const BACKUP_KEEP_COUNT: usize = 7;
+const BACKUP_PREFIX: &str = "vault-backup-";
+const BACKUP_SUFFIX: &str = ".encrypted";
+
+/// F3.1: Create versioned backup; F3.2: also copy to user backup_dir if set. Call after flush on window close.
+/// Returns the versioned backup path. Copies to backup_dir/sync_folder are all attempted; their failures
+/// are reported together afterwards.
+pub fn run_backup(
+    app: &tauri::AppHandle,
+    conn: &rusqlite::Connection,
+    encrypted_path: &Path,
+) -> Result<PathBuf, String> {
+    let app_data = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&app_data).map_err(|e| e.to_string())?;
+    let backups_dir = app_data.join("backups");
+    std::fs::create_dir_all(&backups_dir).map_err(|e| e.to_string())?;
+
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let name = format!("{}{}{}", BACKUP_PREFIX, timestamp, BACKUP_SUFFIX);
+    let dest = backups_dir.join(&name);
+    std::fs::copy(encrypted_path, &dest).map_err(|e| e.to_string())?;
+
+    let keep = backup_keep_count(conn)?;
+    prune_backups_in_dir(&backups_dir, keep)?;
+
+    let mut errors: Vec<String> = Vec::new();
+    if let Some(extra) = setting_get(conn, "backup_dir")? {
+        let extra_path = PathBuf::from(extra.trim());
+        if !extra_path.as_os_str().is_empty() {
+            let copied = std::fs::create_dir_all(&extra_path)
+                .and_then(|_| std::fs::copy(encrypted_path, extra_path.join(&name)));
+            match copied {
+                Ok(_) => {
+                    prune_backups_in_dir(&extra_path, keep).ok();
+                }
+                Err(e) => errors.push(format!("Yedek klasörü ({}): {}", extra_path.display(), e)),
+            }
+        }
+    }
+    // G1.2: Write encrypted DB to sync folder (fixed name; format documented).
+    if let Some(sync_dir) = setting_get(conn, "sync_folder")? {
+        let sync_path = PathBuf::from(sync_dir.trim());
+        if !sync_path.as_os_str().is_empty() {
+            let copied = std::fs::create_dir_all(&sync_path)
+                .and_then(|_| std::fs::copy(encrypted_path, sync_path.join(VAULT_SYNC_NAME)));
+            if let Err(e) = copied {
+                errors.push(format!("Senkron klasörü ({}): {}", sync_path.display(), e));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    Ok(dest)
+}
+
+/// Flushes the open DB to the encrypted file and runs a backup right away (e.g. before a big
+/// import). Returns the path of the versioned backup.
+#[tauri::command]
+pub fn backup_now(
+    app: tauri::AppHandle,
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let paths = paths.0.lock().map_err(|e| e.to_string())?;
+    let (temp_path, encrypted_path) = paths
+        .as_ref()
+        .ok_or("Şifreleme kapalıyken yedek alınamaz")?;
+    crate::db::flush_encrypted_db(conn, temp_path, encrypted_path)?;
+    let dest = run_backup(&app, conn, encrypted_path)?;
+    Ok(dest.to_string_lossy().into_owned())
+}
+
+fn backup_keep_count(conn: &rusqlite::Connection) -> Result<usize, String> {
+    Ok(setting_get(conn, "backup_keep_count")?
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n >= 1)
+        .unwrap_or(BACKUP_KEEP_COUNT))
+}
+
+#[tauri::command]
+pub fn backup_keep_count_get(db: State<DbState>) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    backup_keep_count(conn)
+}
+
+/// Number of versioned backups kept in the app-data backups dir and in backup_dir.
+#[tauri::command]
+pub fn backup_keep_count_set(db: State<DbState>, count: usize) -> Result<(), String> {
+    if count < 1 {
+        return Err("En az 1 yedek tutulmalı".to_string());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, "backup_keep_count", &count.to_string())
+}
+
+fn prune_backups_in_dir(dir: &Path, keep: usize) -> Result<(), String> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.path()
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(BACKUP_PREFIX) && n.ends_with(BACKUP_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.path()
+            .metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+            .cmp(
+                &a.path()
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
+            )
+    });
+    for e in entries.into_iter().skip(keep) {
+        let _ = std::fs::remove_file(e.path());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn backup_dir_get(db: State<DbState>) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    Ok(setting_get(conn, "backup_dir")?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn backup_dir_set(db: State<DbState>, path: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, "backup_dir", path.trim())
+}
+
+// ---- G1 Folder Sync (G1.1 folder, G1.2 write to sync, G1.3 open from sync) ----
+
+#[tauri::command]
+pub fn sync_folder_get(db: State<DbState>) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    Ok(setting_get(conn, "sync_folder")?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn sync_folder_set(db: State<DbState>, path: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, "sync_folder", path.trim())
+}
+
+/// G1.3: Copy vault-sync.encrypted from folder to app_data, derive key from passphrase, store key. Call encryption_setup_open_db after.
+#[tauri::command]
+pub fn open_from_sync_folder(app: tauri::AppHandle, folder_path: String, passphrase: String) -> Result<(), String> {
+    crate::db::open_from_sync_folder(&app, &folder_path, &passphrase)
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SyncMergeSummary {
+    /// Remote rows that did not exist locally.
+    pub inserted: u64,
+    /// Rows where the remote copy was newer and replaced the local one.
+    pub updated: u64,
+    /// Rows edited on both sides where the local copy was newer and kept.
+    pub conflicts: u64,
+}
+
+/// Tables merged by sync_merge: (table, primary key columns, version columns). The row version is the
+/// first non-null version column (older reminders and interactions have no updated_at); tables
+/// without one only gain missing rows. Attachments are excluded — their blobs and key live on the
+/// other device.
+const SYNC_MERGE_TABLES: [(&str, &[&str], &[&str]); 10] = [
+    ("companies", &["id"], &["updated_at"]),
+    ("contacts", &["id"], &["updated_at"]),
+    ("tags", &["id"], &[]),
+    ("custom_fields", &["id"], &[]),
+    ("contact_tags", &["contact_id", "tag_id"], &[]),
+    ("contact_custom_values", &["contact_id", "field_id"], &[]),
+    ("company_custom_values", &["company_id", "field_id"], &[]),
+    ("notes", &["id"], &["updated_at"]),
+    ("interactions", &["id"], &["updated_at", "created_at"]),
+    ("reminders", &["id"], &["updated_at", "completed_at", "created_at"]),
+];
+
+/// `datetime(...)` of a row's version as seen through `alias`.
+fn sync_version_sql(version: &[&str], alias: &str) -> String {
+    let cols: Vec<String> = version.iter().map(|c| format!("{alias}.{c}")).collect();
+    match cols.as_slice() {
+        [only] => format!("datetime({only})"),
+        _ => format!("datetime(COALESCE({}))", cols.join(", ")),
+    }
+}
+
+fn merge_attached_table(
+    conn: &rusqlite::Connection,
+    table: &str,
+    keys: &[&str],
+    version: &[&str],
+    summary: &mut SyncMergeSummary,
+) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(&format!("PRAGMA main.table_info({})", table))
+        .map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    drop(stmt);
+    let cols = columns.join(", ");
+    let join_on = keys
+        .iter()
+        .map(|k| format!("m.{k} = r.{k}"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let count = |sql: String| -> Result<u64, String> {
+        conn.query_row(&sql, [], |row| row.get::<_, i64>(0))
+            .map(|n| n as u64)
+            .map_err(|e| e.to_string())
+    };
+    summary.inserted += count(format!(
+        "SELECT COUNT(*) FROM remote.{table} r WHERE NOT EXISTS (SELECT 1 FROM main.{table} m WHERE {join_on})"
+    ))?;
+
+    if !version.is_empty() {
+        let (remote_version, local_version) = (sync_version_sql(version, "r"), sync_version_sql(version, "m"));
+        summary.updated += count(format!(
+            "SELECT COUNT(*) FROM remote.{table} r JOIN main.{table} m ON {join_on}
+             WHERE {remote_version} > {local_version}"
+        ))?;
+        summary.conflicts += count(format!(
+            "SELECT COUNT(*) FROM remote.{table} r JOIN main.{table} m ON {join_on}
+             WHERE {remote_version} < {local_version}"
+        ))?;
+        let set = columns
+            .iter()
+            .filter(|c| !keys.contains(&c.as_str()))
+            .map(|c| format!("{c} = excluded.{c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        conn.execute(
+            &format!(
+                "INSERT INTO main.{table} ({cols}) SELECT {cols} FROM remote.{table} WHERE true
+                 ON CONFLICT({}) DO UPDATE SET {set}
+                 WHERE {} > {}",
+                keys.join(", "),
+                sync_version_sql(version, "excluded"),
+                sync_version_sql(version, table),
+            ),
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+    } else {
+        conn.execute(
+            &format!("INSERT OR IGNORE INTO main.{table} ({cols}) SELECT {cols} FROM remote.{table}"),
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// G1.3: Merge the sync folder's vault into the local DB instead of replacing it. Companies, contacts,
+/// notes, interactions and reminders resolve newest-wins; other tables only gain rows missing locally.
+#[tauri::command]
+pub fn sync_merge(
+    app: tauri::AppHandle,
+    db: State<DbState>,
+    folder_path: String,
+    passphrase: String,
+) -> Result<SyncMergeSummary, String> {
+    let snapshot = crate::db::decrypt_sync_snapshot(&app, &folder_path, &passphrase)?;
+    let result = match db.0.lock() {
+        Ok(mut conn_guard) => match conn_guard.as_mut() {
+            Some(conn) => merge_snapshot(conn, &snapshot),
+            None => Err("DB not initialized".to_string()),
+        },
+        Err(e) => Err(e.to_string()),
+    };
+    let _ = std::fs::remove_file(&snapshot);
+    result
+}
+
+fn merge_snapshot(conn: &mut rusqlite::Connection, snapshot: &Path) -> Result<SyncMergeSummary, String> {
+    conn.execute(
+        "ATTACH DATABASE ?1 AS remote",
+        params![snapshot.to_string_lossy().to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+    let merged = (|| {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut summary = SyncMergeSummary::default();
+        for (table, keys, version) in SYNC_MERGE_TABLES {
+            merge_attached_table(&tx, table, keys, version, &mut summary)?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(summary)
+    })();
+    let _ = conn.execute("DETACH DATABASE remote", []);
+    merged
+}
+
+/// Per-owner attachment limits (app_settings); None = unlimited.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AttachmentQuota {
+    pub max_count: Option<i64>,
+    pub max_bytes: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentUsage {
+    pub count: i64,
+    pub total_bytes: i64,
+    pub quota: AttachmentQuota,
+}
+
+fn attachment_quota(conn: &rusqlite::Connection) -> Result<AttachmentQuota, String> {
+    let read = |key: &str| -> Result<Option<i64>, String> {
+        Ok(setting_get(conn, key)?
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .filter(|v| *v > 0))
+    };
+    Ok(AttachmentQuota {
+        max_count: read("attachment_quota_count")?,
+        max_bytes: read("attachment_quota_bytes")?,
+    })
+}
+
+/// Default per-file limit; overridable via the `attachment_max_bytes` setting.
+const DEFAULT_ATTACHMENT_MAX_BYTES: i64 = 25 * 1024 * 1024;
+
+fn attachment_max_bytes(conn: &rusqlite::Connection) -> Result<i64, String> {
+    Ok(setting_get(conn, "attachment_max_bytes")?
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_ATTACHMENT_MAX_BYTES))
+}
+
+fn attachment_size_error(size: i64, max: i64) -> Option<String> {
+    (size > max).then(|| {
+        format!(
+            "Dosya çok büyük: {:.1} MB (en fazla {:.1} MB)",
+            size as f64 / 1_048_576.0,
+            max as f64 / 1_048_576.0
+        )
+    })
+}
+
+fn attachment_usage(conn: &rusqlite::Connection, owner_type: &str, owner_id: &str) -> Result<(i64, i64), String> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM attachments WHERE owner_type = ?1 AND owner_id = ?2",
+        params![owner_type, owner_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Error message when adding `new_size` bytes would exceed the quota.
+fn attachment_quota_error(count: i64, total_bytes: i64, quota: &AttachmentQuota, new_size: i64) -> Option<String> {
+    if let Some(max) = quota.max_count {
+        if count + 1 > max {
+            return Some(format!("Ek kotası aşıldı: {} / {} dosya", count, max));
+        }
+    }
+    if let Some(max) = quota.max_bytes {
+        if total_bytes + new_size > max {
+            return Some(format!(
+                "Ek kotası aşıldı: {} / {} bayt kullanılıyor, yeni dosya {} bayt",
+                total_bytes, max, new_size
+            ));
+        }
+    }
+    None
+}
+
+#[tauri::command]
+pub fn attachment_quota_get(db: State<DbState>) -> Result<AttachmentQuota, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    attachment_quota(conn)
+}
+
+/// Sets the per-owner limits; None or 0 removes a limit.
+#[tauri::command]
+pub fn attachment_quota_set(db: State<DbState>, quota: AttachmentQuota) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    for (key, value) in [
+        ("attachment_quota_count", quota.max_count),
+        ("attachment_quota_bytes", quota.max_bytes),
+    ] {
+        match value.filter(|v| *v > 0) {
+            Some(v) => setting_set(conn, key, &v.to_string())?,
+            None => {
+                conn.execute("DELETE FROM app_settings WHERE key = ?1", params![key])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn attachment_max_bytes_get(db: State<DbState>) -> Result<i64, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    attachment_max_bytes(conn)
+}
+
+/// Sets the per-file size limit; None or 0 restores the default.
+#[tauri::command]
+pub fn attachment_max_bytes_set(db: State<DbState>, max_bytes: Option<i64>) -> Result<i64, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    match max_bytes.filter(|v| *v > 0) {
+        Some(v) => setting_set(conn, "attachment_max_bytes", &v.to_string())?,
+        None => {
+            conn.execute("DELETE FROM app_settings WHERE key = 'attachment_max_bytes'", [])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    attachment_max_bytes(conn)
+}
+
+#[tauri::command]
+pub fn attachments_total_size(db: State<DbState>, owner_type: String, owner_id: String) -> Result<i64, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    Ok(attachment_usage(conn, &owner_type, &owner_id)?.1)
+}
+
+/// Usage for one owner, or for every attachment in the vault when no owner is given.
+#[tauri::command]
+pub fn attachments_usage(
+    db: State<DbState>,
+    owner_type: Option<String>,
+    owner_id: Option<String>,
+) -> Result<AttachmentUsage, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let (count, total_bytes) = match (owner_type, owner_id) {
+        (Some(owner_type), Some(owner_id)) => attachment_usage(conn, &owner_type, &owner_id)?,
+        _ => conn
+            .query_row("SELECT COUNT(*), COALESCE(SUM(size), 0) FROM attachments", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(|e| e.to_string())?,
+    };
+    Ok(AttachmentUsage {
+        count,
+        total_bytes,
+        quota: attachment_quota(conn)?,
+    })
+}
+
+#[tauri::command]
+pub fn attachment_list(
+    db: State<DbState>,
+    owner_type: String,
+    owner_id: String,
+) -> Result<Vec<Attachment>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, owner_type, owner_id, file_name, mime, size, storage_path, created_at
+             FROM attachments WHERE owner_type = ?1 AND owner_id = ?2 ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![owner_type, owner_id], |row| {
+            Ok(Attachment {
+                id: row.get(0)?,
+                owner_type: row.get(1)?,
+                owner_id: row.get(2)?,
+                file_name: row.get(3)?,
+                mime: row.get(4)?,
+                size: row.get(5)?,
+                storage_path: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn attachment_allowed_exts_get(db: State<DbState>) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    attachment_allowed_exts(conn)
+}
+
+/// Replaces the attachment allowlist; an empty list restores the defaults. Executables are rejected.
+#[tauri::command]
+pub fn attachment_allowed_exts_set(db: State<DbState>, exts: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for ext in &exts {
+        let ext = normalize_attachment_ext(ext);
+        if ext.is_empty() || normalized.contains(&ext) {
+            continue;
+        }
+        if !ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("Geçersiz dosya uzantısı: {}", ext));
+        }
+        if BLOCKED_ATTACHMENT_EXTS.contains(&ext.as_str()) {
+            return Err(format!("Çalıştırılabilir dosya türüne izin verilemez: .{}", ext));
+        }
+        normalized.push(ext);
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let encoded = serde_json::to_string(&normalized).map_err(|e| e.to_string())?;
+    setting_set(conn, "attachment_allowed_exts", &encoded)?;
+    attachment_allowed_exts(conn)
+}
+
+#[tauri::command]
+pub fn attachment_add(db: State<DbState>, input: AttachmentCreateInput) -> Result<Attachment, String> {
+    if !matches!(input.owner_type.as_str(), "contact" | "company" | "note" | "interaction") {
+        return Err("Invalid owner_type".to_string());
+    }
+    let file_name = sanitize_file_name(&input.file_name);
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    if !is_allowed_attachment(&file_name, &attachment_allowed_exts(conn)?) {
+        return Err("Desteklenmeyen dosya formatı".to_string());
+    }
+    let size = input.bytes.len() as i64;
+    if let Some(err) = attachment_size_error(size, attachment_max_bytes(conn)?) {
+        return Err(err);
+    }
+    let (count, total_bytes) = attachment_usage(conn, &input.owner_type, &input.owner_id)?;
+    if let Some(err) = attachment_quota_error(count, total_bytes, &attachment_quota(conn)?, size) {
+        return Err(err);
+    }
+    let key = attachments_key(conn)?;
+    let dir = attachments_dir(conn)?;
+    let id = Uuid::new_v4().to_string();
+    let encrypted = encrypt_bytes(&key, &input.bytes)?;
+    let path = dir.join(format!("{}.bin", id));
+    std::fs::write(&path, encrypted).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO attachments (id, owner_type, owner_id, file_name, mime, size, storage_path, encrypted, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8)",
+        params![
+            id,
+            input.owner_type,
+            input.owner_id,
+            file_name,
+            input.mime,
+            size,
+            path.to_string_lossy().to_string(),
+            now,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Attachment {
+        id,
+        owner_type: input.owner_type,
+        owner_id: input.owner_id,
+        file_name,
+        mime: input.mime,
+        size: Some(size),
+        storage_path: path.to_string_lossy().to_string(),
+        created_at: now,
+    })
+}
+
+#[tauri::command]
+pub fn attachment_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let row: Option<(String,)> = conn
+        .query_row(
+            "SELECT storage_path FROM attachments WHERE id = ?1",
+            params![id],
+            |r| Ok((r.get(0)?,)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM attachments WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    if let Some((path,)) = row {
+        remove_attachment_files(&[path]);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn attachment_open(db: State<DbState>, id: String) -> Result<String, String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT storage_path, file_name FROM attachments WHERE id = ?1",
+            params![id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (path, file_name) = row.ok_or_else(|| "Attachment not found".to_string())?;
+    let encrypted = std::fs::read(path).map_err(|e| e.to_string())?;
+    let key = attachments_key(conn)?;
+    let decrypted = decrypt_bytes(&key, &encrypted)?;
+    let app_data = setting_get(conn, "app_data_dir")?
+        .ok_or_else(|| "app_data_dir not set".to_string())?;
+    let tmp_dir = Path::new(&app_data).join("tmp");
+    std::fs::create_dir_all(&tmp_dir).map_err(|e| e.to_string())?;
+    let safe_name = sanitize_file_name(&file_name);
+    let out_path = tmp_dir.join(format!("{}_{}", id, safe_name));
+    std::fs::write(&out_path, decrypted).map_err(|e| e.to_string())?;
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentVerifyReport {
+    pub healthy: u64,
+    /// Missing blob or failed AES-GCM authentication.
+    pub failed_ids: Vec<String>,
+}
+
+/// A6.3: Integrity scan — decrypt every attachment blob in memory (nothing is written to disk).
+#[tauri::command]
+pub fn attachments_verify(db: State<DbState>) -> Result<AttachmentVerifyReport, String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let key = attachments_key(conn)?;
+    let mut stmt = conn
+        .prepare("SELECT id, storage_path FROM attachments ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(String, String)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    let mut report = AttachmentVerifyReport {
+        healthy: 0,
+        failed_ids: Vec::new(),
+    };
+    for (id, path) in rows {
+        let ok = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| decrypt_bytes(&key, &bytes))
+            .is_ok();
+        if ok {
+            report.healthy += 1;
+        } else {
+            report.failed_ids.push(id);
+        }
+    }
+    Ok(report)
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentsKeyStatus {
+    /// "stored" | "derived"
+    pub source: String,
+    /// A usable key exists (stored key present, or master key available to derive from).
+    pub exists: bool,
+    /// The key sits base64 in app_settings, i.e. inside the DB next to the data it protects.
+    pub stored_in_db: bool,
+}
+
+#[tauri::command]
+pub fn attachments_key_status(db: State<DbState>) -> Result<AttachmentsKeyStatus, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let stored = setting_get(conn, "attachments_key")?
+        .and_then(|v| general_purpose::STANDARD.decode(v.as_bytes()).ok())
+        .is_some_and(|k| k.len() == 32);
+    let derived = attachments_key_derived(conn)?;
+    Ok(AttachmentsKeyStatus {
+        source: if derived { "derived" } else { "stored" }.to_string(),
+        exists: if derived {
+            crate::db::derive_subkey(ATTACHMENTS_KEY_INFO).is_ok()
+        } else {
+            stored
+        },
+        stored_in_db: stored,
+    })
+}
+
+/// Re-encrypts every attachment blob from `old_key` to `new_key`:
+/// 1. every file is staged as `.rekey` (a failure removes the staged files);
+/// 2. each original is moved to `.bak` and the staged file takes its place;
+/// 3. `persist` saves the key change (settings + flush of the encrypted DB);
+/// 4. only then are the `.bak` files deleted.
+///
+/// A failed rename or `persist` moves every swapped `.bak` back, so the files stay readable with
+/// `old_key`. Blobs that are missing or already unreadable are left as they are.
+fn reencrypt_attachments(
+    conn: &rusqlite::Connection,
+    old_key: &[u8],
+    new_key: &[u8],
+    persist: impl FnOnce() -> Result<(), String>,
+) -> Result<u64, String> {
+    reencrypt_attachments_with(conn, old_key, new_key, persist, |from, to| std::fs::rename(from, to))
+}
+
+fn reencrypt_attachments_with(
+    conn: &rusqlite::Connection,
+    old_key: &[u8],
+    new_key: &[u8],
+    persist: impl FnOnce() -> Result<(), String>,
+    mut rename: impl FnMut(&Path, &Path) -> std::io::Result<()>,
+) -> Result<u64, String> {
+    let mut stmt = conn
+        .prepare("SELECT storage_path FROM attachments")
+        .map_err(|e| e.to_string())?;
+    let paths: Vec<PathBuf> = stmt
+        .query_map([], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .map(PathBuf::from)
+        .collect();
+    let mut staged: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let stage_all = |staged: &mut Vec<(PathBuf, PathBuf)>| -> Result<(), String> {
+        for path in &paths {
+            let Ok(bytes) = std::fs::read(path) else { continue };
+            let Ok(plain) = decrypt_bytes(old_key, &bytes) else { continue };
+            let tmp = path.with_extension("rekey");
+            std::fs::write(&tmp, encrypt_bytes(new_key, &plain)?).map_err(|e| e.to_string())?;
+            staged.push((tmp, path.clone()));
+        }
+        Ok(())
+    };
+    if let Err(e) = stage_all(&mut staged) {
+        for (tmp, _) in &staged {
+            let _ = std::fs::remove_file(tmp);
+        }
+        return Err(e);
+    }
+    let count = staged.len() as u64;
+    // Originals whose `.bak` now holds the old ciphertext.
+    let mut swapped: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut failed = None;
+    for (tmp, path) in &staged {
+        let bak = path.with_extension("bak");
+        if let Err(e) = rename(path, &bak) {
+            failed = Some(e.to_string());
+            break;
+        }
+        if let Err(e) = rename(tmp, path) {
+            let _ = rename(&bak, path);
+            failed = Some(e.to_string());
+            break;
+        }
+        swapped.push((path.clone(), bak));
+    }
+    let result = match failed {
+        Some(e) => Err(e),
+        None => persist(),
+    };
+    if let Err(e) = result {
+        for (path, bak) in &swapped {
+            let _ = rename(bak, path);
+        }
+        for (tmp, _) in &staged {
+            let _ = std::fs::remove_file(tmp);
+        }
+        return Err(e);
+    }
+    for (_, bak) in &swapped {
+        let _ = std::fs::remove_file(bak);
+    }
+    Ok(count)
+}
+
+/// Writes attachments key settings (None deletes the key) and, for an encrypted vault, flushes
+/// them to the encrypted file at once so a crash can't bring back the old key. If the flush
+/// fails the previous values are put back.
+fn persist_attachments_key_settings(
+    conn: &rusqlite::Connection,
+    paths: Option<&(PathBuf, PathBuf)>,
+    values: &[(&str, Option<String>)],
+) -> Result<(), String> {
+    fn write(conn: &rusqlite::Connection, values: &[(&str, Option<String>)]) -> Result<(), String> {
+        for (key, value) in values {
+            match value {
+                Some(value) => setting_set(conn, key, value)?,
+                None => {
+                    conn.execute("DELETE FROM app_settings WHERE key = ?1", params![key])
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        Ok(())
+    }
+    let previous = values
+        .iter()
+        .map(|(key, _)| Ok((*key, setting_get(conn, key)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    write(conn, values)?;
+    if let Some((temp_path, encrypted_path)) = paths {
+        if let Err(e) = crate::db::flush_encrypted_db(conn, temp_path, encrypted_path) {
+            let _ = write(conn, &previous);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Stops storing the attachments key in the DB: derives it from the keychain master key (HKDF)
+/// instead and re-encrypts existing attachments. Returns the number of files re-encrypted.
+#[tauri::command]
+pub fn attachments_key_use_derived(db: State<DbState>, paths: State<EncryptedPathsState>) -> Result<u64, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let paths = paths.0.lock().map_err(|e| e.to_string())?;
+    if attachments_key_derived(conn)? {
+        return Err("Ek anahtarı zaten ana anahtardan türetiliyor".to_string());
+    }
+    let new_key = crate::db::derive_subkey(ATTACHMENTS_KEY_INFO)?;
+    let old_key = attachments_key(conn)?;
+    reencrypt_attachments(conn, &old_key, &new_key, || {
+        persist_attachments_key_settings(
+            conn,
+            paths.as_ref(),
+            &[("attachments_key_source", Some("derived".to_string())), ("attachments_key", None)],
+        )
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttachmentsRotateReport {
+    pub total: u64,
+    pub reencrypted: u64,
+    /// Missing or already unreadable blobs, left as they are.
+    pub skipped: u64,
+}
+
+/// Replaces the stored attachments key with a fresh random one and re-encrypts every blob. The new
+/// key is saved (and flushed to the encrypted DB) before the old blobs are deleted; any failure
+/// keeps the old key and the old files.
+#[tauri::command]
+pub fn attachments_rotate_key(
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+) -> Result<AttachmentsRotateReport, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let paths = paths.0.lock().map_err(|e| e.to_string())?;
+    if attachments_key_derived(conn)? {
+        return Err("Ek anahtarı ana anahtardan türetiliyor; passphrase değişince o da değişir".to_string());
+    }
+    let old_key = attachments_key(conn)?;
+    let mut new_key = vec![0u8; 32];
+    OsRng.fill_bytes(&mut new_key);
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM attachments", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    let reencrypted = reencrypt_attachments(conn, &old_key, &new_key, || {
+        persist_attachments_key_settings(
+            conn,
+            paths.as_ref(),
+            &[("attachments_key", Some(general_purpose::STANDARD.encode(&new_key)))],
+        )
+    })?;
+    Ok(AttachmentsRotateReport {
+        total: total as u64,
+        reencrypted,
+        skipped: (total as u64).saturating_sub(reencrypted),
+    })
+}
+
+/// After the master key changed: moves derived-key attachments from `old_key` to the new derived
+/// key. A failure rolls every swapped file back to `old_key`; since the master key no longer
+/// derives it, `old_key` is then stored (source back to "stored") and flushed.
+fn follow_master_key_change(
+    conn: &rusqlite::Connection,
+    paths: Option<&(PathBuf, PathBuf)>,
+    old_key: Option<Vec<u8>>,
+) -> Result<(), String> {
+    let Some(old_key) = old_key else { return Ok(()) };
+    // The source setting doesn't change on success, so there is nothing more to persist.
+    let rekeyed = crate::db::derive_subkey(ATTACHMENTS_KEY_INFO)
+        .and_then(|new_key| reencrypt_attachments(conn, &old_key, &new_key, || Ok(())));
+    if rekeyed.is_err() {
+        persist_attachments_key_settings(
+            conn,
+            paths,
+            &[
+                ("attachments_key", Some(general_purpose::STANDARD.encode(&old_key))),
+                ("attachments_key_source", Some("stored".to_string())),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// The current attachments key when it is derived from the master key, i.e. when a master key
+/// change has to re-encrypt attachments.
+fn derived_attachments_key(conn: &rusqlite::Connection) -> Result<Option<Vec<u8>>, String> {
+    if attachments_key_derived(conn)? {
+        Ok(Some(crate::db::derive_subkey(ATTACHMENTS_KEY_INFO)?))
+    } else {
+        Ok(None)
+    }
+}
+
+// ---- Import (CSV) ----
+// Frontend sends parsed rows; we create contacts. Dedup/merge can be added later.
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportRow {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub email: Option<String>,
+    pub email_secondary: Option<String>,
+    pub phone: Option<String>,
+    pub phone_secondary: Option<String>,
+    pub linkedin_url: Option<String>,
+    pub twitter_url: Option<String>,
+    pub website: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSkip {
+    /// 0-based index into the rows sent by the frontend.
+    pub row_index: usize,
+    pub reason: String,
+}
+
+/// What import_contacts does with a row matching an existing contact (email first, then phone).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Every valid row becomes a new contact.
+    #[default]
+    CreateAll,
+    /// Matched rows are reported in `skipped`.
+    SkipDuplicates,
+    /// Matched rows only fill fields that are empty on the existing contact.
+    UpdateExisting,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub inserted: u64,
+    /// Existing contacts that got empty fields filled (update_existing).
+    pub updated: u64,
+    pub skipped: Vec<ImportSkip>,
+    /// None when no row was inserted.
+    pub batch_id: Option<String>,
+    /// Company cards created for company names not found in the vault.
+    pub companies_created: u64,
+}
+
+/// Invalid rows are skipped and reported; the rest is written in one transaction (all or nothing).
+/// Inserted contacts are stamped with a new import batch (`source`, e.g. the file name) so the
+/// import can be undone with import_batch_undo; fields filled by update_existing are not undone.
+/// New contacts are linked to the company with the same name (case-insensitive), which is created
+/// when missing; `link_companies: false` keeps only the company text. Undo keeps created companies.
+#[tauri::command]
+pub fn import_contacts(
+    db: State<DbState>,
+    rows: Vec<ImportRow>,
+    source: Option<String>,
+    mode: Option<ImportMode>,
+    link_companies: Option<bool>,
+) -> Result<ImportResult, String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let rows = rows.into_iter().map(|row| (row, Vec::new())).collect();
+    import_rows(
+        conn,
+        rows,
+        source.as_deref(),
+        mode.unwrap_or_default(),
+        link_companies.unwrap_or(true),
+    )
+}
+
+/// Splits a raw CSV row using `column_map` (header -> target). Targets are ImportRow field names
+/// or `custom:<field_id>`; headers missing from the map or mapped to "" are dropped.
+fn map_import_row(
+    raw: HashMap<String, String>,
+    column_map: &HashMap<String, String>,
+) -> Result<(ImportRow, Vec<CustomValueInput>), String> {
+    let mut fields = serde_json::Map::new();
+    let mut custom = Vec::new();
+    for (header, value) in raw {
+        let Some(target) = column_map.get(&header).map(|t| t.trim()).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        if let Some(field_id) = target.strip_prefix("custom:") {
+            custom.push(CustomValueInput {
+                field_id: field_id.to_string(),
+                value: Some(value),
+            });
+        } else if CSV_EXPORT_COLUMNS.contains(&target) {
+            fields.insert(target.to_string(), serde_json::Value::String(value));
+        } else {
+            return Err(format!("Unknown import column target: {}", target));
+        }
+    }
+    let row = serde_json::from_value(serde_json::Value::Object(fields)).map_err(|e| e.to_string())?;
+    Ok((row, custom))
+}
+
+/// Like import_contacts, but rows are raw `header -> value` maps routed through `column_map`.
+/// `custom:<field_id>` targets are written to the contact's custom values with the same checks
+/// as contact_custom_values_set; a row with an invalid custom value is skipped.
+#[tauri::command]
+pub fn import_contacts_mapped(
+    db: State<DbState>,
+    rows: Vec<HashMap<String, String>>,
+    column_map: HashMap<String, String>,
+    source: Option<String>,
+    mode: Option<ImportMode>,
+    link_companies: Option<bool>,
+) -> Result<ImportResult, String> {
+    let rows = rows
+        .into_iter()
+        .map(|raw| map_import_row(raw, &column_map))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    import_rows(
+        conn,
+        rows,
+        source.as_deref(),
+        mode.unwrap_or_default(),
+        link_companies.unwrap_or(true),
+    )
+}
+
+/// company_name_key -> company id for every company; import adds the ones it creates.
+fn import_company_cache(conn: &rusqlite::Connection) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name FROM companies ORDER BY created_at, id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut cache = HashMap::new();
+    for (id, name) in rows.filter_map(|r| r.ok()) {
+        let key = company_name_key(&name);
+        if !key.is_empty() {
+            // Oldest wins when names collide.
+            cache.entry(key).or_insert(id);
+        }
+    }
+    Ok(cache)
+}
+
+/// Id of the company named `name`, creating it when the cache has no match.
+/// Returns None for a blank name; the bool tells whether a company was created.
+fn import_company_id(
+    conn: &rusqlite::Connection,
+    cache: &mut HashMap<String, String>,
+    name: Option<&str>,
+    now: &str,
+) -> Result<Option<(String, bool)>, String> {
+    let Some(name) = name.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let key = company_name_key(name);
+    if let Some(id) = cache.get(&key) {
+        return Ok(Some((id.clone(), false)));
+    }
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO companies (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, name, now, now],
+    )
+    .map_err(|e| e.to_string())?;
+    cache.insert(key, id.clone());
+    Ok(Some((id, true)))
+}
+
+/// Checks a row's custom values against the contact fields (id -> name, kind, options).
+fn import_custom_problem(
+    fields: &HashMap<String, (String, String, Option<String>)>,
+    values: &[CustomValueInput],
+) -> Option<String> {
+    for v in values {
+        let Some((name, kind, options)) = fields.get(&v.field_id) else {
+            return Some("Custom field not found".to_string());
+        };
+        if let Some(ref value) = v.value {
+            if let Err(e) = validate_custom_value(name, kind, options.as_deref(), value) {
+                return Some(e);
+            }
+        }
+    }
+    None
+}
+
+/// Writes non-empty custom values; with `only_empty` an existing non-empty value is kept.
+/// Returns whether any value was written.
+fn write_import_custom_values(
+    conn: &rusqlite::Connection,
+    contact_id: &str,
+    values: &[CustomValueInput],
+    only_empty: bool,
+) -> Result<bool, String> {
+    let sql = if only_empty {
+        "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(contact_id, field_id) DO UPDATE SET value = excluded.value
+         WHERE TRIM(COALESCE(value, '')) IN ('', '[]')"
+    } else {
+        "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(contact_id, field_id) DO UPDATE SET value = excluded.value"
+    };
+    let mut written = false;
+    for v in values {
+        let Some(value) = v.value.as_deref().map(str::trim).filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        written |= conn
+            .execute(sql, params![contact_id, v.field_id, value])
+            .map_err(|e| e.to_string())?
+            > 0;
+    }
+    Ok(written)
+}
+
+fn import_rows(
+    conn: &mut rusqlite::Connection,
+    rows: Vec<(ImportRow, Vec<CustomValueInput>)>,
+    source: Option<&str>,
+    mode: ImportMode,
+    link_companies: bool,
+) -> Result<ImportResult, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let batch_id = Uuid::new_v4().to_string();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut result = ImportResult {
+        inserted: 0,
+        updated: 0,
+        skipped: Vec::new(),
+        batch_id: None,
+        companies_created: 0,
+    };
+    let mut companies = if link_companies {
+        Some(import_company_cache(&tx)?)
+    } else {
+        None
+    };
+    // Rows created by this import join the index, so a file repeating a person matches too.
+    let mut index = match mode {
+        ImportMode::CreateAll => None,
+        _ => Some(import_match_index(&tx)?),
+    };
+    let custom_fields: HashMap<String, (String, String, Option<String>)> = {
+        let mut stmt = tx
+            .prepare("SELECT id, name, kind, options FROM custom_fields WHERE scope = 'contact'")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?))))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    for (row_index, (row, custom)) in rows.into_iter().enumerate() {
+        let problem = import_row_problem(&row)
+            .map(str::to_string)
+            .or_else(|| import_custom_problem(&custom_fields, &custom));
+        if let Some(reason) = problem {
+            result.skipped.push(ImportSkip { row_index, reason });
+            continue;
+        }
+        let matched = index.as_ref().and_then(|i| import_match(i, &row, false));
+        match (mode, matched) {
+            (ImportMode::UpdateExisting, Some((contact_id, _))) => {
+                let (filled, company_created) =
+                    fill_empty_contact_fields(&tx, &contact_id, &row, companies.as_mut(), &now)?;
+                if company_created {
+                    result.companies_created += 1;
+                }
+                if write_import_custom_values(&tx, &contact_id, &custom, true)? | filled {
+                    result.updated += 1;
+                } else {
+                    result.skipped.push(ImportSkip {
+                        row_index,
+                        reason: "Mevcut kişiyle eşleşti, eklenecek yeni bilgi yok".to_string(),
+                    });
+                }
+            }
+            (_, Some((_, matched_by))) => {
+                result.skipped.push(ImportSkip {
+                    row_index,
+                    reason: format!("Mevcut kişiyle eşleşti ({})", matched_by),
+                });
+            }
+            (_, None) => {
+                let company_id = match companies.as_mut() {
+                    Some(cache) => import_company_id(&tx, cache, row.company.as_deref(), &now)?,
+                    None => None,
+                };
+                if let Some((_, true)) = company_id {
+                    result.companies_created += 1;
+                }
+                let company_id = company_id.map(|(id, _)| id);
+                let id = insert_import_row(&tx, &row, company_id.as_deref(), &batch_id, &now)?;
+                write_import_custom_values(&tx, &id, &custom, false)?;
+                if let Some(index) = index.as_mut() {
+                    import_match_index_add(index, &id, &row);
+                }
+                result.inserted += 1;
+            }
+        }
+    }
+    if result.inserted > 0 {
+        insert_import_batch(&tx, &batch_id, source, result.inserted, &now)?;
+        result.batch_id = Some(batch_id);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+fn insert_import_batch(
+    conn: &rusqlite::Connection,
+    id: &str,
+    source: Option<&str>,
+    row_count: u64,
+    now: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO import_batches (id, source, created_at, row_count) VALUES (?1, ?2, ?3, ?4)",
+        params![id, source.map(str::trim).filter(|s| !s.is_empty()), now, row_count as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn import_row_problem(row: &ImportRow) -> Option<&'static str> {
+    if row.first_name.as_deref().unwrap_or("").is_empty() && row.last_name.as_deref().unwrap_or("").is_empty() {
+        Some("Ad ve soyad boş")
+    } else if !is_valid_email(&row.email) || !is_valid_email(&row.email_secondary) {
+        Some("Geçersiz email formatı")
+    } else if !is_valid_phone(&row.phone) || !is_valid_phone(&row.phone_secondary) {
+        Some("Geçersiz telefon formatı")
+    } else {
+        None
+    }
+}
+
+/// Live contacts keyed the way dedup_candidates compares them, for matching incoming rows.
+struct ImportMatchIndex {
+    by_email: HashMap<String, String>,
+    by_phone: HashMap<String, String>,
+    /// (contact id, first name, last name)
+    names: Vec<(String, String, String)>,
+    default_code: Option<String>,
+    name_threshold: f32,
+}
+
+fn import_match_index(conn: &rusqlite::Connection) -> Result<ImportMatchIndex, String> {
+    let mut index = ImportMatchIndex {
+        by_email: HashMap::new(),
+        by_phone: HashMap::new(),
+        names: Vec::new(),
+        default_code: default_country_code(conn)?,
+        name_threshold: dedup_name_threshold(conn)?,
+    };
+    // Oldest first, so when several contacts share a key the original one is the match.
+    let sql = format!("{} WHERE c.deleted_at IS NULL ORDER BY c.created_at, c.id", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_contact).map_err(|e| e.to_string())?;
+    for c in rows.filter_map(|r| r.ok()) {
+        for email in [normalize_email(&c.email), normalize_email(&c.email_secondary)].into_iter().flatten() {
+            index.by_email.entry(email).or_insert_with(|| c.id.clone());
+        }
+        for key in contact_phone_keys(&c, index.default_code.as_deref()) {
+            index.by_phone.entry(key).or_insert_with(|| c.id.clone());
+        }
+        index.names.push((c.id, c.first_name, c.last_name));
+    }
+    Ok(index)
+}
+
+fn import_match_index_add(index: &mut ImportMatchIndex, id: &str, row: &ImportRow) {
+    for email in [normalize_email(&row.email), normalize_email(&row.email_secondary)].into_iter().flatten() {
+        index.by_email.entry(email).or_insert_with(|| id.to_string());
+    }
+    for key in phone_keys(&row.country, [&row.phone, &row.phone_secondary], index.default_code.as_deref()) {
+        index.by_phone.entry(key).or_insert_with(|| id.to_string());
+    }
+    index.names.push((
+        id.to_string(),
+        row.first_name.clone().unwrap_or_default(),
+        row.last_name.clone().unwrap_or_default(),
+    ));
+}
+
+/// Copies row values into the contact's empty columns only; returns whether anything changed and
+/// whether a company card was created. Emails/phones the contact already has (in either slot) are
+/// not copied again. With `companies`, a filled company name is linked like a new row's.
+fn fill_empty_contact_fields(
+    conn: &rusqlite::Connection,
+    contact_id: &str,
+    row: &ImportRow,
+    mut companies: Option<&mut HashMap<String, String>>,
+    now: &str,
+) -> Result<(bool, bool), String> {
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let contact = conn
+        .query_row(&sql, params![contact_id], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    fn blank(v: Option<&str>) -> bool {
+        v.map(str::trim).unwrap_or("").is_empty()
+    }
+    let value = |v: &Option<String>| v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let emails = [normalize_email(&contact.email), normalize_email(&contact.email_secondary)];
+    let phones = [normalize_phone(&contact.phone), normalize_phone(&contact.phone_secondary)];
+    let new_email = |v: &Option<String>| value(v).filter(|_| !emails.contains(&normalize_email(v)));
+    let new_phone = |v: &Option<String>| value(v).filter(|_| !phones.contains(&normalize_phone(v)));
+    let candidates: [(&str, bool, Option<String>); 13] = [
+        ("first_name", blank(Some(&contact.first_name)), value(&row.first_name)),
+        ("last_name", blank(Some(&contact.last_name)), value(&row.last_name)),
+        ("title", blank(contact.title.as_deref()), value(&row.title)),
+        ("company", blank(contact.company.as_deref()), value(&row.company)),
+        ("city", blank(contact.city.as_deref()), value(&row.city)),
+        ("country", blank(contact.country.as_deref()), value(&row.country)),
+        ("email", blank(contact.email.as_deref()), new_email(&row.email)),
+        ("email_secondary", blank(contact.email_secondary.as_deref()), new_email(&row.email_secondary)),
+        ("phone", blank(contact.phone.as_deref()), new_phone(&row.phone)),
+        ("phone_secondary", blank(contact.phone_secondary.as_deref()), new_phone(&row.phone_secondary)),
+        ("linkedin_url", blank(contact.linkedin_url.as_deref()), value(&row.linkedin_url)),
+        ("twitter_url", blank(contact.twitter_url.as_deref()), value(&row.twitter_url)),
+        ("website", blank(contact.website.as_deref()), value(&row.website)),
+    ];
+    let mut sets: Vec<String> = Vec::new();
+    let mut values: Vec<Option<String>> = Vec::new();
+    let mut company_created = false;
+    for (column, empty, v) in candidates {
+        let Some(v) = v.filter(|_| empty) else {
+            continue;
+        };
+        if column == "company" {
+            if let Some(cache) = companies.as_mut() {
+                if let Some((company_id, created)) = import_company_id(conn, cache, Some(&v), now)? {
+                    sets.push("company_id = ?".to_string());
+                    values.push(Some(company_id));
+                    company_created = created;
+                }
+            }
+        }
+        if column == "phone" || column == "phone_secondary" {
+            sets.push(format!("{}_normalized = ?", column));
+            values.push(normalize_phone(&Some(v.clone())));
+        }
+        sets.push(format!("{} = ?", column));
+        values.push(Some(v));
+    }
+    if sets.is_empty() {
+        return Ok((false, false));
+    }
+    values.push(Some(now.to_string()));
+    values.push(Some(contact_id.to_string()));
+    conn.execute(
+        &format!("UPDATE contacts SET {}, updated_at = ? WHERE id = ?", sets.join(", ")),
+        rusqlite::params_from_iter(values.iter()),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok((true, company_created))
+}
+
+/// Existing contact an incoming row duplicates and why: "email", then "phone", then (when
+/// `by_name`) "name" at the dedup name threshold.
+fn import_match(index: &ImportMatchIndex, row: &ImportRow, by_name: bool) -> Option<(String, &'static str)> {
+    for email in [normalize_email(&row.email), normalize_email(&row.email_secondary)].into_iter().flatten() {
+        if let Some(id) = index.by_email.get(&email) {
+            return Some((id.clone(), "email"));
+        }
+    }
+    let phones = phone_keys(&row.country, [&row.phone, &row.phone_secondary], index.default_code.as_deref());
+    for key in phones {
+        if let Some(id) = index.by_phone.get(&key) {
+            return Some((id.clone(), "phone"));
+        }
+    }
+    if by_name {
+        let first = row.first_name.as_deref().unwrap_or("");
+        let last = row.last_name.as_deref().unwrap_or("");
+        return index
+            .names
+            .iter()
+            .map(|(id, f, l)| (id, name_similarity(first, last, f, l)))
+            .filter(|(_, sim)| *sim >= index.name_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| (id.clone(), "name"));
+    }
+    None
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportPreviewStatus {
+    New,
+    /// Exactly one of `contact_id` / `earlier_row` is set.
+    DuplicateOf {
+        contact_id: Option<String>,
+        /// A new row earlier in the same file, which import_contacts would create first.
+        earlier_row: Option<usize>,
+        /// "email" | "phone" | "name"
+        matched_by: String,
+    },
+    Invalid {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportPreviewRow {
+    /// 0-based index into the rows sent by the frontend.
+    pub row_index: usize,
+    #[serde(flatten)]
+    pub status: ImportPreviewStatus,
+}
+
+/// Index id of a previewed new row; contact ids are UUIDs, so these cannot collide.
+const PREVIEW_ROW_ID_PREFIX: &str = "row:";
+
+/// New rows join the index as they are classified, so a file repeating a person reports the
+/// repeat as a duplicate of its first row, as import_rows would.
+fn preview_import_rows(index: &mut ImportMatchIndex, rows: &[ImportRow]) -> Vec<ImportPreviewRow> {
+    rows.iter()
+        .enumerate()
+        .map(|(row_index, row)| {
+            let status = if let Some(reason) = import_row_problem(row) {
+                ImportPreviewStatus::Invalid {
+                    reason: reason.to_string(),
+                }
+            } else if let Some((id, matched_by)) = import_match(index, row, true) {
+                let earlier_row = id.strip_prefix(PREVIEW_ROW_ID_PREFIX).and_then(|i| i.parse().ok());
+                ImportPreviewStatus::DuplicateOf {
+                    contact_id: Some(id).filter(|_| earlier_row.is_none()),
+                    earlier_row,
+                    matched_by: matched_by.to_string(),
+                }
+            } else {
+                import_match_index_add(index, &format!("{}{}", PREVIEW_ROW_ID_PREFIX, row_index), row);
+                ImportPreviewStatus::New
+            };
+            ImportPreviewRow { row_index, status }
+        })
+        .collect()
+}
+
+/// Dry run of import_contacts: classifies each row as new, a duplicate of an existing contact
+/// (same email/phone keys and name threshold as dedup_candidates) or of an earlier row, or invalid.
+/// Writes nothing.
+#[tauri::command]
+pub fn import_preview(db: State<DbState>, rows: Vec<ImportRow>) -> Result<Vec<ImportPreviewRow>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut index = import_match_index(conn)?;
+    Ok(preview_import_rows(&mut index, &rows))
+}
+
+fn insert_import_row(
+    conn: &rusqlite::Connection,
+    row: &ImportRow,
+    company_id: Option<&str>,
+    batch_id: &str,
+    now: &str,
+) -> Result<String, String> {
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO contacts (id, first_name, last_name, title, company, company_id, city, country, email, email_secondary, phone, phone_normalized, phone_secondary, phone_secondary_normalized, linkedin_url, twitter_url, website, import_batch_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+        params![
+            id,
+            row.first_name.as_deref().unwrap_or(""),
+            row.last_name.as_deref().unwrap_or(""),
+            row.title,
+            row.company,
+            company_id,
+            row.city,
+            row.country,
+            row.email,
+            row.email_secondary,
+            row.phone,
+            normalize_phone(&row.phone),
+            row.phone_secondary,
+            normalize_phone(&row.phone_secondary),
+            row.linkedin_url,
+            row.twitter_url,
+            row.website,
+            batch_id,
+            now,
+            now,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Emitted as "import-progress" every IMPORT_PROGRESS_EVERY lines and once at the end.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub lines_read: u64,
+    pub inserted: u64,
+    pub skipped: u64,
+    pub done: bool,
+}
+
+const IMPORT_PROGRESS_EVERY: u64 = 1000;
+
+/// Streams a JSON Lines file (one ImportRow object per line) into contacts inside one transaction,
+/// so memory stays flat for 100k+ rows. Blank lines are ignored; unparseable or invalid rows are
+/// skipped and counted in the progress events. Companies are linked by name as in import_contacts.
+/// Returns the number inserted.
+#[tauri::command]
+pub fn import_contacts_jsonl(app: tauri::AppHandle, db: State<DbState>, path: String) -> Result<u64, String> {
+    use std::io::BufRead;
+    use tauri::Emitter;
+    let file = std::fs::File::open(&path).map_err(|e| format!("Dosya açılamadı: {}", e))?;
+    let reader = std::io::BufReader::new(file);
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let batch_id = Uuid::new_v4().to_string();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut companies = import_company_cache(&tx)?;
+    let mut progress = ImportProgress {
+        lines_read: 0,
+        inserted: 0,
+        skipped: 0,
+        done: false,
+    };
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        progress.lines_read += 1;
+        if !line.trim().is_empty() {
+            match serde_json::from_str::<ImportRow>(&line) {
+                Ok(row) if import_row_problem(&row).is_none() => {
+                    let company_id = import_company_id(&tx, &mut companies, row.company.as_deref(), &now)?;
+                    insert_import_row(&tx, &row, company_id.map(|(id, _)| id).as_deref(), &batch_id, &now)?;
+                    progress.inserted += 1;
+                }
+                _ => progress.skipped += 1,
+            }
+        }
+        if progress.lines_read % IMPORT_PROGRESS_EVERY == 0 {
+            let _ = app.emit("import-progress", progress.clone());
+        }
+    }
+    if progress.inserted > 0 {
+        let source = Path::new(&path).file_name().and_then(|n| n.to_str());
+        insert_import_batch(&tx, &batch_id, source, progress.inserted, &now)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    progress.done = true;
+    let _ = app.emit("import-progress", progress.clone());
+    Ok(progress.inserted)
+}
+
+// ---- Import (vCard) ----
+
+fn vcard_unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// Splits a structured value (N, ADR, ORG) on unescaped semicolons, unescaping each part.
+fn vcard_components(value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for ch in value.chars() {
+        if escaped {
+            current.push('\\');
+            current.push(ch);
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == ';' {
+            parts.push(vcard_unescape(&current));
+            current.clear();
+        } else {
+            current.push(ch);
+        }
+    }
+    parts.push(vcard_unescape(&current));
+    parts
+}
+
+/// Parses every BEGIN:VCARD..END:VCARD block (folded lines are unfolded). The first EMAIL/TEL
+/// goes to the primary column and the second to the secondary one; further ones are dropped.
+/// FN is split into first/last name only when N has no name.
+fn parse_vcards(content: &str) -> Vec<ImportRow> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        match raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')) {
+            Some(cont) if !lines.is_empty() => lines.last_mut().unwrap().push_str(cont),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    fn set(slot: &mut Option<String>, value: String) {
+        let value = value.trim().to_string();
+        if slot.is_none() && !value.is_empty() {
+            *slot = Some(value);
+        }
+    }
+    let mut rows = Vec::new();
+    let mut current: Option<(ImportRow, Option<String>)> = None;
+    for line in lines {
+        let Some((head, value)) = line.split_once(':') else { continue };
+        let mut params = head.split(';');
+        let name = params.next().unwrap_or("").to_ascii_uppercase();
+        // Drop any "item1." group prefix.
+        let name = name.rsplit('.').next().unwrap_or("").to_string();
+        let types = params.collect::<Vec<_>>().join(";").to_ascii_lowercase();
+        if name == "BEGIN" && value.trim().eq_ignore_ascii_case("VCARD") {
+            current = Some((ImportRow::default(), None));
+            continue;
+        }
+        if name == "END" && value.trim().eq_ignore_ascii_case("VCARD") {
+            if let Some((mut row, full_name)) = current.take() {
+                if row.first_name.is_none() && row.last_name.is_none() {
+                    if let Some(full) = full_name {
+                        let mut words = full.split_whitespace();
+                        row.first_name = words.next().map(str::to_string);
+                        let rest = words.collect::<Vec<_>>().join(" ");
+                        set(&mut row.last_name, rest);
+                    }
+                }
+                rows.push(row);
+            }
+            continue;
+        }
+        let Some((row, full_name)) = current.as_mut() else { continue };
+        match name.as_str() {
+            "N" => {
+                let parts = vcard_components(value);
+                set(&mut row.last_name, parts.first().cloned().unwrap_or_default());
+                set(&mut row.first_name, parts.get(1).cloned().unwrap_or_default());
+            }
+            "FN" => set(full_name, vcard_unescape(value)),
+            "ORG" => set(&mut row.company, vcard_components(value).remove(0)),
+            "TITLE" => set(&mut row.title, vcard_unescape(value)),
+            "EMAIL" => {
+                let value = vcard_unescape(value);
+                if row.email.is_none() {
+                    set(&mut row.email, value);
+                } else {
+                    set(&mut row.email_secondary, value);
+                }
+            }
+            "TEL" => {
+                let value = vcard_unescape(value);
+                if row.phone.is_none() {
+                    set(&mut row.phone, value);
+                } else {
+                    set(&mut row.phone_secondary, value);
+                }
+            }
+            "ADR" => {
+                let parts = vcard_components(value);
+                set(&mut row.city, parts.get(3).cloned().unwrap_or_default());
+                set(&mut row.country, parts.get(6).cloned().unwrap_or_default());
+            }
+            "URL" | "X-SOCIALPROFILE" => {
+                let url = vcard_unescape(value);
+                let lower = url.to_ascii_lowercase();
+                if types.contains("linkedin") || lower.contains("linkedin.com") {
+                    set(&mut row.linkedin_url, url);
+                } else if types.contains("twitter") || lower.contains("twitter.com") || lower.contains("x.com/") {
+                    set(&mut row.twitter_url, url);
+                } else if name == "URL" {
+                    set(&mut row.website, url);
+                }
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// Imports a .vcf file (any number of cards) in one transaction as an import batch. Cards without
+/// any name (N or FN) and cards failing the CSV import checks are skipped; row_index is the
+/// 0-based card index.
+#[tauri::command]
+pub fn import_vcard(db: State<DbState>, content: String) -> Result<ImportResult, String> {
+    let rows = parse_vcards(&content);
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let rows = rows.into_iter().map(|row| (row, Vec::new())).collect();
+    import_rows(conn, rows, Some("vCard"), ImportMode::CreateAll, true)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportBatch {
+    pub id: String,
+    pub source: Option<String>,
+    pub created_at: String,
+    pub row_count: i64,
+    /// Contacts from this batch that are not in the trash.
+    pub active_count: i64,
+    pub undone_at: Option<String>,
+}
+
+/// Import batches, newest first.
+#[tauri::command]
+pub fn import_batch_list(db: State<DbState>) -> Result<Vec<ImportBatch>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT b.id, b.source, b.created_at, b.row_count,
+                    (SELECT COUNT(*) FROM contacts c WHERE c.import_batch_id = b.id AND c.deleted_at IS NULL),
+                    b.undone_at
+             FROM import_batches b ORDER BY b.created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ImportBatch {
+                id: row.get(0)?,
+                source: row.get(1)?,
+                created_at: row.get(2)?,
+                row_count: row.get(3)?,
+                active_count: row.get(4)?,
+                undone_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Moves every contact still active from the batch to the trash (restorable with contact_restore)
+/// and marks the batch undone; a batch can be undone only once. Returns the number of contacts
+/// trashed.
+#[tauri::command]
+pub fn import_batch_undo(db: State<DbState>, batch_id: String) -> Result<u64, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let undone_at: Option<String> = tx
+        .query_row(
+            "SELECT undone_at FROM import_batches WHERE id = ?1",
+            params![batch_id],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Import batch not found")?;
+    if undone_at.is_some() {
+        return Err("Bu içe aktarma zaten geri alındı".to_string());
+    }
+    tx.execute(
+        "UPDATE import_batches SET undone_at = ?1 WHERE id = ?2",
+        params![now, batch_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let trashed = tx
+        .execute(
+            "UPDATE contacts SET deleted_at = ?1, updated_at = ?1 WHERE import_batch_id = ?2 AND deleted_at IS NULL",
+            params![now, batch_id],
+        )
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(trashed as u64)
+}
+
+// ---- Search (FTS) ----
+
+/// `match_mode`: "all" (default, every term must match) or "any" (terms joined with OR).
+#[tauri::command]
+pub fn search_contacts(db: State<DbState>, q: String, match_mode: Option<String>) -> Result<Vec<String>, String> {
+    if q.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let any = match match_mode.as_deref().unwrap_or("all") {
+        "all" => false,
+        "any" => true,
+        other => return Err(format!("Invalid match_mode: {}", other)),
+    };
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    // FTS5: content table is 'contacts', so we query contacts_fts and join to get id
+    let query = fts_prefix_query(&q, any);
+    search_contact_ids(conn, &query)
+}
+
+/// Live contacts matching an FTS `query`, at most 50; trashed contacts don't take up slots.
+fn search_contact_ids(conn: &rusqlite::Connection, query: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id FROM contacts_fts JOIN contacts c ON c.rowid = contacts_fts.rowid
+             WHERE contacts_fts MATCH ?1 AND c.deleted_at IS NULL LIMIT 50",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![query], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchIndexAudit {
+    pub contacts_count: i64,
+    pub indexed_count: i64,
+    /// Contacts with no FTS entry (won't show up in search).
+    pub missing_ids: Vec<String>,
+    /// FTS entries whose contact row no longer exists.
+    pub orphan_rowids: Vec<i64>,
+    /// FTS5 integrity-check against the contacts table failed (stale text).
+    pub content_mismatch: bool,
+    pub needs_reindex: bool,
+}
+
+fn fts_audit(conn: &rusqlite::Connection) -> Result<SearchIndexAudit, String> {
+    let contacts_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM contacts", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    // External-content FTS5 keeps one docsize row per indexed rowid.
+    let indexed_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM contacts_fts_docsize", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    let missing_ids: Vec<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id FROM contacts c
+                 WHERE NOT EXISTS (SELECT 1 FROM contacts_fts_docsize d WHERE d.id = c.rowid)
+                 ORDER BY c.id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| r.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    let orphan_rowids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT d.id FROM contacts_fts_docsize d
+                 WHERE NOT EXISTS (SELECT 1 FROM contacts c WHERE c.rowid = d.id)
+                 ORDER BY d.id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| r.get::<_, i64>(0))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    let content_mismatch = conn
+        .execute(
+            "INSERT INTO contacts_fts(contacts_fts, rank) VALUES ('integrity-check', 1)",
+            [],
+        )
+        .is_err();
+    let needs_reindex = content_mismatch
+        || !missing_ids.is_empty()
+        || !orphan_rowids.is_empty()
+        || contacts_count != indexed_count;
+    Ok(SearchIndexAudit {
+        contacts_count,
+        indexed_count,
+        missing_ids,
+        orphan_rowids,
+        content_mismatch,
+        needs_reindex,
+    })
+}
+
+/// Diagnostic: compares contacts against the FTS index and reports drift (see search_reindex).
+#[tauri::command]
+pub fn search_index_audit(db: State<DbState>) -> Result<SearchIndexAudit, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    fts_audit(conn)
+}
+
+/// Rebuilds the contacts FTS index from the contacts table.
+#[tauri::command]
+pub fn search_reindex(db: State<DbState>) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute("INSERT INTO contacts_fts(contacts_fts) VALUES ('rebuild')", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Prefix query with each term quoted, so user input like `"`, `-` or `:` is not FTS syntax.
+/// Terms are ANDed (FTS5 implicit) unless `any`, which joins them with OR.
+fn fts_prefix_query(q: &str, any: bool) -> String {
+    q.split_whitespace()
+        .map(|t| format!("\"{}\"*", t.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(if any { " OR " } else { " " })
+}
+
+// C2.1 — Global hızlı arama: kişi, şirket, not içeriği
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalSearchNoteHit {
+    pub note_id: String,
+    pub contact_id: String,
+    pub contact_name: String,
+    pub body_snippet: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalSearchResult {
+    pub contacts: Vec<Contact>,
+    pub companies: Vec<Company>,
+    pub note_hits: Vec<GlobalSearchNoteHit>,
+}
+
+/// Narrows global_search contacts (and the contacts of note hits); empty strings are ignored.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GlobalSearchFilters {
+    pub tag_id: Option<String>,
+    pub company_id: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    /// Value of the seeded Stage field (cf_stage).
+    pub stage: Option<String>,
+}
+
+/// SQL conditions on contacts aliased `c`, with their positional `?` parameters in order.
+fn contact_filter_clauses(filters: &GlobalSearchFilters) -> (Vec<String>, Vec<String>) {
+    fn value(v: &Option<String>) -> Option<String> {
+        v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+    }
+    let mut clauses = Vec::new();
+    let mut params = Vec::new();
+    if let Some(tag_id) = value(&filters.tag_id) {
+        clauses.push("c.id IN (SELECT contact_id FROM contact_tags WHERE tag_id = ?)".to_string());
+        params.push(tag_id);
+    }
+    if let Some(company_id) = value(&filters.company_id) {
+        clauses.push("c.company_id = ?".to_string());
+        params.push(company_id);
+    }
+    if let Some(city) = value(&filters.city) {
+        clauses.push("TRIM(c.city) = ? COLLATE NOCASE".to_string());
+        params.push(city);
+    }
+    if let Some(country) = value(&filters.country) {
+        clauses.push("TRIM(c.country) = ? COLLATE NOCASE".to_string());
+        params.push(country);
+    }
+    if let Some(stage) = value(&filters.stage) {
+        clauses.push(format!(
+            "c.id IN (SELECT contact_id FROM contact_custom_values WHERE field_id = '{}' AND value = ?)",
+            PIPELINE_STAGE_FIELD
+        ));
+        params.push(stage);
+    }
+    (clauses, params)
+}
+
+/// With only filters (empty `q`), returns every matching contact and no companies or notes.
+#[tauri::command]
+pub fn global_search(
+    db: State<DbState>,
+    q: String,
+    filters: Option<GlobalSearchFilters>,
+) -> Result<GlobalSearchResult, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    run_global_search(conn, &q, &filters.unwrap_or_default(), 20)
+}
+
+/// `contact_limit` caps the contact hits of a text query (-1 = no limit).
+fn run_global_search(
+    conn: &rusqlite::Connection,
+    q: &str,
+    filters: &GlobalSearchFilters,
+    contact_limit: i64,
+) -> Result<GlobalSearchResult, String> {
+    let q_trim = q.trim();
+    let (filter_clauses, filter_params) = contact_filter_clauses(filters);
+    if q_trim.is_empty() && filter_clauses.is_empty() {
+        return Ok(GlobalSearchResult {
+            contacts: vec![],
+            companies: vec![],
+            note_hits: vec![],
+        });
+    }
+    let filter_sql: String = filter_clauses.iter().map(|c| format!(" AND {}", c)).collect();
+
+    if q_trim.is_empty() {
+        let sql = format!(
+            "{} WHERE c.deleted_at IS NULL{} ORDER BY c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE",
+            CONTACT_SELECT, filter_sql
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(filter_params.iter()), row_to_contact)
+            .map_err(|e| e.to_string())?;
+        return Ok(GlobalSearchResult {
+            contacts: rows.filter_map(|r| r.ok()).collect(),
+            companies: vec![],
+            note_hits: vec![],
+        });
+    }
+
+    // Contacts: FTS hits intersected with the filters, best match first
+    let contacts: Vec<Contact> = {
+        let sql = format!(
+            "{} JOIN (SELECT rowid, rank FROM contacts_fts WHERE contacts_fts MATCH ?) f ON f.rowid = c.rowid
+             WHERE c.deleted_at IS NULL{} ORDER BY f.rank LIMIT {}",
+            CONTACT_SELECT, filter_sql, contact_limit
+        );
+        let mut query_params = vec![fts_prefix_query(q_trim, false)];
+        query_params.extend(filter_params.iter().cloned());
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(query_params.iter()), row_to_contact)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    // Companies: LIKE name; a company filter keeps only that company
+    let companies: Vec<Company> = {
+        let pattern = format!("%{}%", q_trim.replace('%', "\\%").replace('_', "\\_"));
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE name LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR id = ?2) LIMIT 20",
+                COMPANY_SELECT
+            ))
+            .map_err(|e| e.to_string())?;
+        let company_filter = filters.company_id.as_deref().map(str::trim).filter(|s| !s.is_empty());
+        let rows = stmt
+            .query_map(params![pattern, company_filter], row_to_company)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    // Notes: FTS on title + body, snippet; only notes of contacts matching the filters
+    let note_hits: Vec<GlobalSearchNoteHit> = {
+        let sql = format!(
+            "SELECT n.id, n.contact_id, n.body, n.created_at, c.first_name, c.last_name
+             FROM notes_fts f
+             JOIN notes n ON n.rowid = f.rowid
+             JOIN contacts c ON n.contact_id = c.id
+             WHERE notes_fts MATCH ? AND c.deleted_at IS NULL{}
+             ORDER BY n.created_at DESC LIMIT 20",
+            filter_sql
+        );
+        let mut query_params = vec![fts_prefix_query(q_trim, false)];
+        query_params.extend(filter_params.iter().cloned());
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(query_params.iter()), |row| {
+                let note_id: String = row.get(0)?;
+                let contact_id: String = row.get(1)?;
+                let body: String = row.get(2)?;
+                let created_at: String = row.get(3)?;
+                let first_name: String = row.get(4)?;
+                let last_name: String = row.get(5)?;
+                let snippet_len = 120;
+                let body_snippet = if body.len() <= snippet_len {
+                    body
+                } else {
+                    format!("{}…", body.chars().take(snippet_len).collect::<String>())
+                };
+                Ok(GlobalSearchNoteHit {
+                    note_id,
+                    contact_id,
+                    contact_name: format!("{} {}", first_name, last_name),
+                    body_snippet,
+                    created_at,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    Ok(GlobalSearchResult {
+        contacts,
+        companies,
+        note_hits,
+    })
+}
+
+// C2.3 — Notlarda #etiket: bu hashtag geçen notları olan contact_id listesi
+#[tauri::command]
+pub fn contact_ids_with_hashtag(db: State<DbState>, hashtag: String) -> Result<Vec<String>, String> {
+    let tag = hashtag.trim();
+    if tag.is_empty() {
+        return Ok(vec![]);
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    hashtag_contact_ids(conn, tag)
+}
+
+fn hashtag_contact_ids(conn: &rusqlite::Connection, tag: &str) -> Result<Vec<String>, String> {
+    let pattern = format!("%#{}%", tag.replace('%', "\\%").replace('_', "\\_"));
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT n.contact_id FROM notes n
+             JOIN contacts c ON c.id = n.contact_id AND c.deleted_at IS NULL
+             WHERE n.body LIKE ?1 ESCAPE '\\'",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![pattern], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+// ---- Saved segments (global_search query + filters) ----
+
+#[derive(Debug, Serialize)]
+pub struct Segment {
+    pub id: String,
+    pub name: String,
+    pub query: String,
+    pub filters: GlobalSearchFilters,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SegmentCreateInput {
+    pub name: String,
+    pub query: Option<String>,
+    pub filters: Option<GlobalSearchFilters>,
+}
+
+fn row_to_segment(row: &Row) -> rusqlite::Result<Segment> {
+    let filters_json: Option<String> = row.get(3)?;
+    Ok(Segment {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        query: row.get(2)?,
+        filters: filters_json
+            .and_then(|f| serde_json::from_str(&f).ok())
+            .unwrap_or_default(),
+        created_at: row.get(4)?,
+    })
+}
+
+#[tauri::command]
+pub fn segment_create(db: State<DbState>, input: SegmentCreateInput) -> Result<Segment, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Segment adı boş olamaz".to_string());
+    }
+    let query = input.query.unwrap_or_default().trim().to_string();
+    let filters = input.filters.unwrap_or_default();
+    if query.is_empty() && contact_filter_clauses(&filters).0.is_empty() {
+        return Err("Segment için arama metni veya filtre gerekli".to_string());
+    }
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let filters_json = serde_json::to_string(&filters).map_err(|e| e.to_string())?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute(
+        "INSERT INTO segments (id, name, query, filters_json, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, name, query, filters_json, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Segment {
+        id,
+        name,
+        query,
+        filters,
+        created_at: now,
+    })
+}
+
+#[tauri::command]
+pub fn segment_list(db: State<DbState>) -> Result<Vec<Segment>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, query, filters_json, created_at FROM segments ORDER BY name COLLATE NOCASE")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_segment).map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[tauri::command]
+pub fn segment_delete(db: State<DbState>, id: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute("DELETE FROM segments WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn segment_by_id(conn: &rusqlite::Connection, id: &str) -> Result<Segment, String> {
+    conn.query_row(
+        "SELECT id, name, query, filters_json, created_at FROM segments WHERE id = ?1",
+        params![id],
+        row_to_segment,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Segment not found".to_string())
+}
+
+/// Re-runs the stored query + filters through global_search against the current data. Every
+/// matching contact is returned, not just the first search page.
+#[tauri::command]
+pub fn segment_run(db: State<DbState>, id: String) -> Result<GlobalSearchResult, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let segment = segment_by_id(conn, &id)?;
+    run_global_search(conn, &segment.query, &segment.filters, -1)
+}
+
+const DEFAULT_DEDUP_NAME_THRESHOLD: f32 = 0.85;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct DedupSettings {
+    /// Minimum name similarity (0.5..=1) for a "name" reason.
+    pub name_threshold: Option<f32>,
+    /// Adds a "phonetic" reason for names that sound alike (default on).
+    pub phonetic: Option<bool>,
+}
+
+fn dedup_phonetic_enabled(conn: &rusqlite::Connection) -> Result<bool, String> {
+    Ok(setting_get(conn, "dedup_phonetic")?.as_deref() != Some("0"))
+}
+
+fn dedup_name_threshold(conn: &rusqlite::Connection) -> Result<f32, String> {
+    Ok(setting_get(conn, "dedup_name_threshold")?
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(DEFAULT_DEDUP_NAME_THRESHOLD))
+}
+
+#[tauri::command]
+pub fn dedup_settings_get(db: State<DbState>) -> Result<DedupSettings, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    Ok(DedupSettings {
+        name_threshold: Some(dedup_name_threshold(conn)?),
+        phonetic: Some(dedup_phonetic_enabled(conn)?),
+    })
+}
+
+/// Ordered (smaller id first) so a pair is stored once whichever side was passed as `a`.
+fn dedup_pair_key(a: &str, b: &str) -> (String, String) {
+    if a < b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// Marks a pair as "not a duplicate"; dedup_candidates no longer returns it.
+#[tauri::command]
+pub fn dedup_ignore(db: State<DbState>, a_id: String, b_id: String) -> Result<(), String> {
+    if a_id == b_id {
+        return Err("Aynı kişi seçildi".to_string());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let (a, b) = dedup_pair_key(&a_id, &b_id);
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    conn.execute(
+        "INSERT OR IGNORE INTO dedup_ignored_pairs (a_id, b_id, created_at) VALUES (?1, ?2, ?3)",
+        params![a, b, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Settings passed in `options` are saved and used from then on.
+#[tauri::command]
+pub fn dedup_candidates(
+    db: State<DbState>,
+    options: Option<DedupSettings>,
+) -> Result<Vec<DedupCandidate>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    if let Some(t) = options.as_ref().and_then(|o| o.name_threshold) {
+        if !(0.5..=1.0).contains(&t) {
+            return Err("İsim eşiği 0.5 ile 1 arasında olmalı".to_string());
+        }
+        setting_set(conn, "dedup_name_threshold", &t.to_string())?;
+    }
+    if let Some(p) = options.as_ref().and_then(|o| o.phonetic) {
+        setting_set(conn, "dedup_phonetic", if p { "1" } else { "0" })?;
+    }
+    let name_threshold = dedup_name_threshold(conn)?;
+    let phonetic = dedup_phonetic_enabled(conn)?;
+    let ignored: std::collections::HashSet<(String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT a_id, b_id FROM dedup_ignored_pairs")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    // Trashed contacts are left out; phone pairs touching them drop out via by_id below.
+    let sql = format!("{} WHERE c.deleted_at IS NULL ORDER BY c.updated_at DESC", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    let contacts: Vec<Contact> = rows.filter_map(|r| r.ok()).collect();
+
+    let mut by_id: HashMap<String, Contact> = HashMap::new();
+    for c in contacts.iter() {
+        by_id.insert(c.id.clone(), c.clone());
+    }
+
+    #[derive(Default)]
+    struct ReasonFlags {
+        email: bool,
+        phone: bool,
+        name: bool,
+        phonetic: bool,
+    }
+
+    let mut pair_reasons: HashMap<(String, String), ReasonFlags> = HashMap::new();
+
+    let mut email_map: HashMap<String, Vec<String>> = HashMap::new();
+    let mut phone_map: HashMap<String, Vec<String>> = HashMap::new();
+
+    for c in contacts.iter() {
+        if let Some(e) = normalize_email(&c.email) {
+            email_map.entry(e).or_default().push(c.id.clone());
+        }
+        if let Some(e) = normalize_email(&c.email_secondary) {
+            email_map.entry(e).or_default().push(c.id.clone());
+        }
+    }
+    // E.164 keys so "+1 212 555 1212", "001 212 555 1212" and a national "212 555 1212" meet.
+    let default_code = default_country_code(conn)?;
+    for c in contacts.iter() {
+        for key in contact_phone_keys(c, default_code.as_deref()) {
+            phone_map.entry(key).or_default().push(c.id.clone());
+        }
+    }
+
+    let mut add_reason = |a: &str, b: &str, kind: &str| {
+        if a == b {
+            return;
+        }
+        let key = dedup_pair_key(a, b);
+        if ignored.contains(&key) {
+            return;
+        }
+        let entry = pair_reasons.entry(key).or_default();
+        match kind {
+            "email" => entry.email = true,
+            "phone" => entry.phone = true,
+            "name" => entry.name = true,
+            "phonetic" => entry.phonetic = true,
+            _ => {}
+        }
+    };
+
+    for ids in email_map.values() {
+        if ids.len() < 2 {
+            continue;
+        }
+        for i in 0..ids.len() {
+            for j in (i + 1)..ids.len() {
+                add_reason(&ids[i], &ids[j], "email");
+            }
+        }
+    }
+
+    for ids in phone_map.values() {
+        if ids.len() < 2 {
+            continue;
+        }
+        for i in 0..ids.len() {
+            for j in (i + 1)..ids.len() {
+                add_reason(&ids[i], &ids[j], "phone");
+            }
+        }
+    }
+
+    for i in 0..contacts.len() {
+        for j in (i + 1)..contacts.len() {
+            let a = &contacts[i];
+            let b = &contacts[j];
+            let sim = name_similarity(&a.first_name, &a.last_name, &b.first_name, &b.last_name);
+            if sim >= name_threshold {
+                add_reason(&a.id, &b.id, "name");
+            }
+        }
+    }
+
+    if phonetic {
+        let mut phonetic_map: HashMap<String, Vec<String>> = HashMap::new();
+        for c in contacts.iter() {
+            if let Some(key) = phonetic_name_key(&c.first_name, &c.last_name) {
+                phonetic_map.entry(key).or_default().push(c.id.clone());
+            }
+        }
+        for ids in phonetic_map.values() {
+            for i in 0..ids.len() {
+                for j in (i + 1)..ids.len() {
+                    add_reason(&ids[i], &ids[j], "phonetic");
+                }
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for ((a_id, b_id), flags) in pair_reasons {
+        if let (Some(a), Some(b)) = (by_id.get(&a_id), by_id.get(&b_id)) {
+            let mut reasons = Vec::new();
+            if flags.email {
+                reasons.push("email".to_string());
+            }
+            if flags.phone {
+                reasons.push("phone".to_string());
+            }
+            if flags.name {
+                reasons.push("name".to_string());
+            }
+            if flags.phonetic {
+                reasons.push("phonetic".to_string());
+            }
+            if !reasons.is_empty() {
+                let name_sim = name_similarity(&a.first_name, &a.last_name, &b.first_name, &b.last_name);
+                candidates.push(DedupCandidate {
+                    a: a.clone(),
+                    b: b.clone(),
+                    reasons,
+                    confidence: dedup_confidence(flags.email, flags.phone, name_sim),
+                });
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.confidence.total_cmp(&x.confidence));
+
+    Ok(candidates)
+}
+
+/// Likely-duplicate companies: same normalized domain and/or names at least 0.85 similar
+/// (legal suffixes such as "Inc" or "A.Ş." ignored).
+#[tauri::command]
+pub fn company_dedup_candidates(db: State<DbState>) -> Result<Vec<CompanyDedupCandidate>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!("{} ORDER BY updated_at DESC", COMPANY_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let companies: Vec<Company> = stmt
+        .query_map([], row_to_company)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    let domains: Vec<Option<String>> = companies
+        .iter()
+        .map(|c| {
+            normalize_domain(&c.domain).map(|d| {
+                let d = d.to_lowercase();
+                d.strip_prefix("www.").map(str::to_string).unwrap_or(d)
+            })
+        })
+        .collect();
+
+    let name_threshold = 0.85;
+    let mut candidates = Vec::new();
+    for i in 0..companies.len() {
+        for j in (i + 1)..companies.len() {
+            let domain = domains[i].is_some() && domains[i] == domains[j];
+            let sim = company_name_similarity(&companies[i].name, &companies[j].name);
+            let name = sim >= name_threshold;
+            if !domain && !name {
+                continue;
+            }
+            let mut reasons = Vec::new();
+            if name {
+                reasons.push("name".to_string());
+            }
+            if domain {
+                reasons.push("domain".to_string());
+            }
+            // Same weights as contacts: a shared domain counts like a shared email.
+            candidates.push(CompanyDedupCandidate {
+                a: companies[i].clone(),
+                b: companies[j].clone(),
+                reasons,
+                confidence: dedup_confidence(domain, false, sim),
+            });
+        }
+    }
+    candidates.sort_by(|x, y| y.confidence.total_cmp(&x.confidence));
+    Ok(candidates)
+}
+
+#[tauri::command]
+pub fn contact_merge(db: State<DbState>, input: MergeContactInput) -> Result<Contact, String> {
+    let mut guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = guard.as_mut().ok_or("DB not initialized")?;
+    merge_contacts(conn, input)
+}
+
+fn merge_contacts(conn: &mut rusqlite::Connection, input: MergeContactInput) -> Result<Contact, String> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    if !is_valid_email(&input.merged.email) || !is_valid_email(&input.merged.email_secondary) {
+        return Err("Geçersiz email formatı".to_string());
+    }
+    if !is_valid_phone(&input.merged.phone) || !is_valid_phone(&input.merged.phone_secondary) {
+        return Err("Geçersiz telefon formatı".to_string());
+    }
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let primary = stmt
+        .query_row(params![input.primary_id.clone()], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    let secondary = stmt
+        .query_row(params![input.secondary_id.clone()], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+
+    let (last_touched_at, next_touch_at) = merged_touch_dates(&primary, &secondary);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let record = merge_record(&tx, &input.primary_id, &input.secondary_id)?;
+
+    tx.execute(
+        "UPDATE contacts SET first_name=?1, last_name=?2, title=?3, company=?4, company_id=?5, city=?6, country=?7, email=?8, email_secondary=?9, phone=?10, phone_secondary=?11, phone_normalized=?12, phone_secondary_normalized=?13, linkedin_url=?14, twitter_url=?15, website=?16, notes=?17, last_touched_at=?18, next_touch_at=?19, updated_at=?20,
+                email_verified = CASE WHEN email IS ?8 THEN email_verified ELSE 0 END,
+                phone_verified = CASE WHEN phone IS ?10 THEN phone_verified ELSE 0 END
+             WHERE id=?21",
+        params![
+            input.merged.first_name,
+            input.merged.last_name,
+            input.merged.title,
+            input.merged.company,
+            input.merged.company_id,
+            input.merged.city,
+            input.merged.country,
+            input.merged.email,
+            input.merged.email_secondary,
+            input.merged.phone,
+            input.merged.phone_secondary,
+            normalize_phone(&input.merged.phone),
+            normalize_phone(&input.merged.phone_secondary),
+            input.merged.linkedin_url,
+            input.merged.twitter_url,
+            input.merged.website,
+            input.merged.notes,
+            last_touched_at,
+            next_touch_at,
+            now,
+            &input.primary_id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Merge tags
+    tx.execute(
+        "INSERT OR IGNORE INTO contact_tags (contact_id, tag_id)
+         SELECT ?1, tag_id FROM contact_tags WHERE contact_id = ?2",
+        params![&input.primary_id, &input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM contact_tags WHERE contact_id = ?1",
+        params![&input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Merge custom values: replace primary with provided values if present
+    if let Some(values) = input.custom_values {
+        tx.execute(
+            "DELETE FROM contact_custom_values WHERE contact_id = ?1",
+            params![&input.primary_id],
+        )
+        .map_err(|e| e.to_string())?;
+        for v in values {
+            tx.execute(
+                "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, ?2, ?3)",
+                params![&input.primary_id, v.field_id, v.value],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    } else {
+        tx.execute(
+            "INSERT OR IGNORE INTO contact_custom_values (contact_id, field_id, value)
+             SELECT ?1, field_id, value FROM contact_custom_values WHERE contact_id = ?2",
+            params![&input.primary_id, &input.secondary_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute(
+        "DELETE FROM contact_custom_values WHERE contact_id = ?1",
+        params![&input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Move related rows
+    tx.execute(
+        "UPDATE notes SET contact_id = ?1 WHERE contact_id = ?2",
+        params![&input.primary_id, &input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE reminders SET contact_id = ?1 WHERE contact_id = ?2",
+        params![&input.primary_id, &input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE interactions SET contact_id = ?1 WHERE contact_id = ?2",
+        params![&input.primary_id, &input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE attachments SET owner_id = ?1 WHERE owner_type = 'contact' AND owner_id = ?2",
+        params![&input.primary_id, &input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "DELETE FROM contacts WHERE id = ?1",
+        params![&input.secondary_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO contact_merges (id, primary_id, secondary_id, data, merged_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            Uuid::new_v4().to_string(),
+            &input.primary_id,
+            &input.secondary_id,
+            serde_json::to_string(&record).map_err(|e| e.to_string())?,
+            now,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let merged = stmt
+        .query_row(params![input.primary_id.clone()], row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(merged)
+}
+
+/// Latest last_touched_at and earliest next_touch_at of the two contacts.
+fn merged_touch_dates(primary: &Contact, secondary: &Contact) -> (Option<String>, Option<String>) {
+    let last_touched_at = match (primary.last_touched_at.clone(), secondary.last_touched_at.clone()) {
+        (Some(a), Some(b)) => Some(if a >= b { a } else { b }),
+        (Some(a), None) => Some(a),
+        (None, Some(b)) => Some(b),
+        _ => None,
+    };
+    let next_touch_at = match (primary.next_touch_at.clone(), secondary.next_touch_at.clone()) {
+        (Some(a), Some(b)) => Some(if a <= b { a } else { b }),
+        (Some(a), None) => Some(a),
+        (None, Some(b)) => Some(b),
+        _ => None,
+    };
+    (last_touched_at, next_touch_at)
+}
+
+/// Contact fields that contact_merge takes from `merged`.
+const MERGE_FIELDS: [&str; 15] = [
+    "first_name",
+    "last_name",
+    "title",
+    "company",
+    "company_id",
+    "city",
+    "country",
+    "email",
+    "email_secondary",
+    "phone",
+    "phone_secondary",
+    "linkedin_url",
+    "twitter_url",
+    "website",
+    "notes",
+];
+
+#[derive(Debug, Serialize)]
+pub struct MergeFieldPreview {
+    pub field: String,
+    pub primary: Option<String>,
+    pub secondary: Option<String>,
+    /// Primary's value, or the secondary's when the primary's is empty.
+    pub proposed: Option<String>,
+    /// Both sides set and different.
+    pub conflict: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergePreview {
+    pub fields: Vec<MergeFieldPreview>,
+    pub last_touched_at: Option<String>,
+    pub next_touch_at: Option<String>,
+    /// Rows moved from the secondary to the primary.
+    pub notes: usize,
+    pub reminders: usize,
+    pub interactions: usize,
+    /// Files on the secondary itself and on its notes and interactions; all of them move to the
+    /// primary.
+    pub attachments: usize,
+    /// Tag ids after the merge.
+    pub tag_ids: Vec<String>,
+    /// Custom values after a merge without explicit custom_values: primary wins, secondary fills gaps.
+    pub custom_values: Vec<CustomValueInput>,
+}
+
+/// What contact_merge would do with `primary_id` kept; nothing is written.
+#[tauri::command]
+pub fn contact_merge_preview(
+    db: State<DbState>,
+    primary_id: String,
+    secondary_id: String,
+) -> Result<MergePreview, String> {
+    if primary_id == secondary_id {
+        return Err("Aynı kişi seçildi".to_string());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    let load = |id: &str| -> Result<Contact, String> {
+        conn.query_row(&sql, params![id], row_to_contact)
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Contact not found".to_string())
+    };
+    let primary = load(&primary_id)?;
+    let secondary = load(&secondary_id)?;
+    let (last_touched_at, next_touch_at) = merged_touch_dates(&primary, &secondary);
+
+    let as_map = |c: &Contact| serde_json::to_value(c).map_err(|e| e.to_string());
+    let (a, b) = (as_map(&primary)?, as_map(&secondary)?);
+    let text = |v: &serde_json::Value, field: &str| {
+        v.get(field)
+            .and_then(|x| x.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let fields = MERGE_FIELDS
+        .iter()
+        .map(|field| {
+            let p = text(&a, field);
+            let s = text(&b, field);
+            MergeFieldPreview {
+                field: field.to_string(),
+                conflict: p.is_some() && s.is_some() && p != s,
+                proposed: p.clone().or_else(|| s.clone()),
+                primary: p,
+                secondary: s,
+            }
+        })
+        .collect();
+
+    let notes = contact_child_ids(conn, "notes", &secondary_id)?;
+    let attachments: usize = conn
+        .query_row(
+            "SELECT COUNT(*) FROM attachments
+             WHERE (owner_type = 'contact' AND owner_id = ?1)
+                OR (owner_type = 'note' AND owner_id IN (SELECT id FROM notes WHERE contact_id = ?1))
+                OR (owner_type = 'interaction' AND owner_id IN (SELECT id FROM interactions WHERE contact_id = ?1))",
+            params![secondary_id],
+            |r| r.get::<_, i64>(0),
+        )
+        .map_err(|e| e.to_string())? as usize;
+    let mut tag_ids = contact_tag_ids(conn, &primary_id)?;
+    for tag in contact_tag_ids(conn, &secondary_id)? {
+        if !tag_ids.contains(&tag) {
+            tag_ids.push(tag);
+        }
+    }
+    let mut custom_values: Vec<CustomValueInput> = contact_custom_pairs(conn, &primary_id)?
+        .into_iter()
+        .map(|(field_id, value)| CustomValueInput { field_id, value })
+        .collect();
+    for (field_id, value) in contact_custom_pairs(conn, &secondary_id)? {
+        if !custom_values.iter().any(|v| v.field_id == field_id) {
+            custom_values.push(CustomValueInput { field_id, value });
+        }
+    }
+
+    Ok(MergePreview {
+        fields,
+        last_touched_at,
+        next_touch_at,
+        notes: notes.len(),
+        reminders: contact_child_ids(conn, "reminders", &secondary_id)?.len(),
+        interactions: contact_child_ids(conn, "interactions", &secondary_id)?.len(),
+        attachments,
+        tag_ids,
+        custom_values,
+    })
+}
+
+// ---- Unmerge (A5): reverse a recorded contact_merge ----
+
+type RowJson = serde_json::Map<String, serde_json::Value>;
+
+/// What contact_merge overwrote or reassigned; stored as JSON in contact_merges.data.
+#[derive(Debug, Serialize, Deserialize)]
+struct MergeRecord {
+    primary_row: RowJson,
+    secondary_row: RowJson,
+    primary_tags: Vec<String>,
+    secondary_tags: Vec<String>,
+    primary_custom: Vec<(String, Option<String>)>,
+    secondary_custom: Vec<(String, Option<String>)>,
+    notes: Vec<String>,
+    reminders: Vec<String>,
+    interactions: Vec<String>,
+    /// Files attached to the secondary contact itself (older records don't have it).
+    #[serde(default)]
+    attachments: Vec<String>,
+}
+
+/// Row keyed by column name. Used for `SELECT *` so new columns are carried along without code changes.
+fn row_json(row: &Row, names: &[String]) -> rusqlite::Result<RowJson> {
+    use rusqlite::types::ValueRef;
+    let mut map = RowJson::new();
+    for (i, name) in names.iter().enumerate() {
+        let value = match row.get_ref(i)? {
+            ValueRef::Integer(n) => serde_json::Value::from(n),
+            ValueRef::Real(f) => serde_json::Value::from(f),
+            ValueRef::Text(t) => serde_json::Value::from(String::from_utf8_lossy(t).into_owned()),
+            // None of the exported tables has BLOB columns
+            ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+        };
+        map.insert(name.clone(), value);
+    }
+    Ok(map)
+}
+
+/// Full contacts row keyed by column name, so new columns survive an unmerge without code changes.
+fn contact_row_json(conn: &rusqlite::Connection, id: &str) -> Result<RowJson, String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM contacts WHERE id = ?1")
+        .map_err(|e| e.to_string())?;
+    let names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+    stmt.query_row(params![id], |row| row_json(row, &names))
+        .map_err(|e| e.to_string())
+}
+
+fn json_to_sql(value: &serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value;
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(Value::Integer)
+            .unwrap_or_else(|| Value::Real(n.as_f64().unwrap_or_default())),
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+fn contact_child_ids(conn: &rusqlite::Connection, table: &str, contact_id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT id FROM {} WHERE contact_id = ?1", table))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+fn contact_attachment_ids(conn: &rusqlite::Connection, contact_id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM attachments WHERE owner_type = 'contact' AND owner_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+fn contact_tag_ids(conn: &rusqlite::Connection, contact_id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT tag_id FROM contact_tags WHERE contact_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |r| r.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+fn contact_custom_pairs(conn: &rusqlite::Connection, contact_id: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT field_id, value FROM contact_custom_values WHERE contact_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Captured inside the merge transaction, before anything is changed.
+fn merge_record(conn: &rusqlite::Connection, primary_id: &str, secondary_id: &str) -> Result<MergeRecord, String> {
+    Ok(MergeRecord {
+        primary_row: contact_row_json(conn, primary_id)?,
+        secondary_row: contact_row_json(conn, secondary_id)?,
+        primary_tags: contact_tag_ids(conn, primary_id)?,
+        secondary_tags: contact_tag_ids(conn, secondary_id)?,
+        primary_custom: contact_custom_pairs(conn, primary_id)?,
+        secondary_custom: contact_custom_pairs(conn, secondary_id)?,
+        notes: contact_child_ids(conn, "notes", secondary_id)?,
+        reminders: contact_child_ids(conn, "reminders", secondary_id)?,
+        interactions: contact_child_ids(conn, "interactions", secondary_id)?,
+        attachments: contact_attachment_ids(conn, secondary_id)?,
+    })
+}
+
+fn restore_contact_custom(conn: &rusqlite::Connection, contact_id: &str, values: &[(String, Option<String>)]) -> Result<(), String> {
+    conn.execute("DELETE FROM contact_custom_values WHERE contact_id = ?1", params![contact_id])
+        .map_err(|e| e.to_string())?;
+    for (field_id, value) in values {
+        conn.execute(
+            "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, ?2, ?3)",
+            params![contact_id, field_id, value],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContactMergeInfo {
+    pub id: String,
+    pub primary_id: String,
+    pub secondary_id: String,
+    pub secondary_name: String,
+    pub merged_at: String,
+    pub undone_at: Option<String>,
+}
+
+/// Merges into `contact_id` (as primary), newest first.
+#[tauri::command]
+pub fn contact_merges_list(db: State<DbState>, contact_id: String) -> Result<Vec<ContactMergeInfo>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, primary_id, secondary_id, data, merged_at, undone_at FROM contact_merges
+             WHERE primary_id = ?1 ORDER BY merged_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contact_id], |r| {
+            let data: String = r.get(3)?;
+            let secondary_name = serde_json::from_str::<MergeRecord>(&data)
+                .map(|rec| {
+                    let field = |k: &str| rec.secondary_row.get(k).and_then(|v| v.as_str()).unwrap_or("").to_string();
+                    format!("{} {}", field("first_name"), field("last_name")).trim().to_string()
+                })
+                .unwrap_or_default();
+            Ok(ContactMergeInfo {
+                id: r.get(0)?,
+                primary_id: r.get(1)?,
+                secondary_id: r.get(2)?,
+                secondary_name,
+                merged_at: r.get(4)?,
+                undone_at: r.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeLogEntry {
+    pub id: String,
+    pub primary_id: String,
+    /// Current name of the kept contact; empty if it no longer exists.
+    pub primary_name: String,
+    pub secondary_id: String,
+    /// The absorbed contact's row as it was right before the merge.
+    pub secondary: RowJson,
+    /// Rows moved over from the secondary.
+    pub notes: usize,
+    pub reminders: usize,
+    pub interactions: usize,
+    pub merged_at: String,
+    pub undone_at: Option<String>,
+}
+
+/// All recorded merges (contact_merges), newest first; `limit` defaults to 200.
+#[tauri::command]
+pub fn merge_log_list(db: State<DbState>, limit: Option<i64>) -> Result<Vec<MergeLogEntry>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.id, m.primary_id, TRIM(COALESCE(c.first_name, '') || ' ' || COALESCE(c.last_name, '')),
+                    m.secondary_id, m.data, m.merged_at, m.undone_at
+             FROM contact_merges m LEFT JOIN contacts c ON c.id = m.primary_id
+             ORDER BY m.merged_at DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![limit.unwrap_or(200).max(1)], |r| {
+            let data: String = r.get(4)?;
+            let record = serde_json::from_str::<MergeRecord>(&data).ok();
+            Ok(MergeLogEntry {
+                id: r.get(0)?,
+                primary_id: r.get(1)?,
+                primary_name: r.get(2)?,
+                secondary_id: r.get(3)?,
+                secondary: record.as_ref().map(|rec| rec.secondary_row.clone()).unwrap_or_default(),
+                notes: record.as_ref().map_or(0, |rec| rec.notes.len()),
+                reminders: record.as_ref().map_or(0, |rec| rec.reminders.len()),
+                interactions: record.as_ref().map_or(0, |rec| rec.interactions.len()),
+                merged_at: r.get(5)?,
+                undone_at: r.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Recreates the secondary contact and moves back exactly the notes/reminders/interactions/files
+/// the merge reassigned. The primary's fields, tags and custom values are restored only if it
+/// hasn't been edited since the merge; otherwise they are left as they are now.
+#[tauri::command]
+pub fn contact_unmerge(db: State<DbState>, merge_id: String) -> Result<Contact, String> {
+    let mut guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = guard.as_mut().ok_or("DB not initialized")?;
+    unmerge_contacts(conn, &merge_id)
+}
+
+fn unmerge_contacts(conn: &mut rusqlite::Connection, merge_id: &str) -> Result<Contact, String> {
+    let (primary_id, secondary_id, data, merged_at, undone_at): (String, String, String, String, Option<String>) = conn
+        .query_row(
+            "SELECT primary_id, secondary_id, data, merged_at, undone_at FROM contact_merges WHERE id = ?1",
+            params![merge_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("Merge not found")?;
+    if undone_at.is_some() {
+        return Err("Bu birleştirme zaten geri alındı".to_string());
+    }
+    let record: MergeRecord = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let secondary_exists: bool = tx
+        .query_row("SELECT 1 FROM contacts WHERE id = ?1", params![secondary_id], |_| Ok(true))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if secondary_exists {
+        return Err("İkincil kişi zaten mevcut".to_string());
+    }
+    let primary_updated_at: Option<String> = tx
+        .query_row("SELECT updated_at FROM contacts WHERE id = ?1", params![primary_id], |r| r.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let primary_untouched = primary_updated_at.as_deref() == Some(merged_at.as_str());
+
+    let columns: Vec<&String> = record.secondary_row.keys().collect();
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+    tx.execute(
+        &format!(
+            "INSERT INTO contacts ({}) VALUES ({})",
+            columns.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "),
+            placeholders.join(", ")
+        ),
+        rusqlite::params_from_iter(record.secondary_row.values().map(json_to_sql)),
+    )
+    .map_err(|e| e.to_string())?;
+
+    for (table, ids) in [
+        ("notes", &record.notes),
+        ("reminders", &record.reminders),
+        ("interactions", &record.interactions),
+    ] {
+        for id in ids {
+            tx.execute(
+                &format!("UPDATE {} SET contact_id = ?1 WHERE id = ?2 AND contact_id = ?3", table),
+                params![secondary_id, id, primary_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    for id in &record.attachments {
+        tx.execute(
+            "UPDATE attachments SET owner_id = ?1 WHERE id = ?2 AND owner_type = 'contact' AND owner_id = ?3",
+            params![secondary_id, id, primary_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for tag_id in &record.secondary_tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO contact_tags (contact_id, tag_id) VALUES (?1, ?2)",
+            params![secondary_id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    restore_contact_custom(&tx, &secondary_id, &record.secondary_custom)?;
+
+    if primary_untouched {
+        let assignments: Vec<String> = record
+            .primary_row
+            .keys()
+            .enumerate()
+            .map(|(i, c)| format!("{} = ?{}", c, i + 2))
+            .collect();
+        let mut values = vec![rusqlite::types::Value::Text(primary_id.clone())];
+        values.extend(record.primary_row.values().map(json_to_sql));
+        tx.execute(
+            &format!("UPDATE contacts SET {} WHERE id = ?1", assignments.join(", ")),
+            rusqlite::params_from_iter(values),
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM contact_tags WHERE contact_id = ?1", params![primary_id])
+            .map_err(|e| e.to_string())?;
+        for tag_id in &record.primary_tags {
+            tx.execute(
+                "INSERT INTO contact_tags (contact_id, tag_id) VALUES (?1, ?2)",
+                params![primary_id, tag_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        restore_contact_custom(&tx, &primary_id, &record.primary_custom)?;
+    } else {
+        refresh_next_touch(&tx, &primary_id, &now)?;
+    }
+    refresh_next_touch(&tx, &secondary_id, &now)?;
+
+    tx.execute(
+        "UPDATE contact_merges SET undone_at = ?1 WHERE id = ?2",
+        params![now, merge_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let sql = format!("{} WHERE c.id = ?1", CONTACT_SELECT);
+    conn.query_row(&sql, params![secondary_id], row_to_contact)
+        .map_err(|e| e.to_string())
+}
+
+// ---- Referential integrity (A5 merges, imports) ----
+
+/// (table, contact reference column, extra filter, id column for samples). foreign_keys is off,
+/// so nothing stops these from pointing at merged-away or deleted contacts.
+const CONTACT_REFERENCES: [(&str, &str, &str, &str); 6] = [
+    ("notes", "contact_id", "1", "id"),
+    ("interactions", "contact_id", "1", "id"),
+    ("reminders", "contact_id", "1", "id"),
+    ("attachments", "owner_id", "owner_type = 'contact'", "id"),
+    ("contact_custom_values", "contact_id", "1", "contact_id || ':' || field_id"),
+    ("contact_tags", "contact_id", "1", "contact_id || ':' || tag_id"),
+];
+
+fn orphan_filter(table: &str, column: &str, extra: &str) -> String {
+    format!(
+        "{extra} AND NOT EXISTS (SELECT 1 FROM contacts c WHERE c.id = {table}.{column})",
+        extra = extra,
+        table = table,
+        column = column
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrphanReport {
+    pub table: String,
+    pub count: i64,
+    /// Up to 10 row ids (composite keys as "contact_id:other_id").
+    pub sample_ids: Vec<String>,
+}
+
+fn referential_orphans(conn: &rusqlite::Connection) -> Result<Vec<OrphanReport>, String> {
+    let mut out = Vec::new();
+    for (table, column, extra, id_expr) in CONTACT_REFERENCES {
+        let filter = orphan_filter(table, column, extra);
+        let count: i64 = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter), [], |r| r.get(0))
+            .map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM {} WHERE {} LIMIT 10", id_expr, table, filter))
+            .map_err(|e| e.to_string())?;
+        let sample_ids = stmt
+            .query_map([], |r| r.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        out.push(OrphanReport {
+            table: table.to_string(),
+            count,
+            sample_ids,
+        });
+    }
+    Ok(out)
+}
+
+/// Rows in notes/interactions/reminders/attachments/custom values/tags whose contact no longer exists.
+#[tauri::command]
+pub fn referential_audit(db: State<DbState>) -> Result<Vec<OrphanReport>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    referential_orphans(conn)
+}
+
+/// Reattaches orphans to `reattach_to` (rows that would duplicate an existing key are dropped),
+/// or deletes them when None. Returns the orphan report as it was before the repair, and the
+/// attachment files to remove after commit.
+fn repair_orphans(
+    conn: &rusqlite::Connection,
+    reattach_to: Option<&str>,
+) -> Result<(Vec<OrphanReport>, Vec<String>), String> {
+    let before = referential_orphans(conn)?;
+    let mut files = Vec::new();
+    for (table, column, extra, _) in CONTACT_REFERENCES {
+        let filter = orphan_filter(table, column, extra);
+        if let Some(target) = reattach_to {
+            conn.execute(
+                &format!("UPDATE OR IGNORE {} SET {} = ?1 WHERE {}", table, column, filter),
+                params![target],
+            )
+            .map_err(|e| e.to_string())?;
+        } else if table == "attachments" {
+            let mut stmt = conn
+                .prepare(&format!("SELECT storage_path FROM attachments WHERE {}", filter))
+                .map_err(|e| e.to_string())?;
+            let paths = stmt
+                .query_map([], |r| r.get::<_, String>(0))
+                .map_err(|e| e.to_string())?
+                .filter_map(|r| r.ok());
+            files.extend(paths);
+        }
+        conn.execute(&format!("DELETE FROM {} WHERE {}", table, filter), [])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok((before, files))
+}
+
+#[tauri::command]
+pub fn referential_repair(
+    db: State<DbState>,
+    reattach_to: Option<String>,
+) -> Result<Vec<OrphanReport>, String> {
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if let Some(target) = reattach_to.as_deref() {
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM contacts WHERE id = ?1)",
+                params![target],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err("Contact not found".to_string());
+        }
+    }
+    let (report, files) = repair_orphans(&tx, reattach_to.as_deref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    remove_attachment_files(&files);
+    Ok(report)
+}
+
+// ---- View preferences (columns / sort per list, stored in app_settings) ----
+
+const VIEW_PREF_VIEWS: [&str; 6] = [
+    "contacts",
+    "companies",
+    "inbox",
+    "dashboard",
+    "contact_detail",
+    "company_detail",
+];
+const VIEW_PREFS_MAX_BYTES: usize = 16 * 1024;
+
+fn view_prefs_key(view: &str) -> Result<String, String> {
+    if VIEW_PREF_VIEWS.contains(&view) {
+        Ok(format!("view_prefs:{}", view))
+    } else {
+        Err(format!("Unknown view: {}", view))
+    }
+}
+
+/// Returns the opaque JSON blob the frontend stored for `view`, if any.
+#[tauri::command]
+pub fn view_prefs_get(db: State<DbState>, view: String) -> Result<Option<String>, String> {
+    let key = view_prefs_key(&view)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_get(conn, &key)
+}
+
+/// Stores per-view preferences; `json` must be valid JSON (shape is up to the frontend).
+#[tauri::command]
+pub fn view_prefs_set(db: State<DbState>, view: String, json: String) -> Result<(), String> {
+    let key = view_prefs_key(&view)?;
+    if json.len() > VIEW_PREFS_MAX_BYTES {
+        return Err("View preferences too large".to_string());
+    }
+    serde_json::from_str::<serde_json::Value>(&json).map_err(|e| format!("Geçersiz JSON: {}", e))?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, &key, &json)
+}
+
+// ---- E3 Export (data portability): write to user-chosen path ----
+
+/// Writes string content to a file at the given path. Path comes from the save dialog (E3.3).
+#[tauri::command]
+pub fn write_export_file(path: String, content: String) -> Result<(), String> {
+    std::fs::write(&path, content.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Which contacts a segment export includes; every given criterion must match. Trashed contacts
+/// are always left out.
+#[derive(Debug, Default, Deserialize)]
+pub struct SegmentFilter {
+    pub contact_ids: Option<Vec<String>>,
+    /// tag_id / company_id / city / country / stage, matched like global_search's filters.
+    #[serde(flatten)]
+    pub filters: GlobalSearchFilters,
+    /// Full-text query, matched like global_search's.
+    pub query: Option<String>,
+    /// Saved segment whose query and filters replace `query` / `filters`.
+    pub segment_id: Option<String>,
+    #[serde(default)]
+    pub exclude_dnc: bool,
+    /// Heading of the page; defaults to "Kişi listesi".
+    pub title: Option<String>,
+}
+
+fn segment_contacts(
+    conn: &rusqlite::Connection,
+    filter: &SegmentFilter,
+    saved: Option<&Segment>,
+) -> Result<Vec<Contact>, String> {
+    let (query, filters) = match saved {
+        Some(segment) => (Some(segment.query.as_str()), &segment.filters),
+        None => (filter.query.as_deref(), &filter.filters),
+    };
+    let mut clauses: Vec<String> = vec!["c.deleted_at IS NULL".to_string()];
+    let mut values: Vec<String> = Vec::new();
+    if let Some(ref ids) = filter.contact_ids {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        // Bundled SQLite allows 32766 bound parameters.
+        if ids.len() > 30_000 {
+            return Err("En fazla 30000 kişi seçilebilir".to_string());
+        }
+        clauses.push(format!("c.id IN ({})", vec!["?"; ids.len()].join(", ")));
+        values.extend(ids.iter().cloned());
+    }
+    if let Some(q) = query.map(str::trim).filter(|s| !s.is_empty()) {
+        clauses.push("c.rowid IN (SELECT rowid FROM contacts_fts WHERE contacts_fts MATCH ?)".to_string());
+        values.push(fts_prefix_query(q, false));
+    }
+    let (filter_clauses, filter_params) = contact_filter_clauses(filters);
+    clauses.extend(filter_clauses);
+    values.extend(filter_params);
+    if filter.exclude_dnc {
+        clauses.push("c.do_not_contact = 0".to_string());
+    }
+    let sql = format!(
+        "{} WHERE {} ORDER BY c.last_name COLLATE NOCASE, c.first_name COLLATE NOCASE",
+        CONTACT_SELECT,
+        clauses.join(" AND ")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), row_to_contact)
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Self-contained page (inline CSS, no scripts or external resources); every value is escaped.
+fn segment_html(title: &str, contacts: &[Contact], generated_at: &str) -> String {
+    fn cell(values: &[&Option<String>], link: Option<&str>) -> String {
+        values
+            .iter()
+            .filter_map(|v| v.as_deref().map(str::trim).filter(|s| !s.is_empty()))
+            .map(|v| match link {
+                Some(scheme) => format!("<a href=\"{}{}\">{}</a>", scheme, html_escape(v), html_escape(v)),
+                None => html_escape(v),
+            })
+            .collect::<Vec<_>>()
+            .join("<br>")
+    }
+    let mut rows = String::new();
+    for c in contacts {
+        let name = format!("{} {}", c.first_name.trim(), c.last_name.trim());
+        let location = [c.city.as_deref(), c.country.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            html_escape(name.trim()),
+            cell(&[&c.company], None),
+            cell(&[&c.title], None),
+            cell(&[&c.email, &c.email_secondary], Some("mailto:")),
+            cell(&[&c.phone, &c.phone_secondary], Some("tel:")),
+            html_escape(&location),
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"tr\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n\
+         <style>\n\
+         body {{ font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", sans-serif; margin: 2rem; color: #1f2933; }}\n\
+         h1 {{ font-size: 1.4rem; margin-bottom: 0.25rem; }}\n\
+         p.meta {{ color: #6b7280; font-size: 0.85rem; margin-top: 0; }}\n\
+         table {{ border-collapse: collapse; width: 100%; font-size: 0.9rem; }}\n\
+         th, td {{ text-align: left; padding: 0.5rem 0.75rem; border-bottom: 1px solid #e5e7eb; vertical-align: top; }}\n\
+         th {{ background: #f9fafb; font-weight: 600; }}\n\
+         a {{ color: #2563eb; text-decoration: none; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"meta\">{count} kişi · {generated_at}</p>\n\
+         <table>\n<thead><tr><th>Ad</th><th>Şirket</th><th>Ünvan</th><th>E-posta</th><th>Telefon</th><th>Konum</th></tr></thead>\n\
+         <tbody>\n{rows}</tbody>\n</table>\n</body>\n</html>\n",
+        title = html_escape(title),
+        count = contacts.len(),
+        generated_at = html_escape(generated_at),
+        rows = rows,
+    )
+}
+
+/// Read-only HTML page of the contacts matching `filter`, for sharing with people without
+/// VaultCRM; save it with write_export_file as .html.
+#[tauri::command]
+pub fn segment_export_html(db: State<DbState>, filter: SegmentFilter) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let saved = filter
+        .segment_id
+        .as_deref()
+        .map(|id| segment_by_id(conn, id))
+        .transpose()?;
+    let contacts = segment_contacts(conn, &filter, saved.as_ref())?;
+    let title = filter
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .or(saved.as_ref().map(|s| s.name.as_str()))
+        .unwrap_or("Kişi listesi");
+    let generated_at = Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+    Ok(segment_html(title, &contacts, &generated_at))
+}
+
+const EXPORT_JSON_VERSION: i64 = 1;
+
+/// Tables in a JSON export, parents first. Settings, snapshots, merges and attachments
+/// (files live outside the DB) are not part of it.
+const EXPORT_JSON_TABLES: &[&str] = &[
+    "companies",
+    "custom_fields",
+    "tags",
+    "contacts",
+    "contact_custom_values",
+    "company_custom_values",
+    "contact_tags",
+    "notes",
+    "interactions",
+    "reminders",
+];
+
+/// The whole database as `{ version, exported_at, contacts: [...], companies: [...], ... }`, one
+/// array of full rows per table; save it with write_export_file.
+#[tauri::command]
+pub fn export_json(db: State<DbState>) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let doc = export_json_doc(conn)?;
+    serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())
+}
+
+fn export_json_doc(conn: &rusqlite::Connection) -> Result<RowJson, String> {
+    let mut doc = RowJson::new();
+    doc.insert("version".to_string(), EXPORT_JSON_VERSION.into());
+    doc.insert(
+        "exported_at".to_string(),
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string().into(),
+    );
+    for table in EXPORT_JSON_TABLES {
+        let mut stmt = conn
+            .prepare(&format!("SELECT * FROM {} ORDER BY rowid", table))
+            .map_err(|e| e.to_string())?;
+        let names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+        let rows: Vec<serde_json::Value> = stmt
+            .query_map([], |row| row_json(row, &names))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .map(serde_json::Value::Object)
+            .collect();
+        doc.insert(table.to_string(), serde_json::Value::Array(rows));
+    }
+    Ok(doc)
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonImportCount {
+    pub table: String,
+    pub inserted: u64,
+    /// Rows whose id (or key) already existed; existing rows are never overwritten.
+    pub ignored: u64,
+}
+
+/// Restores an export_json document in one transaction, keeping ids so relationships stay intact.
+/// Uses INSERT OR IGNORE, so rows already present win; unknown columns are dropped.
+#[tauri::command]
+pub fn import_json(db: State<DbState>, content: String) -> Result<Vec<JsonImportCount>, String> {
+    let doc = parse_export_json(&content)?;
+    let mut conn_guard = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn_guard.as_mut().ok_or("DB not initialized")?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let counts = import_json_tables(&tx, &doc)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(counts)
+}
+
+fn parse_export_json(content: &str) -> Result<RowJson, String> {
+    let doc: RowJson = serde_json::from_str(content).map_err(|e| format!("Geçersiz JSON: {}", e))?;
+    match doc.get("version").and_then(|v| v.as_i64()) {
+        Some(EXPORT_JSON_VERSION) => Ok(doc),
+        Some(v) => Err(format!("Desteklenmeyen dışa aktarma sürümü: {}", v)),
+        None => Err("Dışa aktarma sürümü eksik".to_string()),
+    }
+}
 
-    let mut email_map: HashMap<String, Vec<String>> = HashMap::new();
-    let mut phone_map: HashMap<String, Vec<String>> = HashMap::new();
+/// Tables compared by diff_exports; each is keyed by its `id` column.
+const DIFF_EXPORT_TABLES: [&str; 3] = ["contacts", "companies", "notes"];
+
+#[derive(Debug, Serialize)]
+pub struct RowChange {
+    pub id: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableDiff {
+    pub table: String,
+    /// Ids only in `b`.
+    pub added: Vec<String>,
+    /// Ids only in `a`.
+    pub removed: Vec<String>,
+    pub changed: Vec<RowChange>,
+}
+
+/// Compares two export_json documents (`a` = before, `b` = after); updated_at alone is not a change.
+/// Read-only; nothing is written.
+#[tauri::command]
+pub fn diff_exports(a_json: String, b_json: String) -> Result<Vec<TableDiff>, String> {
+    let a = parse_export_json(&a_json)?;
+    let b = parse_export_json(&b_json)?;
+    Ok(diff_export_docs(&a, &b))
+}
+
+fn diff_export_docs(a: &RowJson, b: &RowJson) -> Vec<TableDiff> {
+    fn rows_by_id<'a>(doc: &'a RowJson, table: &str) -> std::collections::BTreeMap<&'a str, &'a RowJson> {
+        doc.get(table)
+            .and_then(|v| v.as_array())
+            .map(|rows| {
+                rows.iter()
+                    .filter_map(|r| r.as_object())
+                    .filter_map(|r| r.get("id").and_then(|v| v.as_str()).map(|id| (id, r)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    let null = serde_json::Value::Null;
+    DIFF_EXPORT_TABLES
+        .iter()
+        .map(|table| {
+            let before = rows_by_id(a, table);
+            let after = rows_by_id(b, table);
+            let added = after
+                .keys()
+                .filter(|id| !before.contains_key(*id))
+                .map(|id| id.to_string())
+                .collect();
+            let removed = before
+                .keys()
+                .filter(|id| !after.contains_key(*id))
+                .map(|id| id.to_string())
+                .collect();
+            let mut changed = Vec::new();
+            for (id, old) in before.iter() {
+                let Some(new) = after.get(id) else { continue };
+                let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+                names.sort();
+                names.dedup();
+                names.retain(|n| n.as_str() != "updated_at");
+                let fields: Vec<FieldChange> = names
+                    .into_iter()
+                    .filter_map(|name| {
+                        let x = old.get(name).unwrap_or(&null);
+                        let y = new.get(name).unwrap_or(&null);
+                        (x != y).then(|| FieldChange {
+                            field: name.clone(),
+                            before: x.clone(),
+                            after: y.clone(),
+                        })
+                    })
+                    .collect();
+                if !fields.is_empty() {
+                    changed.push(RowChange {
+                        id: id.to_string(),
+                        fields,
+                    });
+                }
+            }
+            TableDiff {
+                table: table.to_string(),
+                added,
+                removed,
+                changed,
+            }
+        })
+        .collect()
+}
+
+fn import_json_tables(conn: &rusqlite::Connection, doc: &RowJson) -> Result<Vec<JsonImportCount>, String> {
+    let mut counts = Vec::new();
+    for table in EXPORT_JSON_TABLES {
+        let Some(rows) = doc.get(*table).and_then(|v| v.as_array()) else { continue };
+        let mut stmt = conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .map_err(|e| e.to_string())?;
+        let known: std::collections::HashSet<String> = stmt
+            .query_map([], |r| r.get::<_, String>(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        let mut count = JsonImportCount {
+            table: table.to_string(),
+            inserted: 0,
+            ignored: 0,
+        };
+        for row in rows {
+            let Some(obj) = row.as_object() else {
+                return Err(format!("{}: satır nesne olmalı", table));
+            };
+            let (columns, values): (Vec<&String>, Vec<rusqlite::types::Value>) = obj
+                .iter()
+                .filter(|(k, _)| known.contains(*k))
+                .map(|(k, v)| (k, json_to_sql(v)))
+                .unzip();
+            if columns.is_empty() {
+                count.ignored += 1;
+                continue;
+            }
+            let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+            let sql = format!(
+                "INSERT OR IGNORE INTO {} ({}) VALUES ({})",
+                table,
+                columns.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "),
+                placeholders.join(", ")
+            );
+            let changed = conn
+                .execute(&sql, rusqlite::params_from_iter(values))
+                .map_err(|e| format!("{}: {}", table, e))?;
+            if changed > 0 {
+                count.inserted += 1;
+            } else {
+                count.ignored += 1;
+            }
+        }
+        counts.push(count);
+    }
+    Ok(counts)
+}
+
+// ---- F1 Encryption & key (F1.2 keychain, F1.3 first-run setup) ----
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionStateResponse {
+    Ready,
+    NeedSetup { reason: crate::db::SetupReason },
+}
+
+/// F1.3: Returns "ready" or need_setup with reason (first_run / migrate_plain).
+#[tauri::command]
+pub fn get_encryption_state(setup: State<EncryptionSetupState>) -> Result<EncryptionStateResponse, String> {
+    let guard = setup.0.lock().map_err(|e| e.to_string())?;
+    Ok(match guard.as_ref() {
+        Some(reason) => EncryptionStateResponse::NeedSetup {
+            reason: reason.clone(),
+        },
+        None => EncryptionStateResponse::Ready,
+    })
+}
+
+/// F1.3: First-run — create key (device or passphrase), empty encrypted DB, store key in keychain.
+#[tauri::command]
+pub fn encryption_setup_create_key(app: tauri::AppHandle, passphrase: Option<String>) -> Result<(), String> {
+    crate::db::setup_create_key(&app, passphrase)
+}
+
+/// F1.1/F1.2: Migrate plain vault.db to encrypted; store key in keychain.
+#[tauri::command]
+pub fn encryption_migrate_plain_db(app: tauri::AppHandle, passphrase: Option<String>) -> Result<(), String> {
+    crate::db::migrate_plain_to_encrypted(&app, passphrase)
+}
+
+/// F1.3: Rotate the passphrase; fails with "Mevcut parola hatalı" (and changes nothing) if the old one is wrong.
+#[tauri::command]
+pub fn encryption_change_passphrase(
+    app: tauri::AppHandle,
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    let paths = paths.0.lock().map_err(|e| e.to_string())?;
+    let (temp_path, encrypted_path) = paths.as_ref().ok_or("Encrypted DB not open")?;
+    let old_attachments_key = derived_attachments_key(conn)?;
+    crate::db::change_passphrase(&app, conn, temp_path, encrypted_path, &old_passphrase, &new_passphrase)?;
+    follow_master_key_change(conn, paths.as_ref(), old_attachments_key)
+}
+
+/// UNSAFE developer mode: switches the open vault to a plain vault.db. `confirm` must equal
+/// PLAINTEXT_CONFIRM ("DISABLE ENCRYPTION").
+#[tauri::command]
+pub fn encryption_disable(
+    app: tauri::AppHandle,
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+    confirm: String,
+) -> Result<(), String> {
+    if confirm != crate::db::PLAINTEXT_CONFIRM {
+        return Err(format!("Onay için \"{}\" yazın", crate::db::PLAINTEXT_CONFIRM));
+    }
+    let mut guard = db.0.lock().map_err(|e| e.to_string())?;
+    let mut paths = paths.0.lock().map_err(|e| e.to_string())?;
+    let (temp_path, _) = paths.as_ref().ok_or("Şifreleme zaten kapalı")?;
+    let temp_path = temp_path.clone();
+    let conn = guard.as_ref().ok_or("DB not initialized")?;
+    crate::db::disable_encryption(&app, conn, &temp_path)?;
+    *guard = None;
+    let (conn, path_tuple) = crate::db::init_db(&app).map_err(|e| e.to_string())?;
+    *guard = Some(conn);
+    *paths = path_tuple;
+    let _ = std::fs::remove_file(&temp_path);
+    Ok(())
+}
+
+/// Leaves plaintext mode; `passphrase` None uses a device key (as first-run setup).
+#[tauri::command]
+pub fn encryption_enable(
+    app: tauri::AppHandle,
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let mut guard = db.0.lock().map_err(|e| e.to_string())?;
+    let mut paths = paths.0.lock().map_err(|e| e.to_string())?;
+    if paths.is_some() {
+        return Err("Şifreleme zaten açık".to_string());
+    }
+    let old_attachments_key = match guard.as_ref() {
+        Some(conn) => derived_attachments_key(conn)?,
+        None => None,
+    };
+    if let Some(conn) = guard.take() {
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);").map_err(|e| e.to_string())?;
+    }
+    let result = crate::db::enable_encryption(&app, passphrase);
+    // Reopen either way: encrypted on success, the plain file (marker still there) on failure.
+    let (conn, path_tuple) = crate::db::init_db(&app).map_err(|e| e.to_string())?;
+    if result.is_ok() {
+        follow_master_key_change(&conn, path_tuple.as_ref(), old_attachments_key)?;
+    }
+    *guard = Some(conn);
+    *paths = path_tuple;
+    result
+}
+
+/// F1.3: Unlock with the passphrase after a wrong_passphrase setup state; then call encryption_setup_open_db.
+#[tauri::command]
+pub fn encryption_unlock(app: tauri::AppHandle, passphrase: String) -> Result<(), String> {
+    crate::db::unlock_with_passphrase(&app, &passphrase)
+}
+
+/// After setup or migrate: open DB and clear setup state. Also the unlock path after lock_now;
+/// passphrase vaults then need `passphrase`.
+#[tauri::command]
+pub fn encryption_setup_open_db(
+    app: tauri::AppHandle,
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+    setup: State<EncryptionSetupState>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let locked = matches!(
+        *setup.0.lock().map_err(|e| e.to_string())?,
+        Some(crate::db::SetupReason::Locked)
+    );
+    if locked {
+        // A supplied passphrase is always checked; that also moves legacy-salt vaults to passphrase mode.
+        match passphrase.filter(|p| !p.is_empty()) {
+            Some(passphrase) => crate::db::unlock_with_passphrase(&app, &passphrase)?,
+            None if crate::db::vault_uses_passphrase(&app)? => return Err("Passphrase gerekli".into()),
+            None => {}
+        }
+    }
+    let (conn, path_tuple) = crate::db::init_db(&app).map_err(|e| e.to_string())?;
+    *db.0.lock().map_err(|e| e.to_string())? = Some(conn);
+    *paths.0.lock().map_err(|e| e.to_string())? = path_tuple;
+    *setup.0.lock().map_err(|e| e.to_string())? = None;
+    Ok(())
+}
+
+/// Flushes the encrypted DB, closes the connection and wipes the plaintext temp file. Only for
+/// encrypted vaults; unlock with encryption_setup_open_db.
+#[tauri::command]
+pub fn lock_now(
+    db: State<DbState>,
+    paths: State<EncryptedPathsState>,
+    setup: State<EncryptionSetupState>,
+) -> Result<(), String> {
+    let mut guard = db.0.lock().map_err(|e| e.to_string())?;
+    let mut paths = paths.0.lock().map_err(|e| e.to_string())?;
+    let (temp_path, encrypted_path) = paths
+        .clone()
+        .ok_or("Şifreleme kapalıyken kasa kilitlenemez")?;
+    let conn = guard.take().ok_or("DB not initialized")?;
+    if let Err(e) = crate::db::flush_encrypted_db(&conn, &temp_path, &encrypted_path) {
+        *guard = Some(conn);
+        return Err(e);
+    }
+    if let Err((conn, e)) = conn.close() {
+        *guard = Some(conn);
+        return Err(e.to_string());
+    }
+    *paths = None;
+    *setup.0.lock().map_err(|e| e.to_string())? = Some(crate::db::SetupReason::Locked);
+    crate::db::wipe_temp_db(&temp_path)
+}
+
+#[tauri::command]
+pub fn is_locked(setup: State<EncryptionSetupState>) -> Result<bool, String> {
+    let guard = setup.0.lock().map_err(|e| e.to_string())?;
+    Ok(matches!(*guard, Some(crate::db::SetupReason::Locked)))
+}
+
+/// Whether unlocking asks for a passphrase (false for device-key vaults).
+#[tauri::command]
+pub fn encryption_uses_passphrase(app: tauri::AppHandle) -> Result<bool, String> {
+    crate::db::vault_uses_passphrase(&app)
+}
+
+/// Idle minutes before the frontend calls lock_now; 0 = never.
+#[tauri::command]
+pub fn auto_lock_minutes_get(db: State<DbState>) -> Result<u32, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    Ok(setting_get(conn, "auto_lock_minutes")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0))
+}
+
+#[tauri::command]
+pub fn auto_lock_minutes_set(db: State<DbState>, minutes: u32) -> Result<(), String> {
+    if minutes > 1440 {
+        return Err("Otomatik kilit en fazla 1440 dakika olabilir".to_string());
+    }
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let conn = conn.as_ref().ok_or("DB not initialized")?;
+    setting_set(conn, "auto_lock_minutes", &minutes.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn validates_email_format() {
+        assert!(is_valid_email(&None));
+        assert!(is_valid_email(&Some("".to_string())));
+        assert!(is_valid_email(&Some("test@example.com".to_string())));
+        assert!(!is_valid_email(&Some("bad-email".to_string())));
+        assert!(!is_valid_email(&Some("no-at.example.com".to_string())));
+        assert!(!is_valid_email(&Some("a@b".to_string())));
+    }
+
+    #[test]
+    fn validates_phone_format() {
+        assert!(is_valid_phone(&None));
+        assert!(is_valid_phone(&Some("".to_string())));
+        assert!(is_valid_phone(&Some("+90 532 123 45 67".to_string())));
+        assert!(is_valid_phone(&Some("(212) 555-1212".to_string())));
+        assert!(!is_valid_phone(&Some("abc".to_string())));
+        assert!(!is_valid_phone(&Some("12".to_string())));
+    }
+
+    #[test]
+    fn resolves_company_name_from_id() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute(
+            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL)",
+            [],
+        )
+        .expect("create companies table");
+        conn.execute(
+            "INSERT INTO companies (id, name) VALUES (?1, ?2)",
+            params!["c1", "Acme"],
+        )
+        .expect("insert company");
+
+        let mut company = None;
+        let company_id = Some("c1".to_string());
+        resolve_company_name(&conn, &company_id, &mut company);
+        assert_eq!(company, Some("Acme".to_string()));
+    }
+
+    #[test]
+    fn refreshes_stale_cached_company_names() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+             CREATE TABLE contacts (id TEXT PRIMARY KEY, company TEXT, company_id TEXT, updated_at TEXT);
+             INSERT INTO companies (id, name) VALUES ('c1', 'Acme Corp.');
+             INSERT INTO contacts (id, company, company_id) VALUES
+                ('stale', 'Acme', 'c1'), ('empty', NULL, 'c1'), ('ok', 'Acme Corp.', 'c1'),
+                ('unlinked', 'Other', NULL), ('dangling', 'Gone', 'missing');",
+        )
+        .expect("setup");
+        assert_eq!(refresh_company_names(&conn, "2024-01-01T00:00:00Z").unwrap(), 2);
+        let names: Vec<(String, Option<String>)> = conn
+            .prepare("SELECT id, company FROM contacts ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("dangling".to_string(), Some("Gone".to_string())),
+                ("empty".to_string(), Some("Acme Corp.".to_string())),
+                ("ok".to_string(), Some("Acme Corp.".to_string())),
+                ("stale".to_string(), Some("Acme Corp.".to_string())),
+                ("unlinked".to_string(), Some("Other".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_override_existing_company_name() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute(
+            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL)",
+            [],
+        )
+        .expect("create companies table");
+        conn.execute(
+            "INSERT INTO companies (id, name) VALUES (?1, ?2)",
+            params!["c1", "Acme"],
+        )
+        .expect("insert company");
+
+        let mut company = Some("Manual Co".to_string());
+        let company_id = Some("c1".to_string());
+        resolve_company_name(&conn, &company_id, &mut company);
+        assert_eq!(company, Some("Manual Co".to_string()));
+    }
+
+    #[test]
+    fn validates_funding_stage_against_options() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )
+        .expect("create app_settings table");
+
+        assert_eq!(validate_funding_stage(&conn, &None), Ok(None));
+        assert_eq!(validate_funding_stage(&conn, &Some(" ".to_string())), Ok(None));
+        assert_eq!(
+            validate_funding_stage(&conn, &Some(" Seed ".to_string())),
+            Ok(Some("Seed".to_string()))
+        );
+        assert!(validate_funding_stage(&conn, &Some("Series Z".to_string())).is_err());
+
+        setting_set(&conn, "funding_stage_options", "[\"Series Z\"]").expect("set options");
+        assert!(validate_funding_stage(&conn, &Some("Seed".to_string())).is_err());
+        assert_eq!(
+            validate_funding_stage(&conn, &Some("Series Z".to_string())),
+            Ok(Some("Series Z".to_string()))
+        );
+    }
+
+    #[test]
+    fn computes_next_annual_occurrence() {
+        let d = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let today = d("2025-03-10");
+        assert_eq!(next_annual_occurrence("1990-03-12", today), Some(d("2025-03-12")));
+        assert_eq!(next_annual_occurrence("1990-03-10", today), Some(d("2025-03-10")));
+        assert_eq!(next_annual_occurrence("1990-01-05", today), Some(d("2026-01-05")));
+        assert_eq!(next_annual_occurrence("2000-02-29", d("2025-02-01")), Some(d("2025-02-28")));
+        assert_eq!(next_annual_occurrence("not a date", today), None);
+    }
+
+    #[test]
+    fn merges_attached_table_newest_wins() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute("ATTACH DATABASE ':memory:' AS remote", [])
+            .expect("attach remote");
+        for schema in ["main", "remote"] {
+            conn.execute(
+                &format!("CREATE TABLE {schema}.notes (id TEXT PRIMARY KEY, body TEXT, updated_at TEXT)"),
+                [],
+            )
+            .expect("create notes");
+        }
+        conn.execute_batch(
+            "INSERT INTO main.notes VALUES ('a', 'local a', '2024-01-02T00:00:00Z'), ('b', 'local b', '2024-01-01T00:00:00Z');
+             INSERT INTO remote.notes VALUES ('a', 'remote a', '2024-01-01T00:00:00Z'), ('b', 'remote b', '2024-01-05T00:00:00Z'), ('c', 'remote c', '2024-01-01T00:00:00Z');",
+        )
+        .expect("seed notes");
+
+        let mut summary = SyncMergeSummary::default();
+        merge_attached_table(&conn, "notes", &["id"], &["updated_at"], &mut summary).expect("merge");
+        assert_eq!((summary.inserted, summary.updated, summary.conflicts), (1, 1, 1));
+
+        let body = |id: &str| -> String {
+            conn.query_row("SELECT body FROM main.notes WHERE id = ?1", params![id], |r| r.get(0))
+                .expect("note exists")
+        };
+        assert_eq!(body("a"), "local a");
+        assert_eq!(body("b"), "remote b");
+        assert_eq!(body("c"), "remote c");
+    }
+
+    #[test]
+    fn canonicalizes_phone_numbers() {
+        let tr = Some("Türkiye".to_string());
+        let us = Some("USA".to_string());
+        assert_eq!(canonical_phone("0532 123 45 67", &tr), Some("+905321234567".to_string()));
+        assert_eq!(canonical_phone("+90 532 123 45 67", &None), Some("+905321234567".to_string()));
+        assert_eq!(canonical_phone("0090 532 123 45 67", &None), Some("+905321234567".to_string()));
+        assert_eq!(canonical_phone("(212) 555-1212", &us), Some("+12125551212".to_string()));
+        assert_eq!(canonical_phone("1 212 555 1212", &us), Some("+12125551212".to_string()));
+        assert_eq!(canonical_phone("(212) 555-1212", &None), Some("2125551212".to_string()));
+        assert_eq!(canonical_phone("+12125551212", &us), Some("+12125551212".to_string()));
+        assert_eq!(canonical_phone("12", &us), None);
+        assert_eq!(canonical_phone("call me", &us), None);
+    }
+
+    #[test]
+    fn normalizes_international_phone_formats() {
+        let key = |v: &str, code: Option<&str>| phone_e164_digits(v, code);
+        let ny = Some("12125551212".to_string());
+        assert_eq!(key("+1 212 555 1212", None), ny);
+        assert_eq!(key("001 (212) 555-1212", None), ny);
+        assert_eq!(key("212 555 1212", Some("1")), ny);
+        assert_eq!(key("+1 212 555 1212", Some("90")), ny);
+        assert_eq!(key("212 555 1212", None), Some("2125551212".to_string()));
+        let ist = Some("905321234567".to_string());
+        assert_eq!(key("0532 123 45 67", Some("90")), ist);
+        assert_eq!(key("0090 532 123 45 67", None), ist);
+        assert_eq!(key("90 532 123 45 67", Some("90")), ist);
+        assert_eq!(key("+44 20 7946 0958", Some("1")), Some("442079460958".to_string()));
+        assert_eq!(key("12345", None), None);
+        assert_eq!(key("0012345", None), None);
+    }
+
+    #[test]
+    fn parses_stored_datetimes() {
+        let expected = parse_datetime_utc("2024-05-01T10:30:00Z").expect("rfc3339");
+        assert_eq!(parse_datetime_utc("2024-05-01 10:30:00"), Some(expected));
+        assert_eq!(parse_datetime_utc("2024-05-01T10:30"), Some(expected));
+        assert_eq!(
+            parse_datetime_utc("2024-05-01"),
+            parse_datetime_utc("2024-05-01T00:00:00Z")
+        );
+        assert_eq!(parse_datetime_utc("yesterday"), None);
+    }
+
+    #[test]
+    fn projects_recurring_occurrences() {
+        let at = |s: &str| parse_datetime_utc(s).unwrap();
+        let now = at("2024-01-10T00:00:00Z");
+        let horizon = at("2024-02-10T00:00:00Z");
+        assert_eq!(
+            project_recurring(at("2024-01-12T00:00:00Z"), 10, now, horizon),
+            vec![at("2024-01-22T00:00:00Z"), at("2024-02-01T00:00:00Z")]
+        );
+        // Overdue: next one counts from now, like completing it today.
+        assert_eq!(
+            project_recurring(at("2023-12-01T00:00:00Z"), 14, now, horizon),
+            vec![at("2024-01-24T00:00:00Z"), at("2024-02-07T00:00:00Z")]
+        );
+        assert!(project_recurring(at("2024-01-12T00:00:00Z"), 0, now, horizon).is_empty());
+    }
+
+    #[test]
+    fn normalizes_domain_values() {
+        assert_eq!(normalize_domain(&None), None);
+        assert_eq!(normalize_domain(&Some("".to_string())), None);
+        assert_eq!(
+            normalize_domain(&Some(" https://example.com/path ".to_string())),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            normalize_domain(&Some("http://example.com".to_string())),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            normalize_domain(&Some("example.com".to_string())),
+            Some("example.com".to_string())
+        );
+    }
 
-    for c in contacts.iter() {
-        if let Some(e) = normalize_email(&c.email) {
-            email_map.entry(e).or_default().push(c.id.clone());
-        }
-        if let Some(e) = normalize_email(&c.email_secondary) {
-            email_map.entry(e).or_default().push(c.id.clone());
-        }
-        if let Some(p) = normalize_phone(&c.phone) {
-            phone_map.entry(p).or_default().push(c.id.clone());
-        }
-        if let Some(p) = normalize_phone(&c.phone_secondary) {
-            phone_map.entry(p).or_default().push(c.id.clone());
+    fn test_contact() -> Contact {
+        Contact {
+            id: "c1".to_string(),
+            first_name: "Ada".to_string(),
+            last_name: "Lovelace".to_string(),
+            title: None,
+            company: None,
+            company_id: None,
+            city: None,
+            country: None,
+            email: None,
+            email_secondary: None,
+            phone: None,
+            phone_secondary: None,
+            linkedin_url: None,
+            twitter_url: None,
+            website: None,
+            notes: None,
+            last_touched_at: None,
+            next_touch_at: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            do_not_contact: false,
+            consent_status: None,
+            consent_at: None,
+            timezone: None,
+            email_verified: false,
+            phone_verified: false,
+            deleted_at: None,
+            is_pinned: false,
         }
     }
 
-    let mut add_reason = |a: &str, b: &str, kind: &str| {
-        if a == b {
-            return;
-        }
-        let (x, y) = if a < b { (a.to_string(), b.to_string()) } else { (b.to_string(), a.to_string()) };
-        let entry = pair_reasons.entry((x, y)).or_default();
-        match kind {
-            "email" => entry.email = true,
-            "phone" => entry.phone = true,
-            "name" => entry.name = true,
-            _ => {}
-        }
-    };
+    #[test]
+    fn builds_vcard_skipping_missing_fields() {
+        let contact = Contact {
+            company: Some("Analytical, Inc.".to_string()),
+            city: Some("London".to_string()),
+            email: Some("ada@example.com".to_string()),
+            email_secondary: Some("  ".to_string()),
+            phone: Some("+44 20 1234 5678".to_string()),
+            phone_secondary: Some("+442012345678".to_string()),
+            notes: Some("not exported".to_string()),
+            ..test_contact()
+        };
+        assert_eq!(
+            contact_to_vcard(&contact),
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nN:Lovelace;Ada;;;\r\nFN:Ada Lovelace\r\n\
+             ORG:Analytical\\, Inc.\r\nEMAIL;TYPE=INTERNET,WORK:ada@example.com\r\n\
+             TEL;TYPE=WORK:+44 20 1234 5678\r\nADR;TYPE=WORK:;;;London;;;\r\nEND:VCARD\r\n"
+        );
+        let with_home = Contact {
+            email_secondary: Some("ada@home.example".to_string()),
+            phone_secondary: Some("+44 7700 900123".to_string()),
+            ..contact
+        };
+        let card = contact_to_vcard(&with_home);
+        assert!(card.contains("EMAIL;TYPE=INTERNET,HOME:ada@home.example\r\n"));
+        assert!(card.contains("TEL;TYPE=HOME:+44 7700 900123\r\n"));
+        let nameless = Contact {
+            first_name: String::new(),
+            last_name: String::new(),
+            website: Some("https://ada.example".to_string()),
+            linkedin_url: Some("https://linkedin.com/in/ada".to_string()),
+            ..with_home
+        };
+        let card = contact_to_vcard(&nameless);
+        assert!(card.contains("N:;;;;\r\nFN:Analytical\\, Inc.\r\n"));
+        assert!(card.contains("URL:https://ada.example\r\n"));
+        assert!(card.contains("X-SOCIALPROFILE;TYPE=linkedin:https://linkedin.com/in/ada\r\n"));
+    }
 
-    for ids in email_map.values() {
-        if ids.len() < 2 {
-            continue;
-        }
-        for i in 0..ids.len() {
-            for j in (i + 1)..ids.len() {
-                add_reason(&ids[i], &ids[j], "email");
-            }
+    #[test]
+    fn parses_multi_card_vcf() {
+        let vcf = "BEGIN:VCARD\r\nVERSION:3.0\r\nN:Lovelace;Ada;;;\r\nFN:Ada Lovelace\r\n\
+                   ORG:Analytical\\, Inc.;Engines\r\nEMAIL;TYPE=WORK:ada@example.com\r\n\
+                   EMAIL;TYPE=HOME:ada@home.example\r\nEMAIL:third@example.com\r\n\
+                   TEL;TYPE=CELL:+44 20 1234\r\n 5678\r\nADR;TYPE=WORK:;;;London;;;UK\r\n\
+                   X-SOCIALPROFILE;TYPE=linkedin:https://linkedin.com/in/ada\r\nEND:VCARD\r\n\
+                   BEGIN:VCARD\r\nFN:Charles Babbage\r\nitem1.URL:https://babbage.example\r\nEND:VCARD\r\n\
+                   BEGIN:VCARD\r\nORG:Nameless Ltd\r\nEND:VCARD\r\n";
+        let rows = parse_vcards(vcf);
+        assert_eq!(rows.len(), 3);
+        let ada = &rows[0];
+        assert_eq!(ada.first_name.as_deref(), Some("Ada"));
+        assert_eq!(ada.last_name.as_deref(), Some("Lovelace"));
+        assert_eq!(ada.company.as_deref(), Some("Analytical, Inc."));
+        assert_eq!(ada.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(ada.email_secondary.as_deref(), Some("ada@home.example"));
+        assert_eq!(ada.phone.as_deref(), Some("+44 20 12345678"));
+        assert_eq!(ada.city.as_deref(), Some("London"));
+        assert_eq!(ada.country.as_deref(), Some("UK"));
+        assert_eq!(ada.linkedin_url.as_deref(), Some("https://linkedin.com/in/ada"));
+        assert_eq!(rows[1].first_name.as_deref(), Some("Charles"));
+        assert_eq!(rows[1].last_name.as_deref(), Some("Babbage"));
+        assert_eq!(rows[1].website.as_deref(), Some("https://babbage.example"));
+        assert_eq!(import_row_problem(&rows[2]), Some("Ad ve soyad boş"));
+    }
+
+    #[test]
+    fn scores_contact_completeness() {
+        let contact = Contact {
+            last_name: String::new(),
+            company: Some("Analytical".to_string()),
+            email: Some("ada@example.com".to_string()),
+            phone: Some(" ".to_string()),
+            ..test_contact()
+        };
+        let none = std::collections::HashSet::new();
+        let base = contact_completeness(&contact, &[], &none);
+        assert_eq!(base.score, 60);
+        assert_eq!(base.missing, vec!["Telefon", "Ünvan"]);
+        let required = vec![
+            ("cf_a".to_string(), "Sektör".to_string()),
+            ("cf_b".to_string(), "Kaynak".to_string()),
+            ("cf_c".to_string(), "Segment".to_string()),
+        ];
+        let filled: std::collections::HashSet<String> = ["cf_a".to_string()].into_iter().collect();
+        let with_custom = contact_completeness(&contact, &required, &filled);
+        assert_eq!(with_custom.score, 50);
+        assert_eq!(with_custom.missing, vec!["Telefon", "Ünvan", "Kaynak", "Segment"]);
+    }
+
+    #[test]
+    fn enforces_attachment_quota() {
+        let unlimited = AttachmentQuota::default();
+        assert_eq!(attachment_quota_error(500, 1 << 30, &unlimited, 1 << 20), None);
+        let quota = AttachmentQuota {
+            max_count: Some(3),
+            max_bytes: Some(1000),
+        };
+        assert_eq!(attachment_quota_error(2, 500, &quota, 500), None);
+        assert_eq!(
+            attachment_quota_error(3, 100, &quota, 10),
+            Some("Ek kotası aşıldı: 3 / 3 dosya".to_string())
+        );
+        assert!(attachment_quota_error(1, 900, &quota, 101).is_some());
+
+        assert_eq!(attachment_size_error(DEFAULT_ATTACHMENT_MAX_BYTES, DEFAULT_ATTACHMENT_MAX_BYTES), None);
+        assert_eq!(
+            attachment_size_error(3 * 1_048_576, 2 * 1_048_576),
+            Some("Dosya çok büyük: 3.0 MB (en fazla 2.0 MB)".to_string())
+        );
+    }
+
+    #[test]
+    fn deleting_note_detaches_reminders_and_removes_attachments() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE notes (id TEXT PRIMARY KEY, contact_id TEXT NOT NULL, kind TEXT NOT NULL,
+                 title TEXT, body TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL);
+             CREATE TABLE reminders (id TEXT PRIMARY KEY, note_id TEXT REFERENCES notes(id) ON DELETE SET NULL);
+             CREATE TABLE attachments (id TEXT PRIMARY KEY, owner_type TEXT NOT NULL, owner_id TEXT NOT NULL,
+                 storage_path TEXT NOT NULL);
+             INSERT INTO notes VALUES ('n1', 'c1', 'note', NULL, 'body', 'x', 'x');
+             INSERT INTO reminders VALUES ('r1', 'n1');
+             INSERT INTO attachments VALUES ('a1', 'note', 'n1', '/nonexistent/a1.bin'),
+                 ('a2', 'contact', 'c1', '/nonexistent/a2.bin');",
+        )
+        .expect("setup");
+        let files = delete_note(&conn, "n1").expect("delete");
+        assert_eq!(files, vec!["/nonexistent/a1.bin".to_string()]);
+        let note_id: Option<String> = conn
+            .query_row("SELECT note_id FROM reminders WHERE id = 'r1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(note_id, None);
+        let remaining: Vec<String> = conn
+            .prepare("SELECT id FROM attachments")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(remaining, vec!["a2".to_string()]);
+        assert!(note_by_id(&conn, "n1").unwrap().is_none());
+    }
+
+    #[test]
+    fn repairs_orphaned_contact_references() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE contacts (id TEXT PRIMARY KEY);
+             CREATE TABLE notes (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE interactions (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE reminders (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE attachments (id TEXT PRIMARY KEY, owner_type TEXT, owner_id TEXT, storage_path TEXT);
+             CREATE TABLE contact_custom_values (contact_id TEXT, field_id TEXT, PRIMARY KEY (contact_id, field_id));
+             CREATE TABLE contact_tags (contact_id TEXT, tag_id TEXT, PRIMARY KEY (contact_id, tag_id));
+             INSERT INTO contacts VALUES ('keep');
+             INSERT INTO notes VALUES ('n1', 'gone'), ('n2', 'keep');
+             INSERT INTO attachments VALUES ('a1', 'company', 'gone', '/nonexistent');
+             INSERT INTO contact_tags VALUES ('gone', 't1'), ('keep', 't1'), ('gone', 't2');",
+        )
+        .expect("setup");
+        let report = referential_orphans(&conn).unwrap();
+        let count = |table: &str| report.iter().find(|r| r.table == table).unwrap().count;
+        assert_eq!(count("notes"), 1);
+        assert_eq!(count("attachments"), 0, "company attachments are not contact references");
+        assert_eq!(count("contact_tags"), 2);
+
+        repair_orphans(&conn, Some("keep")).unwrap();
+        assert!(referential_orphans(&conn).unwrap().iter().all(|r| r.count == 0));
+        let tags: Vec<String> = conn
+            .prepare("SELECT tag_id FROM contact_tags WHERE contact_id = 'keep' ORDER BY tag_id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(tags, vec!["t1".to_string(), "t2".to_string()]);
+    }
+
+    #[test]
+    fn quotes_fts_prefix_terms() {
+        assert_eq!(fts_prefix_query("acme  deck", false), "\"acme\"* \"deck\"*");
+        assert_eq!(fts_prefix_query("acme deck", true), "\"acme\"* OR \"deck\"*");
+        assert_eq!(fts_prefix_query("q3-\"plan\"", false), "\"q3-\"\"plan\"\"\"*");
+    }
+
+    #[test]
+    fn diffs_contact_snapshots() {
+        let rows = |pairs: &[(&str, &str)]| -> std::collections::BTreeMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        let mut before_related = RelatedRows::new();
+        before_related.insert("notes".into(), rows(&[("n1", "2024-01-01"), ("n2", "2024-01-02")]));
+        before_related.insert("contact_tags".into(), rows(&[("t1", "")]));
+        let before = SnapshotData {
+            contact: serde_json::json!({"title": "CTO", "city": "Ankara", "updated_at": "2024-01-01"}),
+            related: before_related,
+        };
+        let mut after_related = RelatedRows::new();
+        after_related.insert("notes".into(), rows(&[("n1", "2024-02-01"), ("n3", "2024-02-02")]));
+        after_related.insert("contact_tags".into(), rows(&[("t1", "")]));
+        let after = SnapshotData {
+            contact: serde_json::json!({"title": "CEO", "city": "Ankara", "updated_at": "2024-02-01"}),
+            related: after_related,
+        };
+        let (fields, related) = diff_snapshots(&before, &after);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].field, "title");
+        assert_eq!(fields[0].before, serde_json::json!("CTO"));
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].table, "notes");
+        assert_eq!(related[0].added, vec!["n3".to_string()]);
+        assert_eq!(related[0].removed, vec!["n2".to_string()]);
+        assert_eq!(related[0].changed, vec!["n1".to_string()]);
+    }
+
+    #[test]
+    fn reencrypts_attachments_to_new_key() {
+        let dir = std::env::temp_dir().join(format!("vaultcrm-rekey-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (old_key, new_key) = ([1u8; 32], [2u8; 32]);
+        let good = dir.join("good.bin");
+        std::fs::write(&good, encrypt_bytes(&old_key, b"hello").unwrap()).unwrap();
+        let broken = dir.join("broken.bin");
+        std::fs::write(&broken, b"not ciphertext").unwrap();
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch("CREATE TABLE attachments (id TEXT PRIMARY KEY, storage_path TEXT);")
+            .unwrap();
+        for (id, path) in [("a1", &good), ("a2", &broken), ("a3", &dir.join("missing.bin"))] {
+            conn.execute(
+                "INSERT INTO attachments VALUES (?1, ?2)",
+                params![id, path.to_string_lossy()],
+            )
+            .unwrap();
         }
+        assert_eq!(reencrypt_attachments(&conn, &old_key, &new_key, || Ok(())).unwrap(), 1);
+        let bytes = std::fs::read(&good).unwrap();
+        assert_eq!(decrypt_bytes(&new_key, &bytes).unwrap(), b"hello");
+        assert!(decrypt_bytes(&old_key, &bytes).is_err());
+        assert_eq!(std::fs::read(&broken).unwrap(), b"not ciphertext");
+        assert!(!good.with_extension("bak").exists());
+        std::fs::remove_dir_all(&dir).ok();
     }
 
-    for ids in phone_map.values() {
-        if ids.len() < 2 {
-            continue;
+    #[test]
+    fn rolls_back_reencryption_on_failure() {
+        let dir = std::env::temp_dir().join(format!("vaultcrm-rekey-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (old_key, new_key) = ([1u8; 32], [2u8; 32]);
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch("CREATE TABLE attachments (id TEXT PRIMARY KEY, storage_path TEXT);")
+            .unwrap();
+        let files: Vec<PathBuf> = (0..3).map(|i| dir.join(format!("f{}.bin", i))).collect();
+        for (i, path) in files.iter().enumerate() {
+            std::fs::write(path, encrypt_bytes(&old_key, format!("file {}", i).as_bytes()).unwrap()).unwrap();
+            conn.execute(
+                "INSERT INTO attachments VALUES (?1, ?2)",
+                params![format!("a{}", i), path.to_string_lossy()],
+            )
+            .unwrap();
         }
-        for i in 0..ids.len() {
-            for j in (i + 1)..ids.len() {
-                add_reason(&ids[i], &ids[j], "phone");
+        let readable_with_old_key = |files: &[PathBuf]| {
+            files.iter().enumerate().all(|(i, path)| {
+                let bytes = std::fs::read(path).unwrap();
+                decrypt_bytes(&old_key, &bytes).ok() == Some(format!("file {}", i).into_bytes())
+            })
+        };
+        let leftovers = || {
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().and_then(|x| x.to_str()) != Some("bin"))
+                .count()
+        };
+
+        // The third staged file can't be moved into place.
+        let mut calls = 0;
+        let err = reencrypt_attachments_with(&conn, &old_key, &new_key, || Ok(()), |from, to| {
+            calls += 1;
+            if calls == 6 {
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, "disk full"));
             }
-        }
+            std::fs::rename(from, to)
+        });
+        assert!(err.is_err());
+        assert!(readable_with_old_key(&files));
+        assert_eq!(leftovers(), 0);
+
+        // Saving the new key fails after every file was swapped.
+        let err = reencrypt_attachments(&conn, &old_key, &new_key, || Err("flush failed".to_string()));
+        assert_eq!(err, Err("flush failed".to_string()));
+        assert!(readable_with_old_key(&files));
+        assert_eq!(leftovers(), 0);
+        std::fs::remove_dir_all(&dir).ok();
     }
 
-    let name_threshold = 0.85;
-    for i in 0..contacts.len() {
-        for j in (i + 1)..contacts.len() {
-            let a = &contacts[i];
-            let b = &contacts[j];
-            let sim = name_similarity(&a.first_name, &a.last_name, &b.first_name, &b.last_name);
-            if sim >= name_threshold {
-                add_reason(&a.id, &b.id, "name");
-            }
-        }
+    #[test]
+    fn quotes_csv_export_fields() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("Acme, Inc."), "\"Acme, Inc.\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(
+            CSV_EXPORT_COLUMNS.join(","),
+            "first_name,last_name,title,company,city,country,email,email_secondary,phone,phone_secondary,linkedin_url,twitter_url,website"
+        );
+        // Header names deserialize straight into ImportRow.
+        let row: ImportRow = serde_json::from_value(serde_json::Value::Object(
+            CSV_EXPORT_COLUMNS
+                .iter()
+                .map(|c| (c.to_string(), serde_json::Value::from(*c)))
+                .collect(),
+        ))
+        .unwrap();
+        assert_eq!(row.website.as_deref(), Some("website"));
+        assert_eq!(row.phone_secondary.as_deref(), Some("phone_secondary"));
     }
 
-    let mut candidates = Vec::new();
-    for ((a_id, b_id), flags) in pair_reasons {
-        if let (Some(a), Some(b)) = (by_id.get(&a_id), by_id.get(&b_id)) {
-            let mut reasons = Vec::new();
-            if flags.email {
-                reasons.push("email".to_string());
-            }
-            if flags.phone {
-                reasons.push("phone".to_string());
-            }
-            if flags.name {
-                reasons.push("name".to_string());
-            }
-            if !reasons.is_empty() {
-                candidates.push(DedupCandidate {
-                    a: a.clone(),
-                    b: b.clone(),
-                    reasons,
-                });
-            }
-        }
+    #[test]
+    fn escapes_segment_html() {
+        let contact = Contact {
+            first_name: "<script>alert(1)</script>".to_string(),
+            last_name: "O'Brien".to_string(),
+            company: Some("A & B".to_string()),
+            city: Some("Cork".to_string()),
+            country: Some("IE".to_string()),
+            email: Some("x\"@example.com".to_string()),
+            ..test_contact()
+        };
+        let html = segment_html("<b>VIP</b>", &[contact], "2024-01-01 00:00 UTC");
+        assert!(!html.contains("<script>") && !html.contains("<b>VIP"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt; O&#39;Brien"));
+        assert!(html.contains("<td>A &amp; B</td>"));
+        assert!(html.contains("<a href=\"mailto:x&quot;@example.com\">"));
+        assert!(html.contains("<td>Cork, IE</td>"));
+        assert!(html.contains("1 kişi"));
     }
 
-    Ok(candidates)
-}
+    #[test]
+    fn round_trips_json_export() {
+        let schema = "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT);
+             CREATE TABLE custom_fields (id TEXT PRIMARY KEY, name TEXT);
+             CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT);
+             CREATE TABLE contacts (id TEXT PRIMARY KEY, first_name TEXT, company_id TEXT, do_not_contact INTEGER);
+             CREATE TABLE contact_custom_values (contact_id TEXT, field_id TEXT, value TEXT, PRIMARY KEY (contact_id, field_id));
+             CREATE TABLE company_custom_values (company_id TEXT, field_id TEXT, value TEXT, PRIMARY KEY (company_id, field_id));
+             CREATE TABLE contact_tags (contact_id TEXT, tag_id TEXT, PRIMARY KEY (contact_id, tag_id));
+             CREATE TABLE notes (id TEXT PRIMARY KEY, contact_id TEXT, body TEXT);
+             CREATE TABLE interactions (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE reminders (id TEXT PRIMARY KEY, contact_id TEXT, title TEXT);";
+        let source = Connection::open_in_memory().expect("open in-memory db");
+        source.execute_batch(schema).expect("schema");
+        source
+            .execute_batch(
+                "INSERT INTO companies VALUES ('co1', 'Acme');
+                 INSERT INTO contacts VALUES ('c1', 'Ada', 'co1', 1), ('c2', 'Bob', NULL, 0);
+                 INSERT INTO contact_tags VALUES ('c1', 't1');
+                 INSERT INTO notes VALUES ('n1', 'c1', 'hello');",
+            )
+            .expect("seed");
+        let doc = export_json_doc(&source).unwrap();
+        assert_eq!(doc.get("version").and_then(|v| v.as_i64()), Some(EXPORT_JSON_VERSION));
+
+        let target = Connection::open_in_memory().expect("open in-memory db");
+        target.execute_batch(schema).expect("schema");
+        target
+            .execute_batch("INSERT INTO contacts VALUES ('c2', 'Bobby', NULL, 0);")
+            .expect("seed");
+        let counts = import_json_tables(&target, &doc).unwrap();
+        let contacts = counts.iter().find(|c| c.table == "contacts").unwrap();
+        assert_eq!((contacts.inserted, contacts.ignored), (1, 1));
+        let text = |sql: &str| -> String { target.query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(text("SELECT first_name FROM contacts WHERE id = 'c2'"), "Bobby");
+        assert_eq!(text("SELECT company_id FROM contacts WHERE id = 'c1'"), "co1");
+        assert_eq!(text("SELECT body FROM notes WHERE contact_id = 'c1'"), "hello");
+        assert_eq!(text("SELECT tag_id FROM contact_tags WHERE contact_id = 'c1'"), "t1");
+    }
 
-#[tauri::command]
-pub fn contact_merge(db: State<DbState>, input: MergeContactInput) -> Result<Contact, String> {
-    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    if !is_valid_email(&input.merged.email) || !is_valid_email(&input.merged.email_secondary) {
-        return Err("Geçersiz email formatı".to_string());
+    #[test]
+    fn purges_contact_with_dependents() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE contacts (id TEXT PRIMARY KEY, deleted_at TEXT);
+             CREATE TABLE notes (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE reminders (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE interactions (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE contact_custom_values (contact_id TEXT, field_id TEXT);
+             CREATE TABLE contact_tags (contact_id TEXT, tag_id TEXT);
+             CREATE TABLE contact_snapshots (id TEXT PRIMARY KEY, contact_id TEXT);
+             CREATE TABLE attachments (id TEXT PRIMARY KEY, owner_type TEXT, owner_id TEXT, storage_path TEXT);
+             CREATE TABLE dedup_ignored_pairs (a_id TEXT, b_id TEXT, created_at TEXT);
+             INSERT INTO contacts VALUES ('gone', '2024-01-01T00:00:00Z'), ('kept', NULL);
+             INSERT INTO dedup_ignored_pairs VALUES ('gone', 'kept', '2024-01-01T00:00:00Z');
+             INSERT INTO notes VALUES ('n1', 'gone'), ('n2', 'kept');
+             INSERT INTO reminders VALUES ('r1', 'gone');
+             INSERT INTO interactions VALUES ('i1', 'gone');
+             INSERT INTO contact_tags VALUES ('gone', 't1'), ('kept', 't1');
+             INSERT INTO attachments VALUES ('a1', 'contact', 'gone', '/nonexistent/a1'),
+                 ('a2', 'note', 'n1', '/nonexistent/a2'), ('a3', 'note', 'n2', '/nonexistent/a3');",
+        )
+        .expect("setup");
+        let mut files = purge_contact(&conn, "gone").unwrap();
+        files.sort();
+        assert_eq!(files, vec!["/nonexistent/a1".to_string(), "/nonexistent/a2".to_string()]);
+        let count = |sql: &str| -> i64 { conn.query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM contacts"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM notes"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM reminders") + count("SELECT COUNT(*) FROM interactions"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM contact_tags"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM attachments WHERE id = 'a3'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM attachments"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM dedup_ignored_pairs"), 0);
     }
-    if !is_valid_phone(&input.merged.phone) || !is_valid_phone(&input.merged.phone_secondary) {
-        return Err("Geçersiz telefon formatı".to_string());
+
+    #[test]
+    fn recomputes_last_touched_from_remaining_events() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, last_touched_at, created_at, updated_at)
+                 VALUES ('c1', 'Ali', '2024-06-01', '2024-01-01T00:00:00Z', '2024-08-01T00:00:00Z');
+             INSERT INTO interactions (id, contact_id, kind, happened_at) VALUES ('i1', 'c1', 'call', '2024-06-01');
+             INSERT INTO interactions (id, contact_id, kind, happened_at) VALUES ('i2', 'c1', 'call', '2024-03-05T09:00:00Z');
+             INSERT INTO notes (id, contact_id, created_at, updated_at)
+                 VALUES ('n1', 'c1', '2024-04-10 12:00:00', '2024-04-10 12:00:00');
+             INSERT INTO reminders (id, contact_id, title, due_at) VALUES ('r1', 'c1', 'Ara', '2024-09-01T00:00:00Z');",
+        )
+        .expect("setup");
+        let touched = |conn: &Connection| -> Option<String> {
+            conn.query_row("SELECT last_touched_at FROM contacts WHERE id = 'c1'", [], |r| r.get(0))
+                .unwrap()
+        };
+        conn.execute("DELETE FROM interactions WHERE id = 'i1'", []).unwrap();
+        refresh_last_touched(&conn, "c1", "2024-07-01T00:00:00Z").unwrap();
+        assert_eq!(touched(&conn).as_deref(), Some("2024-04-10 12:00:00"));
+        conn.execute("UPDATE notes SET updated_at = '2024-05-02T08:00:00+03:00' WHERE id = 'n1'", []).unwrap();
+        refresh_last_touched(&conn, "c1", "2024-07-01T00:00:00Z").unwrap();
+        assert_eq!(touched(&conn).as_deref(), Some("2024-05-02T08:00:00+03:00"));
+        conn.execute_batch("DELETE FROM notes; DELETE FROM interactions;").unwrap();
+        refresh_last_touched(&conn, "c1", "2024-07-01T00:00:00Z").unwrap();
+        assert_eq!(touched(&conn), None);
     }
-    let mut guard = db.0.lock().map_err(|e| e.to_string())?;
-    let conn = guard.as_mut().ok_or("DB not initialized")?;
-    let sql = "SELECT c.id, c.first_name, c.last_name, c.title,
-        COALESCE(co.name, c.company), c.company_id, c.city, c.country,
-        c.email, c.email_secondary, c.phone, c.phone_secondary,
-        c.linkedin_url, c.twitter_url, c.website, c.notes,
-        c.last_touched_at, c.next_touch_at, c.created_at, c.updated_at
-        FROM contacts c LEFT JOIN companies co ON c.company_id = co.id WHERE c.id = ?1";
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-    let primary = stmt
-        .query_row(params![input.primary_id.clone()], row_to_contact)
-        .map_err(|e| e.to_string())?;
-    let secondary = stmt
-        .query_row(params![input.secondary_id.clone()], row_to_contact)
-        .map_err(|e| e.to_string())?;
-    drop(stmt);
 
-    let last_touched_at = match (primary.last_touched_at.clone(), secondary.last_touched_at.clone()) {
-        (Some(a), Some(b)) => Some(if a >= b { a } else { b }),
-        (Some(a), None) => Some(a),
-        (None, Some(b)) => Some(b),
-        _ => None,
-    };
-    let next_touch_at = match (primary.next_touch_at.clone(), secondary.next_touch_at.clone()) {
-        (Some(a), Some(b)) => Some(if a <= b { a } else { b }),
-        (Some(a), None) => Some(a),
-        (None, Some(b)) => Some(b),
-        _ => None,
-    };
+    #[test]
+    fn matches_names_phonetically() {
+        assert_eq!(metaphone("Geoff"), metaphone("Jeff"));
+        assert_eq!(metaphone("Catherine"), metaphone("Kathryn"));
+        assert_eq!(metaphone("Philip"), metaphone("Filip"));
+        assert_eq!(metaphone("Knight"), metaphone("Nite"));
+        assert_ne!(metaphone("Ada"), metaphone("Bob"));
+        assert_eq!(phonetic_name_key("Stephen", "Smith"), phonetic_name_key("Steven", "Smyth"));
+        assert_eq!(phonetic_name_key("Şule", "Çelik"), phonetic_name_key("Sule", "Celik"));
+        assert_eq!(phonetic_name_key("", "Smith"), None);
+    }
 
-    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    #[test]
+    fn ranks_dedup_confidence() {
+        let email_only = dedup_confidence(true, false, 0.0);
+        let phone_only = dedup_confidence(false, true, 0.0);
+        let name_only = dedup_confidence(false, false, 0.9);
+        let all = dedup_confidence(true, true, 1.0);
+        assert!((email_only - 0.9).abs() < 1e-6);
+        assert!(email_only > phone_only && phone_only > name_only);
+        assert!(all > email_only && all <= 1.0);
+        assert!(dedup_confidence(false, false, 0.85) < dedup_confidence(false, false, 1.0));
+    }
 
-    tx.execute(
-        "UPDATE contacts SET first_name=?1, last_name=?2, title=?3, company=?4, company_id=?5, city=?6, country=?7, email=?8, email_secondary=?9, phone=?10, phone_secondary=?11, linkedin_url=?12, twitter_url=?13, website=?14, notes=?15, last_touched_at=?16, next_touch_at=?17, updated_at=?18 WHERE id=?19",
-        params![
-            input.merged.first_name,
-            input.merged.last_name,
-            input.merged.title,
-            input.merged.company,
-            input.merged.company_id,
-            input.merged.city,
-            input.merged.country,
-            input.merged.email,
-            input.merged.email_secondary,
-            input.merged.phone,
-            input.merged.phone_secondary,
-            input.merged.linkedin_url,
-            input.merged.twitter_url,
-            input.merged.website,
-            input.merged.notes,
-            last_touched_at,
-            next_touch_at,
-            now,
-            &input.primary_id,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+    #[test]
+    fn validates_custom_values_by_kind() {
+        let opts = Some(r#"["A","B"]"#);
+        assert!(validate_custom_value("Yaş", "number", None, "42.5").is_ok());
+        assert!(validate_custom_value("Yaş", "number", None, "kırk").unwrap_err().starts_with("Yaş:"));
+        assert!(validate_custom_value("Doğum", "date", None, "2024-02-29").is_ok());
+        assert!(validate_custom_value("Doğum", "date", None, "29.02.2024").is_err());
+        assert!(validate_custom_value("Segment", "single_select", opts, "A").is_ok());
+        assert!(validate_custom_value("Segment", "single_select", opts, "C").is_err());
+        assert!(validate_custom_value("Etiket", "multi_select", opts, r#"["A","B"]"#).is_ok());
+        assert!(validate_custom_value("Etiket", "multi_select", opts, r#"["A","C"]"#).is_err());
+        assert!(validate_custom_value("Etiket", "multi_select", opts, "A").is_err());
+        assert!(validate_custom_value("Yaş", "number", None, "  ").is_ok());
+    }
 
-    // Merge tags
-    tx.execute(
-        "INSERT OR IGNORE INTO contact_tags (contact_id, tag_id)
-         SELECT ?1, tag_id FROM contact_tags WHERE contact_id = ?2",
-        params![&input.primary_id, &input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
-    tx.execute(
-        "DELETE FROM contact_tags WHERE contact_id = ?1",
-        params![&input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
+    #[test]
+    fn advances_review_by_cadence() {
+        let now = parse_datetime_utc("2024-03-20T10:00:00Z").unwrap();
+        assert_eq!(next_review_after(None, 7, now), parse_datetime_utc("2024-03-27T10:00:00Z").unwrap());
+        // Missed reviews skip ahead but stay on the original weekday.
+        let monday = parse_datetime_utc("2024-03-04T09:00:00Z");
+        assert_eq!(next_review_after(monday, 7, now), parse_datetime_utc("2024-03-25T09:00:00Z").unwrap());
+        // Reviewing early still moves the schedule on by one cadence.
+        let early = parse_datetime_utc("2024-03-22T09:00:00Z");
+        assert_eq!(next_review_after(early, 7, now), parse_datetime_utc("2024-03-29T09:00:00Z").unwrap());
+    }
 
-    // Merge custom values: replace primary with provided values if present
-    if let Some(values) = input.custom_values {
-        tx.execute(
-            "DELETE FROM contact_custom_values WHERE contact_id = ?1",
-            params![&input.primary_id],
+    #[test]
+    fn clears_selected_contact_history() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE contacts (id TEXT PRIMARY KEY, last_touched_at TEXT, next_touch_at TEXT, updated_at TEXT);
+             CREATE TABLE notes (id TEXT PRIMARY KEY, contact_id TEXT, kind TEXT, title TEXT, body TEXT, created_at TEXT, updated_at TEXT);
+             CREATE TABLE interactions (id TEXT PRIMARY KEY, contact_id TEXT, happened_at TEXT);
+             CREATE TABLE reminders (id TEXT PRIMARY KEY, contact_id TEXT, note_id TEXT, due_at TEXT, snooze_until TEXT, completed_at TEXT);
+             CREATE TABLE attachments (id TEXT PRIMARY KEY, owner_type TEXT, owner_id TEXT, storage_path TEXT);
+             INSERT INTO contacts VALUES ('c1', '2024-03-05T00:00:00Z', '2024-04-01T00:00:00Z', NULL);
+             INSERT INTO notes VALUES ('n1', 'c1', 'note', NULL, 'hi', '2024-03-05T00:00:00Z', '2024-03-05T00:00:00Z');
+             INSERT INTO interactions VALUES ('i1', 'c1', '2024-03-01T00:00:00Z');
+             INSERT INTO reminders VALUES ('r1', 'c1', 'n1', '2024-04-01T00:00:00Z', NULL, NULL);
+             INSERT INTO attachments VALUES ('a1', 'note', 'n1', '/nonexistent/a1.bin');",
         )
-        .map_err(|e| e.to_string())?;
-        for v in values {
-            tx.execute(
-                "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, ?2, ?3)",
-                params![&input.primary_id, v.field_id, v.value],
+        .expect("schema");
+        let include = ClearHistoryInclude {
+            notes: true,
+            interactions: false,
+            reminders: false,
+        };
+        let (result, files) = clear_contact_history(&conn, "c1", &include, "2024-03-10T00:00:00Z").unwrap();
+        assert_eq!(files, vec!["/nonexistent/a1.bin".to_string()]);
+        assert_eq!((result.notes, result.interactions, result.reminders), (1, 0, 0));
+        let (touched, next): (Option<String>, Option<String>) = conn
+            .query_row("SELECT last_touched_at, next_touch_at FROM contacts WHERE id = 'c1'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(touched.as_deref(), Some("2024-03-01T00:00:00Z"));
+        assert_eq!(next.as_deref(), Some("2024-04-01T00:00:00Z"));
+        let attachments: i64 = conn
+            .query_row("SELECT COUNT(*) FROM attachments", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(attachments, 0);
+        assert!(clear_contact_history(&conn, "nope", &include, "2024-03-10T00:00:00Z").is_err());
+    }
+
+    #[test]
+    fn diffs_json_exports_by_id() {
+        let a = parse_export_json(
+            r#"{"version":1,"contacts":[{"id":"c1","first_name":"Ada","city":null},{"id":"c2","first_name":"Bob"}],
+                "notes":[{"id":"n1","body":"hi"}]}"#,
+        )
+        .unwrap();
+        let b = parse_export_json(
+            r#"{"version":1,"contacts":[{"id":"c1","first_name":"Ada","city":"Izmir"},{"id":"c3","first_name":"Cem"}],
+                "notes":[{"id":"n1","body":"hi"}]}"#,
+        )
+        .unwrap();
+        let diff = diff_export_docs(&a, &b);
+        let contacts = diff.iter().find(|d| d.table == "contacts").unwrap();
+        assert_eq!(contacts.added, vec!["c3"]);
+        assert_eq!(contacts.removed, vec!["c2"]);
+        assert_eq!(contacts.changed.len(), 1);
+        assert_eq!(contacts.changed[0].fields[0].field, "city");
+        assert_eq!(contacts.changed[0].fields[0].after, "Izmir");
+        let notes = diff.iter().find(|d| d.table == "notes").unwrap();
+        assert!(notes.added.is_empty() && notes.removed.is_empty() && notes.changed.is_empty());
+        assert!(parse_export_json(r#"{"version":2}"#).is_err());
+    }
+
+    #[test]
+    fn ignores_legal_suffixes_in_company_names() {
+        assert_eq!(normalize_company_name("Acme, Inc."), "acme");
+        assert_eq!(normalize_company_name("Yıldız Yazılım A.Ş."), "yıldız yazılım");
+        assert_eq!(normalize_company_name("Co"), "co");
+        assert!(company_name_similarity("ACME Ltd", "Acme") >= 0.99);
+        assert!(company_name_similarity("Acme", "Apex") < 0.85);
+    }
+
+    #[test]
+    fn skips_weekends_and_holidays() {
+        let d = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // 2024-03-01 is a Friday.
+        assert_eq!(add_business_days(d("2024-03-01"), 1, &[]), d("2024-03-04"));
+        assert_eq!(add_business_days(d("2024-03-06"), 3, &[]), d("2024-03-11"));
+        assert_eq!(add_business_days(d("2024-03-02"), 1, &[]), d("2024-03-04"));
+        assert_eq!(add_business_days(d("2024-03-01"), 1, &[d("2024-03-04")]), d("2024-03-05"));
+    }
+
+    #[test]
+    fn computes_reminder_windows() {
+        let now = parse_datetime_utc("2024-03-10T15:30:00Z").unwrap();
+        let named = |n: &str| reminder_window_bounds(&ReminderWindow::Named(n.to_string()), now);
+        assert_eq!(named("overdue").unwrap(), (None, Some(now)));
+        let (from, to) = named("today").unwrap();
+        assert_eq!(from, parse_datetime_utc("2024-03-10"));
+        assert_eq!(to, parse_datetime_utc("2024-03-11"));
+        assert_eq!(named("this_week").unwrap().1, parse_datetime_utc("2024-03-17"));
+        assert!(named("someday").is_err());
+        let range = |from: &str, to: &str| {
+            reminder_window_bounds(
+                &ReminderWindow::Range {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                },
+                now,
             )
-            .map_err(|e| e.to_string())?;
+        };
+        assert!(range("2024-03-01", "2024-04-01").is_ok());
+        assert!(range("2024-04-01", "2024-03-01").is_err());
+        assert!(range("yesterday", "2024-03-01").is_err());
+    }
+
+    #[test]
+    fn fts_match_treats_special_characters_as_literals() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE contacts_fts USING fts5(first_name, last_name, company, notes);
+             INSERT INTO contacts_fts(rowid, first_name, last_name, company) VALUES (1, 'Conan', 'O''Brien', 'foo-bar');
+             INSERT INTO contacts_fts(rowid, first_name, last_name, company) VALUES (2, 'Ada', 'Lovelace', 'a\"b Labs');",
+        )
+        .expect("setup");
+        let hits = |q: &str| -> Vec<i64> {
+            let mut stmt = conn
+                .prepare("SELECT rowid FROM contacts_fts WHERE contacts_fts MATCH ?1 ORDER BY rowid")
+                .unwrap();
+            let rows = stmt
+                .query_map(params![fts_prefix_query(q, false)], |r| r.get(0))
+                .expect("query must not be parsed as FTS syntax");
+            rows.map(|r| r.unwrap()).collect()
+        };
+        assert_eq!(hits("O'Brien"), vec![1]);
+        assert_eq!(hits("foo-bar"), vec![1]);
+        assert_eq!(hits("a\"b"), vec![2]);
+        assert_eq!(hits("Ada AND"), Vec::<i64>::new());
+        for q in ["\"", "-", "NOT", "OR ada", "(", "col:x", "^", "*"] {
+            hits(q);
         }
-    } else {
-        tx.execute(
-            "INSERT OR IGNORE INTO contact_custom_values (contact_id, field_id, value)
-             SELECT ?1, field_id, value FROM contact_custom_values WHERE contact_id = ?2",
-            params![&input.primary_id, &input.secondary_id],
+    }
+
+    #[test]
+    fn audits_fts_drift() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE contacts (id TEXT PRIMARY KEY, first_name TEXT, last_name TEXT, company TEXT, notes TEXT);
+             CREATE VIRTUAL TABLE contacts_fts USING fts5(
+                 first_name, last_name, company, notes, content='contacts', content_rowid='rowid');
+             CREATE TRIGGER contacts_fts_insert AFTER INSERT ON contacts BEGIN
+                 INSERT INTO contacts_fts(rowid, first_name, last_name, company, notes)
+                 VALUES (new.rowid, new.first_name, new.last_name, new.company, new.notes);
+             END;
+             INSERT INTO contacts (id, first_name, last_name) VALUES ('a', 'Ada', 'Lovelace');
+             INSERT INTO contacts (id, first_name, last_name) VALUES ('b', 'Alan', 'Turing');",
         )
-        .map_err(|e| e.to_string())?;
+        .expect("setup");
+        assert!(!fts_audit(&conn).unwrap().needs_reindex);
+
+        // Raw writes without triggers: 'c' is never indexed, 'b' leaves an orphan entry.
+        conn.execute("DROP TRIGGER contacts_fts_insert", []).unwrap();
+        conn.execute("INSERT INTO contacts (id, first_name, last_name) VALUES ('c', 'Grace', 'Hopper')", [])
+            .unwrap();
+        conn.execute("DELETE FROM contacts WHERE id = 'b'", []).unwrap();
+        let audit = fts_audit(&conn).unwrap();
+        assert!(audit.needs_reindex);
+        assert_eq!(audit.missing_ids, vec!["c".to_string()]);
+        assert_eq!(audit.orphan_rowids.len(), 1);
+
+        conn.execute("INSERT INTO contacts_fts(contacts_fts) VALUES ('rebuild')", [])
+            .unwrap();
+        assert!(!fts_audit(&conn).unwrap().needs_reindex);
     }
-    tx.execute(
-        "DELETE FROM contact_custom_values WHERE contact_id = ?1",
-        params![&input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
 
-    // Move related rows
-    tx.execute(
-        "UPDATE notes SET contact_id = ?1 WHERE contact_id = ?2",
-        params![&input.primary_id, &input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
-    tx.execute(
-        "UPDATE reminders SET contact_id = ?1 WHERE contact_id = ?2",
-        params![&input.primary_id, &input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
-    tx.execute(
-        "UPDATE interactions SET contact_id = ?1 WHERE contact_id = ?2",
-        params![&input.primary_id, &input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
+    #[test]
+    fn checks_attachment_extensions_against_allowlist() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch("CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);")
+            .expect("setup");
+        let defaults = attachment_allowed_exts(&conn).unwrap();
+        assert!(is_allowed_attachment("kartvizit.JPG", &defaults));
+        assert!(is_allowed_attachment("butce.xlsx", &defaults));
+        assert!(!is_allowed_attachment("setup.exe", &defaults));
+        assert!(!is_allowed_attachment("pdf", &defaults));
+
+        // Blocked extensions stay rejected even if someone writes them into the setting.
+        setting_set(&conn, "attachment_allowed_exts", r#"["csv","exe"]"#).unwrap();
+        let custom = attachment_allowed_exts(&conn).unwrap();
+        assert!(is_allowed_attachment("liste.csv", &custom));
+        assert!(!is_allowed_attachment("rapor.pdf", &custom));
+        assert!(!is_allowed_attachment("setup.exe", &custom));
+    }
 
-    tx.execute(
-        "DELETE FROM contacts WHERE id = ?1",
-        params![&input.secondary_id],
-    )
-    .map_err(|e| e.to_string())?;
+    #[test]
+    fn only_exposes_allowlisted_settings_to_ui() {
+        assert_eq!(ui_setting_key(" theme "), Ok("theme"));
+        assert!(ui_setting_key("attachments_key").is_err());
+        assert!(ui_setting_key("attachments_dir").is_err());
+        assert!(ui_setting_key("").is_err());
+    }
 
-    tx.commit().map_err(|e| e.to_string())?;
+    #[test]
+    fn counts_days_since_last_touch() {
+        let now = parse_datetime_utc("2024-05-10T12:00:00Z").unwrap();
+        assert_eq!(days_since_touch(Some("2024-05-01T13:00:00Z"), now), Some(8));
+        assert_eq!(days_since_touch(Some("2024-04-10"), now), Some(30));
+        assert_eq!(days_since_touch(Some(""), now), None);
+        assert_eq!(days_since_touch(None, now), None);
+    }
 
-    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-    let merged = stmt
-        .query_row(params![input.primary_id.clone()], row_to_contact)
-        .map_err(|e| e.to_string())?;
-    Ok(merged)
-}
+    #[test]
+    fn orders_pipeline_counts_by_field_options() {
+        let options = vec!["Lead".to_string(), "Qualified".to_string(), "Closed".to_string()];
+        let report = order_pipeline_counts(
+            &options,
+            vec![(Some("Closed".to_string()), 2), (None, 5), (Some("Legacy".to_string()), 1), (Some("Lead".to_string()), 3)],
+        );
+        let got: Vec<(Option<&str>, i64)> = report.iter().map(|r| (r.option.as_deref(), r.count)).collect();
+        assert_eq!(
+            got,
+            vec![(Some("Lead"), 3), (Some("Qualified"), 0), (Some("Closed"), 2), (Some("Legacy"), 1), (None, 5)]
+        );
+    }
 
-// ---- E3 Export (data portability): write to user-chosen path ----
+    #[test]
+    fn buckets_interactions_by_period_and_kind() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE contacts (id TEXT PRIMARY KEY, deleted_at TEXT);
+             CREATE TABLE interactions (id TEXT PRIMARY KEY, contact_id TEXT, kind TEXT, happened_at TEXT);
+             INSERT INTO contacts VALUES ('c1', NULL), ('gone', '2024-01-01T00:00:00Z');
+             INSERT INTO interactions VALUES
+                 ('i1', 'c1', 'call', '2024-03-04T09:00:00Z'),
+                 ('i2', 'c1', 'email', '2024-03-10T18:00:00Z'),
+                 ('i3', 'c1', 'call', '2024-03-11T09:00:00Z'),
+                 ('i4', 'c1', 'meeting', '2024-04-02T09:00:00Z'),
+                 ('i5', 'gone', 'call', '2024-03-05T09:00:00Z');",
+        )
+        .expect("setup");
+        let weeks = interaction_stats_rows(&conn, "2024-03-01", "2024-03-31", "week").unwrap();
+        let got: Vec<(&str, i64)> = weeks.iter().map(|b| (b.bucket.as_str(), b.total)).collect();
+        assert_eq!(got, vec![("2024-03-04", 2), ("2024-03-11", 1)]);
+        assert_eq!(weeks[0].by_kind.get("call"), Some(&1));
+        assert_eq!(weeks[0].by_kind.get("email"), Some(&1));
+
+        let months = interaction_stats_rows(&conn, "2024-03-01", "2024-04-30", "month").unwrap();
+        let got: Vec<(&str, i64)> = months.iter().map(|b| (b.bucket.as_str(), b.total)).collect();
+        assert_eq!(got, vec![("2024-03", 3), ("2024-04", 1)]);
+        assert!(interaction_stats_rows(&conn, "2024-03-01", "2024-03-31", "year").is_err());
+    }
 
-/// Writes string content to a file at the given path. Path comes from the save dialog (E3.3).
-#[tauri::command]
-pub fn write_export_file(path: String, content: String) -> Result<(), String> {
-    std::fs::write(&path, content.as_bytes()).map_err(|e| e.to_string())
-}
+    #[test]
+    fn pages_contact_timeline_newest_first() {
+        let entry = |id: &str, at: &str| TimelineEntry {
+            kind: "note".to_string(),
+            id: id.to_string(),
+            at: at.to_string(),
+            title: "note".to_string(),
+            snippet: None,
+        };
+        let entries = || {
+            vec![
+                entry("a", "2024-03-01T10:00:00Z"),
+                entry("b", "2024-03-03T10:00:00Z"),
+                entry("c", "2024-03-02T10:00:00Z"),
+                entry("d", "2024-03-02T10:00:00Z"),
+            ]
+        };
+        let first = page_timeline(entries(), 2, None);
+        let ids: Vec<&str> = first.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "d"]);
+        let rest = page_timeline(entries(), 2, Some((first[1].at.as_str(), first[1].id.as_str())));
+        let ids: Vec<&str> = rest.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a"]);
+        let older = page_timeline(entries(), 5, Some(("2024-03-02T10:00:00Z", "")));
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].id, "a");
+        assert_eq!(text_snippet("  kısa  ", 10), "kısa");
+        assert_eq!(text_snippet("çok uzun bir not", 3), "çok…");
+    }
 
-// ---- F1 Encryption & key (F1.2 keychain, F1.3 first-run setup) ----
+    #[test]
+    fn filters_search_contacts_in_sql() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO companies (id, name) VALUES ('co1', 'Acme'), ('co2', 'Globex');
+             INSERT INTO tags (id, name) VALUES ('t1', 'VIP');
+             INSERT INTO contacts (id, first_name, company_id, city, country, created_at, updated_at) VALUES
+                 ('a', 'Ayşe', 'co1', 'İstanbul', 'TR', 'x', 'x'), ('b', 'Ayla', 'co2', 'istanbul ', 'TR', 'x', 'x'),
+                 ('c', 'Can', 'co1', 'Ankara', 'TR', 'x', 'x');
+             INSERT INTO contact_tags (contact_id, tag_id) VALUES ('a', 't1'), ('c', 't1');
+             INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES ('a', 'cf_stage', 'Lead'), ('c', 'cf_stage', 'Lead');",
+        )
+        .expect("setup");
+        let ids = |filters: GlobalSearchFilters, q: &str| -> Vec<String> {
+            let mut ids: Vec<String> = run_global_search(&conn, q, &filters, -1)
+                .unwrap()
+                .contacts
+                .into_iter()
+                .map(|c| c.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let tagged_leads = GlobalSearchFilters {
+            tag_id: Some("t1".to_string()),
+            stage: Some("Lead".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(tagged_leads, ""), vec!["a", "c"]);
+        let company = GlobalSearchFilters {
+            company_id: Some("co1".to_string()),
+            country: Some(" tr ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(company, "Ay"), vec!["a"]);
+        let city = GlobalSearchFilters {
+            city: Some("ankara".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(city, ""), vec!["c"]);
+        let blank = GlobalSearchFilters {
+            city: Some("  ".to_string()),
+            ..Default::default()
+        };
+        assert!(contact_filter_clauses(&blank).0.is_empty());
+        assert!(ids(blank, "").is_empty());
+    }
 
-#[derive(serde::Serialize)]
-#[serde(rename_all = "snake_case")]
-pub enum EncryptionStateResponse {
-    Ready,
-    NeedSetup { reason: crate::db::SetupReason },
-}
+    #[test]
+    fn bulk_tags_only_live_contacts_once() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE tags (id TEXT PRIMARY KEY, name TEXT, color TEXT, created_at TEXT);
+             CREATE TABLE contacts (id TEXT PRIMARY KEY, deleted_at TEXT, updated_at TEXT);
+             CREATE TABLE contact_tags (contact_id TEXT, tag_id TEXT, PRIMARY KEY (contact_id, tag_id));
+             INSERT INTO tags VALUES ('t1', 'VIP', NULL, 'x');
+             INSERT INTO contacts VALUES ('a', NULL, 'old'), ('b', NULL, 'old'), ('trashed', 'x', 'old');
+             INSERT INTO contact_tags VALUES ('b', 't1');",
+        )
+        .expect("setup");
+        let ids: Vec<String> = ["a", "b", "trashed", "missing"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(tag_contacts_bulk(&conn, &ids, "t1", true, "now").unwrap(), 1);
+        let touched: String = conn
+            .query_row("SELECT updated_at FROM contacts WHERE id = 'b'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(touched, "old");
+        assert_eq!(tag_contacts_bulk(&conn, &ids, "t1", false, "now").unwrap(), 2);
+        assert!(tag_contacts_bulk(&conn, &ids, "nope", true, "now").is_err());
+    }
 
-/// F1.3: Returns "ready" or need_setup with reason (first_run / migrate_plain).
-#[tauri::command]
-pub fn get_encryption_state(setup: State<EncryptionSetupState>) -> Result<EncryptionStateResponse, String> {
-    let guard = setup.0.lock().map_err(|e| e.to_string())?;
-    Ok(match guard.as_ref() {
-        Some(reason) => EncryptionStateResponse::NeedSetup {
-            reason: reason.clone(),
-        },
-        None => EncryptionStateResponse::Ready,
-    })
-}
+    #[test]
+    fn bulk_assigns_company_and_refreshes_name() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+             CREATE TABLE contacts (id TEXT PRIMARY KEY, company TEXT, company_id TEXT, deleted_at TEXT, updated_at TEXT);
+             INSERT INTO companies VALUES ('co1', 'Acme');
+             INSERT INTO contacts VALUES ('a', 'acme inc', NULL, NULL, 'x'), ('b', NULL, 'old', NULL, 'x'),
+                 ('t', NULL, NULL, 'trashed', 'x');",
+        )
+        .expect("setup");
+        let ids: Vec<String> = ["a", "b", "t"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(set_company_bulk(&conn, &ids, Some("co1".to_string()), "now").unwrap(), 2);
+        let company: (String, String) = conn
+            .query_row("SELECT company, company_id FROM contacts WHERE id = 'a'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(company, ("Acme".to_string(), "co1".to_string()));
+        assert!(set_company_bulk(&conn, &ids, Some("missing".to_string()), "now").is_err());
+        assert_eq!(set_company_bulk(&conn, &ids[..1], None, "now").unwrap(), 1);
+        let company: Option<String> = conn
+            .query_row("SELECT company FROM contacts WHERE id = 'a'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(company, None);
+    }
 
-/// F1.3: First-run — create key (device or passphrase), empty encrypted DB, store key in keychain.
-#[tauri::command]
-pub fn encryption_setup_create_key(app: tauri::AppHandle, passphrase: Option<String>) -> Result<(), String> {
-    crate::db::setup_create_key(&app, passphrase)
-}
+    #[test]
+    fn previews_import_rows_against_existing_contacts() {
+        let mut index = ImportMatchIndex {
+            by_email: HashMap::new(),
+            by_phone: HashMap::new(),
+            names: vec![("c3".to_string(), "Zeynep".to_string(), "Kaya".to_string())],
+            default_code: Some("90".to_string()),
+            name_threshold: DEFAULT_DEDUP_NAME_THRESHOLD,
+        };
+        index.by_email.insert("ali@example.com".to_string(), "c1".to_string());
+        index.by_phone.insert("905321234567".to_string(), "c2".to_string());
+        let row = |first: &str, email: Option<&str>, phone: Option<&str>| ImportRow {
+            first_name: Some(first.to_string()),
+            last_name: Some("Yılmaz".to_string()),
+            email: email.map(str::to_string),
+            phone: phone.map(str::to_string),
+            ..Default::default()
+        };
+        let rows = vec![
+            row("Ali", Some(" ALI@example.com "), None),
+            row("Veli", None, Some("0532 123 45 67")),
+            ImportRow {
+                first_name: Some("Zeynep".to_string()),
+                last_name: Some("Kaya".to_string()),
+                ..Default::default()
+            },
+            row("Ayşe", Some("bad-email"), None),
+            row("Can", Some("can@example.com"), None),
+            row("Cem", Some("CAN@example.com"), None),
+        ];
+        let preview = preview_import_rows(&mut index, &rows);
+        let statuses: Vec<String> = preview
+            .iter()
+            .map(|p| match &p.status {
+                ImportPreviewStatus::New => "new".to_string(),
+                ImportPreviewStatus::DuplicateOf {
+                    contact_id: Some(contact_id),
+                    matched_by,
+                    ..
+                } => format!("{}:{}", contact_id, matched_by),
+                ImportPreviewStatus::DuplicateOf {
+                    earlier_row: Some(row),
+                    matched_by,
+                    ..
+                } => format!("row {}:{}", row, matched_by),
+                ImportPreviewStatus::DuplicateOf { .. } => "duplicate".to_string(),
+                ImportPreviewStatus::Invalid { .. } => "invalid".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            vec!["c1:email", "c2:phone", "c3:name", "invalid", "new", "row 4:email"]
+        );
+    }
 
-/// F1.1/F1.2: Migrate plain vault.db to encrypted; store key in keychain.
-#[tauri::command]
-pub fn encryption_migrate_plain_db(app: tauri::AppHandle, passphrase: Option<String>) -> Result<(), String> {
-    crate::db::migrate_plain_to_encrypted(&app, passphrase)
-}
+    #[test]
+    fn import_update_fills_only_empty_fields() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, last_name, title, email, created_at, updated_at)
+                 VALUES ('c1', 'Ali', 'Veli', 'CEO', 'ali@example.com', 'x', 'x');",
+        )
+        .expect("setup");
+        let row = ImportRow {
+            first_name: Some("Ali".to_string()),
+            last_name: Some("Veli".to_string()),
+            title: Some("CTO".to_string()),
+            city: Some("İzmir".to_string()),
+            email: Some("ALI@example.com".to_string()),
+            email_secondary: Some("ali@example.com".to_string()),
+            phone: Some("0532 123 45 67".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(fill_empty_contact_fields(&conn, "c1", &row, None, "now").unwrap(), (true, false));
+        let got: (String, String, Option<String>, String, String) = conn
+            .query_row(
+                "SELECT title, city, email_secondary, phone_normalized, updated_at FROM contacts WHERE id = 'c1'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            got,
+            ("CEO".to_string(), "İzmir".to_string(), None, "05321234567".to_string(), "now".to_string())
+        );
+        assert!(!fill_empty_contact_fields(&conn, "c1", &row, None, "later").unwrap().0);
+    }
 
-/// After setup or migrate: open DB and clear setup state.
-#[tauri::command]
-pub fn encryption_setup_open_db(
-    app: tauri::AppHandle,
-    db: State<DbState>,
-    paths: State<EncryptedPathsState>,
-    setup: State<EncryptionSetupState>,
-) -> Result<(), String> {
-    let (conn, path_tuple) = crate::db::init_db(&app).map_err(|e| e.to_string())?;
-    *db.0.lock().map_err(|e| e.to_string())? = Some(conn);
-    *paths.0.lock().map_err(|e| e.to_string())? = path_tuple;
-    *setup.0.lock().map_err(|e| e.to_string())? = None;
-    Ok(())
-}
+    #[test]
+    fn import_update_links_filled_company() {
+        let mut conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, last_name, email, created_at, updated_at)
+                 VALUES ('c1', 'Ali', 'Veli', 'ali@example.com', 'x', 'x');",
+        )
+        .expect("setup");
+        let row = ImportRow {
+            first_name: Some("Ali".to_string()),
+            last_name: Some("Veli".to_string()),
+            company: Some(" Acme ".to_string()),
+            email: Some("ali@example.com".to_string()),
+            ..Default::default()
+        };
+        let result = import_rows(&mut conn, vec![(row, vec![])], None, ImportMode::UpdateExisting, true).unwrap();
+        assert_eq!((result.inserted, result.updated, result.companies_created), (0, 1, 1));
+        let (company, company_name): (String, String) = conn
+            .query_row(
+                "SELECT c.company, co.name FROM contacts c JOIN companies co ON co.id = c.company_id WHERE c.id = 'c1'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((company.as_str(), company_name.as_str()), ("Acme", "Acme"));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rusqlite::Connection;
+    #[test]
+    fn maps_import_columns_and_validates_custom_values() {
+        let column_map: HashMap<String, String> = [
+            ("Ad", "first_name"),
+            ("E-posta", "email"),
+            ("Aşama", "custom:f1"),
+            ("Yok say", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let raw: HashMap<String, String> = [("Ad", "Ayşe"), ("E-posta", "a@example.com"), ("Aşama", "Seed"), ("Yok say", "x")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let (row, custom) = map_import_row(raw, &column_map).unwrap();
+        assert_eq!(row.first_name.as_deref(), Some("Ayşe"));
+        assert_eq!(row.email.as_deref(), Some("a@example.com"));
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].field_id, "f1");
+
+        let bad_map: HashMap<String, String> = [("Ad".to_string(), "nickname".to_string())].into_iter().collect();
+        let raw: HashMap<String, String> = [("Ad".to_string(), "Ayşe".to_string())].into_iter().collect();
+        assert!(map_import_row(raw, &bad_map).is_err());
+
+        let fields: HashMap<String, (String, String, Option<String>)> = [(
+            "f1".to_string(),
+            ("Aşama".to_string(), "single_select".to_string(), Some(r#"["Seed","Series A"]"#.to_string())),
+        )]
+        .into_iter()
+        .collect();
+        assert_eq!(import_custom_problem(&fields, &custom), None);
+        let invalid = vec![CustomValueInput {
+            field_id: "f1".to_string(),
+            value: Some("IPO".to_string()),
+        }];
+        assert!(import_custom_problem(&fields, &invalid).is_some());
+        let unknown = vec![CustomValueInput {
+            field_id: "f2".to_string(),
+            value: None,
+        }];
+        assert!(import_custom_problem(&fields, &unknown).is_some());
+
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE contact_custom_values (contact_id TEXT NOT NULL, field_id TEXT NOT NULL, value TEXT,
+                 PRIMARY KEY (contact_id, field_id));
+             INSERT INTO contact_custom_values VALUES ('c1', 'f1', 'Series A');",
+        )
+        .expect("setup");
+        assert!(!write_import_custom_values(&conn, "c1", &custom, true).unwrap());
+        assert!(write_import_custom_values(&conn, "c1", &custom, false).unwrap());
+        let value: String = conn
+            .query_row("SELECT value FROM contact_custom_values WHERE contact_id = 'c1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(value, "Seed");
+    }
 
     #[test]
-    fn validates_email_format() {
-        assert!(is_valid_email(&None));
-        assert!(is_valid_email(&Some("".to_string())));
-        assert!(is_valid_email(&Some("test@example.com".to_string())));
-        assert!(!is_valid_email(&Some("bad-email".to_string())));
-        assert!(!is_valid_email(&Some("no-at.example.com".to_string())));
-        assert!(!is_valid_email(&Some("a@b".to_string())));
+    fn import_reuses_companies_by_name() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute_batch(
+            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL, created_at TEXT, updated_at TEXT);
+             INSERT INTO companies VALUES ('co1', 'Acme  Corp', '2024-01-01', '2024-01-01');",
+        )
+        .expect("setup");
+        let mut cache = import_company_cache(&conn).unwrap();
+        assert_eq!(
+            import_company_id(&conn, &mut cache, Some(" acme corp "), "now").unwrap(),
+            Some(("co1".to_string(), false))
+        );
+        assert_eq!(import_company_id(&conn, &mut cache, Some("  "), "now").unwrap(), None);
+        let (created, is_new) = import_company_id(&conn, &mut cache, Some("Yıldız Yazılım"), "now")
+            .unwrap()
+            .unwrap();
+        assert!(is_new);
+        assert_eq!(
+            import_company_id(&conn, &mut cache, Some("yıldız  Yazılım"), "now").unwrap(),
+            Some((created, false))
+        );
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM companies", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2);
     }
 
     #[test]
-    fn validates_phone_format() {
-        assert!(is_valid_phone(&None));
-        assert!(is_valid_phone(&Some("".to_string())));
-        assert!(is_valid_phone(&Some("+90 532 123 45 67".to_string())));
-        assert!(is_valid_phone(&Some("(212) 555-1212".to_string())));
-        assert!(!is_valid_phone(&Some("abc".to_string())));
-        assert!(!is_valid_phone(&Some("12".to_string())));
+    fn looks_up_contacts_by_exact_email_and_phone() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO app_settings (key, value) VALUES ('default_country_code', '90');
+             INSERT INTO contacts (id, first_name, last_name, email, phone, phone_normalized, created_at, updated_at)
+                 VALUES ('c1', 'Ali', 'Veli', ' Ali@Example.com', '0532 123 45 67', '05321234567', 'x', '2024-01-01');
+             INSERT INTO contacts (id, first_name, last_name, email_secondary, phone_secondary, phone_secondary_normalized,
+                 created_at, updated_at)
+                 VALUES ('c2', 'Ayşe', 'Kaya', 'ali@example.com', '+90 532 123 45 67', '905321234567', 'x', '2024-02-01');
+             INSERT INTO contacts (id, first_name, last_name, email, created_at, updated_at, deleted_at)
+                 VALUES ('c3', 'Eski', 'Kayıt', 'ali@example.com', 'x', 'x', '2024-03-01');",
+        )
+        .expect("setup");
+        let ids = |contacts: Vec<Contact>| contacts.into_iter().map(|c| c.id).collect::<Vec<_>>();
+        assert_eq!(ids(contacts_with_email(&conn, "ALI@example.com ").unwrap()), vec!["c2", "c1"]);
+        assert!(contacts_with_email(&conn, "  ").unwrap().is_empty());
+        // National and international forms of the same number match each other.
+        assert_eq!(ids(contacts_with_phone(&conn, "0532 123-45-67").unwrap()), vec!["c2", "c1"]);
+        assert_eq!(ids(contacts_with_phone(&conn, "+90 (532) 123 45 67").unwrap()), vec!["c2", "c1"]);
+        assert_eq!(ids(contacts_with_phone(&conn, "0090 532 123 45 67").unwrap()), vec!["c2", "c1"]);
+        assert!(contacts_with_phone(&conn, "0532 123 45 68").unwrap().is_empty());
     }
 
     #[test]
-    fn resolves_company_name_from_id() {
+    fn lists_pinned_contacts_first() {
         let conn = Connection::open_in_memory().expect("open in-memory db");
-        conn.execute(
-            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL)",
-            [],
-        )
-        .expect("create companies table");
-        conn.execute(
-            "INSERT INTO companies (id, name) VALUES (?1, ?2)",
-            params!["c1", "Acme"],
+        conn.execute_batch(
+            "CREATE TABLE contacts (id TEXT PRIMARY KEY, last_name TEXT, updated_at TEXT, is_pinned INTEGER NOT NULL DEFAULT 0);
+             INSERT INTO contacts VALUES ('a', 'Ak', '2024-03-01', 0), ('b', 'Bal', '2024-01-01', 1),
+                 ('c', 'Can', '2024-02-01', 0), ('d', 'Dal', '2024-04-01', 1);",
         )
-        .expect("insert company");
+        .expect("setup");
+        let ids = |params: ContactListParams| -> Vec<String> {
+            let order = contact_list_order(&params).unwrap();
+            conn.prepare(&format!("SELECT c.id FROM contacts c {}", order))
+                .unwrap()
+                .query_map([], |r| r.get(0))
+                .unwrap()
+                .filter_map(|r| r.ok())
+                .collect()
+        };
+        assert_eq!(ids(ContactListParams::default()), vec!["d", "b", "a", "c"]);
+        let by_name = ContactListParams {
+            sort_by: Some("last_name".to_string()),
+            sort_dir: Some("asc".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_name), vec!["b", "d", "a", "c"]);
+    }
 
-        let mut company = None;
-        let company_id = Some("c1".to_string());
-        resolve_company_name(&conn, &company_id, &mut company);
-        assert_eq!(company, Some("Acme".to_string()));
+    #[test]
+    fn weekly_digest_skips_trashed_contacts() {
+        let conn = crate::db::test_db();
+        let now = Utc::now();
+        let ago = |days: i64| (now - chrono::Duration::days(days)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let birthday = (now + chrono::Duration::days(2)).format("1990-%m-%d").to_string();
+        for (id, deleted_at) in [("live", None), ("trashed", Some(ago(1)))] {
+            conn.execute(
+                "INSERT INTO contacts (id, first_name, last_name, last_touched_at, created_at, updated_at, deleted_at)
+                 VALUES (?1, ?1, '', ?2, ?3, ?3, ?4)",
+                params![id, ago(26), ago(2), deleted_at],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO reminders (id, contact_id, title, due_at, created_at) VALUES (?1, ?2, 'Ara', ?3, ?3)",
+                params![format!("r-{}", id), id, ago(3)],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO interactions (id, contact_id, kind, happened_at, created_at) VALUES (?1, ?2, 'call', ?3, ?3)",
+                params![format!("i-{}", id), id, ago(1)],
+            )
+            .unwrap();
+        }
+        conn.execute_batch("INSERT INTO custom_fields (id, name, kind, scope, created_at) VALUES ('bday', 'Doğum günü', 'date', 'contact', 'x');")
+            .unwrap();
+        for id in ["live", "trashed"] {
+            conn.execute(
+                "INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES (?1, 'bday', ?2)",
+                params![id, birthday],
+            )
+            .unwrap();
+        }
+        let digest = build_weekly_digest(&conn, Some(30), None).unwrap();
+        let contact_ids = |list: &[Contact]| list.iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+        assert_eq!(contact_ids(&digest.going_cold), vec!["live"]);
+        assert_eq!(contact_ids(&digest.new_contacts), vec!["live"]);
+        assert_eq!(
+            digest.overdue_reminders.iter().map(|r| r.contact_id.as_str()).collect::<Vec<_>>(),
+            vec!["live"]
+        );
+        assert_eq!(
+            digest.upcoming_dates.iter().map(|d| d.contact_id.as_str()).collect::<Vec<_>>(),
+            vec!["live"]
+        );
+        assert_eq!(
+            digest.recent_interactions.iter().map(|i| i.contact_id.as_str()).collect::<Vec<_>>(),
+            vec!["live"]
+        );
     }
 
     #[test]
-    fn does_not_override_existing_company_name() {
-        let conn = Connection::open_in_memory().expect("open in-memory db");
+    fn saved_segments_match_every_contact() {
+        let conn = crate::db::test_db();
+        for i in 0..25 {
+            conn.execute(
+                "INSERT INTO contacts (id, first_name, last_name, city, created_at, updated_at)
+                 VALUES (?1, 'Deniz', ?1, ?2, 'x', 'x')",
+                params![format!("c{:02}", i), if i % 5 == 0 { "Bursa" } else { "İzmir" }],
+            )
+            .unwrap();
+        }
         conn.execute(
-            "CREATE TABLE companies (id TEXT PRIMARY KEY, name TEXT NOT NULL)",
+            "INSERT INTO segments (id, name, query, filters_json, created_at) VALUES ('s1', 'Denizler', 'Den', '{}', 'x')",
             [],
         )
-        .expect("create companies table");
-        conn.execute(
-            "INSERT INTO companies (id, name) VALUES (?1, ?2)",
-            params!["c1", "Acme"],
-        )
-        .expect("insert company");
+        .unwrap();
+        let segment = segment_by_id(&conn, "s1").unwrap();
+        assert_eq!(run_global_search(&conn, &segment.query, &segment.filters, 20).unwrap().contacts.len(), 20);
+        assert_eq!(run_global_search(&conn, &segment.query, &segment.filters, -1).unwrap().contacts.len(), 25);
+        let export = SegmentFilter {
+            segment_id: Some("s1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(segment_contacts(&conn, &export, Some(&segment)).unwrap().len(), 25);
+        let in_bursa = SegmentFilter {
+            query: Some("den".to_string()),
+            filters: GlobalSearchFilters {
+                city: Some(" bursa".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(segment_contacts(&conn, &in_bursa, None).unwrap().len(), 5);
+    }
 
-        let mut company = Some("Manual Co".to_string());
-        let company_id = Some("c1".to_string());
-        resolve_company_name(&conn, &company_id, &mut company);
-        assert_eq!(company, Some("Manual Co".to_string()));
+    fn merged_input(primary_id: &str, secondary_id: &str, email: Option<&str>, phone: Option<&str>) -> MergeContactInput {
+        MergeContactInput {
+            primary_id: primary_id.to_string(),
+            secondary_id: secondary_id.to_string(),
+            merged: CreateContactInput {
+                first_name: "Ali".to_string(),
+                last_name: "Veli".to_string(),
+                title: Some("CTO".to_string()),
+                company: None,
+                company_id: None,
+                city: Some("Bursa".to_string()),
+                country: None,
+                email: email.map(str::to_string),
+                email_secondary: None,
+                phone: phone.map(str::to_string),
+                phone_secondary: None,
+                linkedin_url: None,
+                twitter_url: None,
+                website: None,
+                notes: None,
+                next_touch_at: None,
+            },
+            custom_values: None,
+        }
     }
 
     #[test]
-    fn normalizes_domain_values() {
-        assert_eq!(normalize_domain(&None), None);
-        assert_eq!(normalize_domain(&Some("".to_string())), None);
+    fn unmerge_restores_both_contacts() {
+        let mut conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, last_name, title, email, created_at, updated_at)
+                 VALUES ('p', 'Ali', 'Veli', 'CEO', 'ali@example.com', 'x', 'x'),
+                        ('s', 'Ali', 'V.', NULL, 'ali@work.example', 'x', 'x');
+             INSERT INTO tags (id, name) VALUES ('t1', 'VIP'), ('t2', 'Yatırımcı');
+             INSERT INTO contact_tags (contact_id, tag_id) VALUES ('p', 't1'), ('s', 't2');
+             INSERT INTO contact_custom_values (contact_id, field_id, value) VALUES ('s', 'cf_stage', 'Lead');
+             INSERT INTO interactions (id, contact_id, kind, happened_at) VALUES ('i1', 's', 'call', '2024-01-01');
+             INSERT INTO attachments (id, owner_type, owner_id, file_name, storage_path)
+                 VALUES ('f1', 'contact', 's', 'cv.pdf', 'f1.bin');",
+        )
+        .expect("setup");
+        let file_owner = |conn: &rusqlite::Connection| -> String {
+            conn.query_row("SELECT owner_id FROM attachments WHERE id = 'f1'", [], |r| r.get(0))
+                .unwrap()
+        };
+        let tags = |conn: &rusqlite::Connection, id: &str| contact_tag_ids(conn, id).unwrap();
+        let custom = |conn: &rusqlite::Connection, id: &str| contact_custom_pairs(conn, id).unwrap();
+        let interaction_owner = |conn: &rusqlite::Connection| -> String {
+            conn.query_row("SELECT contact_id FROM interactions WHERE id = 'i1'", [], |r| r.get(0))
+                .unwrap()
+        };
+
+        let merged = merge_contacts(&mut conn, merged_input("p", "s", Some("ali@example.com"), None)).unwrap();
+        assert_eq!((merged.title.as_deref(), merged.city.as_deref()), (Some("CTO"), Some("Bursa")));
+        assert_eq!(tags(&conn, "p"), vec!["t1", "t2"]);
+        assert_eq!(custom(&conn, "p"), vec![("cf_stage".to_string(), Some("Lead".to_string()))]);
+        assert_eq!(interaction_owner(&conn), "p");
+        assert_eq!(file_owner(&conn), "p");
+
+        let merge_id: String = conn
+            .query_row("SELECT id FROM contact_merges WHERE primary_id = 'p'", [], |r| r.get(0))
+            .unwrap();
+        let secondary = unmerge_contacts(&mut conn, &merge_id).unwrap();
         assert_eq!(
-            normalize_domain(&Some(" https://example.com/path ".to_string())),
-            Some("example.com".to_string())
+            (secondary.id.as_str(), secondary.last_name.as_str(), secondary.email.as_deref()),
+            ("s", "V.", Some("ali@work.example"))
         );
+        let primary: (Option<String>, Option<String>) = conn
+            .query_row("SELECT title, city FROM contacts WHERE id = 'p'", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!(primary, (Some("CEO".to_string()), None));
+        assert_eq!(tags(&conn, "p"), vec!["t1"]);
+        assert_eq!(tags(&conn, "s"), vec!["t2"]);
+        assert!(custom(&conn, "p").is_empty());
+        assert_eq!(custom(&conn, "s"), vec![("cf_stage".to_string(), Some("Lead".to_string()))]);
+        assert_eq!(interaction_owner(&conn), "s");
+        assert_eq!(file_owner(&conn), "s");
+        assert!(unmerge_contacts(&mut conn, &merge_id).is_err());
+    }
+
+    #[test]
+    fn merge_resets_verification_of_changed_values() {
+        let mut conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, last_name, email, phone, email_verified, phone_verified, created_at, updated_at)
+                 VALUES ('p', 'Ali', 'Veli', 'ali@example.com', '0532 111 11 11', 1, 1, 'x', 'x'),
+                        ('s', 'Ali', 'V.', 'ali@work.example', '0532 222 22 22', 1, 1, 'x', 'x');",
+        )
+        .expect("setup");
+        let merged = merge_contacts(
+            &mut conn,
+            merged_input("p", "s", Some("ali@example.com"), Some("0532 222 22 22")),
+        )
+        .unwrap();
+        assert_eq!((merged.email_verified, merged.phone_verified), (true, false));
+    }
+
+    #[test]
+    fn company_merge_moves_contacts_values_and_files() {
+        let mut conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO companies (id, name) VALUES ('co1', 'Acme'), ('co2', 'ACME Ltd');
+             INSERT INTO custom_fields (id, name, scope) VALUES ('cf_sector', 'Sektör', 'company'), ('cf_size', 'Boyut', 'company');
+             INSERT INTO company_custom_values (company_id, field_id, value)
+                 VALUES ('co1', 'cf_sector', 'SaaS'), ('co2', 'cf_sector', 'Yazılım'), ('co2', 'cf_size', '50');
+             INSERT INTO contacts (id, first_name, company, company_id, created_at, updated_at)
+                 VALUES ('a', 'Ali', 'Acme', 'co1', 'x', 'x'), ('b', 'Banu', 'ACME Ltd', 'co2', 'x', 'x');
+             INSERT INTO attachments (id, owner_type, owner_id, file_name, storage_path)
+                 VALUES ('f1', 'company', 'co2', 'deck.pdf', 'f1.bin');",
+        )
+        .expect("setup");
+        let merged = UpdateCompanyInput {
+            name: " Acme A.Ş. ".to_string(),
+            domain: None,
+            industry: None,
+            notes: None,
+            headcount: None,
+            hq_location: None,
+            funding_stage: None,
+        };
+        let negative = UpdateCompanyInput {
+            name: "Acme".to_string(),
+            domain: None,
+            industry: None,
+            notes: None,
+            headcount: Some(-5),
+            hq_location: None,
+            funding_stage: None,
+        };
+        assert!(merge_companies(&mut conn, "co1", "co2", &negative).is_err());
+        merge_companies(&mut conn, "co1", "co2", &merged).unwrap();
+        let contacts: Vec<(String, String, String)> = conn
+            .prepare("SELECT id, company_id, company FROM contacts ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
         assert_eq!(
-            normalize_domain(&Some("http://example.com".to_string())),
-            Some("example.com".to_string())
+            contacts,
+            vec![
+                ("a".to_string(), "co1".to_string(), "Acme A.Ş.".to_string()),
+                ("b".to_string(), "co1".to_string(), "Acme A.Ş.".to_string()),
+            ]
         );
+        let values: Vec<(String, String, String)> = conn
+            .prepare("SELECT company_id, field_id, value FROM company_custom_values ORDER BY field_id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
         assert_eq!(
-            normalize_domain(&Some("example.com".to_string())),
-            Some("example.com".to_string())
+            values,
+            vec![
+                ("co1".to_string(), "cf_sector".to_string(), "SaaS".to_string()),
+                ("co1".to_string(), "cf_size".to_string(), "50".to_string()),
+            ]
         );
+        let owner: String = conn
+            .query_row("SELECT owner_id FROM attachments WHERE id = 'f1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(owner, "co1");
+        let companies: i64 = conn
+            .query_row("SELECT COUNT(*) FROM companies WHERE id = 'co2'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(companies, 0);
+        assert!(merge_companies(&mut conn, "co1", "co1", &merged).is_err());
+    }
+
+    #[test]
+    fn refreshes_last_touched_from_edited_notes() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, last_touched_at, created_at, updated_at)
+                 VALUES ('c1', 'Ali', '2024-05-01T00:00:00Z', 'x', 'x');
+             INSERT INTO notes (id, contact_id, body, created_at, updated_at)
+                 VALUES ('n1', 'c1', 'edited', '2024-01-01T00:00:00Z', '2024-04-01T00:00:00Z');
+             INSERT INTO interactions (id, contact_id, kind, happened_at) VALUES ('i1', 'c1', 'call', '2024-03-01T00:00:00Z');",
+        )
+        .expect("setup");
+        refresh_last_touched(&conn, "c1", "now").unwrap();
+        let touched: Option<String> = conn
+            .query_row("SELECT last_touched_at FROM contacts WHERE id = 'c1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(touched.as_deref(), Some("2024-04-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn trashed_contacts_leave_reminder_and_note_views() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, created_at, updated_at, deleted_at)
+                 VALUES ('live', 'Deniz', 'x', 'x', NULL), ('trashed', 'Deniz', 'x', 'x', '2024-01-01T00:00:00Z');
+             INSERT INTO reminders (id, contact_id, title, due_at, recurring_days) VALUES
+                 ('r_live', 'live', 'Ara', '2020-01-01T00:00:00Z', 7),
+                 ('r_trashed', 'trashed', 'Ara', '2020-01-01T00:00:00Z', 7);
+             INSERT INTO notes (id, contact_id, body) VALUES ('n_live', 'live', 'bkz #yatırım'), ('n_trashed', 'trashed', 'bkz #yatırım');",
+        )
+        .expect("setup");
+        let stale: Vec<String> = reminders_stale_rows(&conn, 30).unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(stale, vec!["r_live"]);
+        let forecast = recurring_forecast_rows(&conn, Utc::now(), 30).unwrap();
+        assert!(!forecast.is_empty());
+        assert!(forecast.iter().all(|o| o.contact_id == "live"));
+        assert_eq!(hashtag_contact_ids(&conn, "yatırım").unwrap(), vec!["live"]);
+        assert_eq!(search_contact_ids(&conn, &fts_prefix_query("Deniz", false)).unwrap(), vec!["live"]);
+    }
+
+    #[test]
+    fn rejected_custom_value_batch_writes_nothing() {
+        let mut conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO companies (id, name) VALUES ('co1', 'Acme');
+             INSERT INTO custom_fields (id, name, kind, scope) VALUES
+                 ('cf_sector', 'Sektör', 'text', 'company'), ('cf_size', 'Çalışan', 'number', 'company');",
+        )
+        .expect("setup");
+        let value = |field_id: &str, value: &str| CustomValueInput {
+            field_id: field_id.to_string(),
+            value: Some(value.to_string()),
+        };
+        let bad = vec![value("cf_sector", "SaaS"), value("cf_size", "elli")];
+        assert!(set_custom_values(&mut conn, "company", "co1", &bad).is_err());
+        let wrong_scope = vec![value("cf_sector", "SaaS"), value("cf_stage", "Lead")];
+        assert!(set_custom_values(&mut conn, "company", "co1", &wrong_scope).is_err());
+        let count = |conn: &rusqlite::Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM company_custom_values", [], |r| r.get(0))
+                .unwrap()
+        };
+        assert_eq!(count(&conn), 0);
+        set_custom_values(&mut conn, "company", "co1", &[value("cf_sector", "SaaS"), value("cf_size", "50")]).unwrap();
+        assert_eq!(count(&conn), 2);
+        assert!(set_custom_values(&mut conn, "contact", "c1", &[value("cf_size", "50")]).is_err());
+    }
+
+    #[test]
+    fn creating_reminders_moves_next_touch_to_the_earliest() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, created_at, updated_at) VALUES ('c1', 'Ali', 'x', 'x');",
+        )
+        .expect("setup");
+        let reminder = |due_at: &str| CreateReminderInput {
+            contact_id: "c1".to_string(),
+            note_id: None,
+            title: "Ara".to_string(),
+            due_at: due_at.to_string(),
+            recurring_days: None,
+            notify_before_minutes: None,
+        };
+        let next_touch = |conn: &rusqlite::Connection| -> Option<String> {
+            conn.query_row("SELECT next_touch_at FROM contacts WHERE id = 'c1'", [], |r| r.get(0))
+                .unwrap()
+        };
+        insert_reminder(&conn, reminder("2030-02-01T09:00:00Z")).unwrap();
+        assert_eq!(next_touch(&conn).as_deref(), Some("2030-02-01T09:00:00Z"));
+        insert_reminder(&conn, reminder("2030-01-01T09:00:00Z")).unwrap();
+        assert_eq!(next_touch(&conn).as_deref(), Some("2030-01-01T09:00:00Z"));
+        insert_reminder(&conn, reminder("2030-03-01T09:00:00Z")).unwrap();
+        assert_eq!(next_touch(&conn).as_deref(), Some("2030-01-01T09:00:00Z"));
+    }
+
+    #[test]
+    fn orders_timeline_by_instant_across_formats() {
+        let conn = crate::db::test_db();
+        conn.execute_batch(
+            "INSERT INTO contacts (id, first_name, created_at, updated_at) VALUES ('c1', 'Ali', 'x', 'x');
+             INSERT INTO notes (id, contact_id, body, created_at) VALUES ('n1', 'c1', 'not', '2024-03-02 12:00:00');
+             INSERT INTO interactions (id, contact_id, kind, happened_at)
+                 VALUES ('i1', 'c1', 'call', '2024-03-02T13:00:00+03:00');
+             INSERT INTO reminders (id, contact_id, title, due_at, completed_at)
+                 VALUES ('r1', 'c1', 'Ara', '2024-03-01', '2024-03-02T11:00:00Z');
+             INSERT INTO reminders (id, contact_id, title, due_at, completed_at)
+                 VALUES ('r2', 'c1', 'Ara', '2024-03-01', '2024-03-02T10:00:00Z');",
+        )
+        .expect("setup");
+        let ids = |entries: &[TimelineEntry]| entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        let all = timeline_rows(&conn, "c1", 10, None).unwrap();
+        assert_eq!(ids(&all), vec!["n1", "r1", "r2", "i1"]);
+        let first = timeline_rows(&conn, "c1", 2, None).unwrap();
+        assert_eq!(ids(&first), vec!["n1", "r1"]);
+        let next = timeline_rows(&conn, "c1", 2, Some((first[1].at.as_str(), first[1].id.as_str()))).unwrap();
+        assert_eq!(ids(&next), vec!["r2", "i1"]);
+        let older = timeline_rows(&conn, "c1", 10, Some(("2024-03-02T13:00:00+03:00", ""))).unwrap();
+        assert!(older.is_empty());
+    }
+
+    #[test]
+    fn merges_reminders_and_interactions_newest_wins() {
+        let conn = crate::db::test_db();
+        conn.execute("ATTACH DATABASE ':memory:' AS remote", [])
+            .expect("attach remote");
+        conn.execute_batch(
+            "CREATE TABLE remote.reminders AS SELECT * FROM main.reminders;
+             CREATE TABLE remote.interactions AS SELECT * FROM main.interactions;
+             INSERT INTO contacts (id, first_name, created_at, updated_at) VALUES ('c1', 'Ali', 'x', 'x');
+             INSERT INTO main.reminders (id, contact_id, title, due_at, created_at, updated_at) VALUES
+                 ('r1', 'c1', 'Ara', '2024-02-01', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z'),
+                 ('r2', 'c1', 'Yerel', '2024-02-01', '2024-01-01T00:00:00Z', '2024-01-09T00:00:00Z');
+             INSERT INTO remote.reminders
+                 (id, contact_id, title, due_at, completed_at, notify_before_minutes, created_at, updated_at) VALUES
+                 ('r1', 'c1', 'Ara', '2024-02-01', '2024-01-05T00:00:00Z', 0, '2024-01-01T00:00:00Z', '2024-01-05T00:00:00Z'),
+                 ('r2', 'c1', 'Uzak', '2024-02-01', NULL, 0, '2024-01-01T00:00:00Z', '2024-01-03T00:00:00Z');
+             INSERT INTO main.interactions (id, contact_id, kind, happened_at, summary, created_at) VALUES
+                 ('i1', 'c1', 'call', '2024-01-02', 'eski', '2024-01-02T00:00:00Z');
+             INSERT INTO remote.interactions (id, contact_id, kind, happened_at, summary, created_at, updated_at) VALUES
+                 ('i1', 'c1', 'call', '2024-01-02', 'yeni', '2024-01-02T00:00:00Z', '2024-01-04T00:00:00Z');",
+        )
+        .expect("seed");
+
+        let mut summary = SyncMergeSummary::default();
+        for (table, keys, version) in SYNC_MERGE_TABLES {
+            if table == "reminders" || table == "interactions" {
+                merge_attached_table(&conn, table, keys, version, &mut summary).expect("merge");
+            }
+        }
+        assert_eq!((summary.inserted, summary.updated, summary.conflicts), (0, 2, 1));
+        let reminder = |id: &str| -> (String, Option<String>) {
+            conn.query_row("SELECT title, completed_at FROM main.reminders WHERE id = ?1", params![id], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .expect("reminder exists")
+        };
+        assert_eq!(reminder("r1"), ("Ara".to_string(), Some("2024-01-05T00:00:00Z".to_string())));
+        assert_eq!(reminder("r2"), ("Yerel".to_string(), None));
+        let summary_text: String = conn
+            .query_row("SELECT summary FROM main.interactions WHERE id = 'i1'", [], |r| r.get(0))
+            .expect("interaction exists");
+        assert_eq!(summary_text, "yeni");
     }
 }
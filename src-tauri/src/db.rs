@@ -5,9 +5,11 @@ use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Key, Nonce};
 use argon2::Argon2;
 use base64::{engine::general_purpose, Engine as _};
+use hkdf::Hkdf;
 use rand::rngs::OsRng;
 use rand::RngCore;
 use rusqlite::{params, Connection, Result as SqlResult};
+use sha2::Sha256;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager};
@@ -17,6 +19,26 @@ const KEYRING_ENTRY: &str = "db_master_key";
 const VAULT_DB: &str = "vault.db";
 const VAULT_DB_ENCRYPTED: &str = "vault.db.encrypted";
 const VAULT_DB_TMP: &str = "vault.db.tmp";
+const VAULT_SYNC_MERGE_TMP: &str = "vault-sync-merge.tmp";
+/// Per-install Argon2 salt (16 raw bytes); only present when the key was derived from a passphrase.
+const VAULT_SALT: &str = "vault.salt";
+/// Staged copy of vault.salt while a re-keyed vault file is swapped in.
+const VAULT_SALT_TMP: &str = "vault.salt.new";
+/// Salt used before per-install salts; kept so older vaults and sync files still open.
+const LEGACY_SALT: &[u8] = b"vaultcrm_db_salt";
+/// How the keychain key was made: "passphrase" or "device". Vaults set up before this file existed
+/// fall back to vault.salt; a legacy-salt passphrase vault has neither until its passphrase is entered
+/// once, which moves it to a per-install salt.
+const VAULT_KEY_MODE: &str = "vault.keymode";
+const KEY_MODE_PASSPHRASE: &str = "passphrase";
+const KEY_MODE_DEVICE: &str = "device";
+/// Encrypted-file header: magic + salt, followed by nonce || ciphertext. Lets a synced copy carry its salt.
+const SALT_HEADER_MAGIC: &[u8; 4] = b"VCS1";
+const SALT_LEN: usize = 16;
+/// UNSAFE developer mode: while this file exists, vault.db is opened as plain SQLite and never encrypted.
+const VAULT_PLAINTEXT_MARKER: &str = "vault.plaintext";
+/// Must be passed verbatim to encryption_disable.
+pub const PLAINTEXT_CONFIRM: &str = "DISABLE ENCRYPTION";
 
 /// G1.2: Filename in sync folder (NAS, Dropbox, etc.); same format as vault.db.encrypted (AES-256-GCM).
 pub const VAULT_SYNC_NAME: &str = "vault-sync.encrypted";
@@ -42,34 +64,148 @@ fn set_db_key(key: &[u8]) -> Result<(), String> {
     Ok(())
 }
 
+/// 32-byte HKDF-SHA256 subkey of `master` for the purpose named by `info`.
+fn hkdf_subkey(master: &[u8], info: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = [0u8; 32];
+    Hkdf::<Sha256>::new(None, master)
+        .expand(info, &mut out)
+        .map_err(|e| e.to_string())?;
+    Ok(out.to_vec())
+}
+
+/// Subkey of the keychain master key, so a secret such as the attachments key need not be stored.
+/// Changes whenever the master key does (passphrase change, re-enabling encryption).
+pub fn derive_subkey(info: &[u8]) -> Result<Vec<u8>, String> {
+    let master = get_db_key()?.ok_or_else(|| "No key in keychain".to_string())?;
+    hkdf_subkey(&master, info)
+}
+
 /// Derive 32-byte key from passphrase (F1.3).
-fn derive_key(passphrase: &str) -> Result<Vec<u8>, String> {
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Vec<u8>, String> {
     let mut key = [0u8; 32];
     Argon2::default()
-        .hash_password_into(passphrase.as_bytes(), b"vaultcrm_db_salt", &mut key)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
         .map_err(|e| e.to_string())?;
     Ok(key.to_vec())
 }
 
-fn encrypt_file(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+fn new_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+fn read_salt(app_data: &Path) -> Result<Option<Vec<u8>>, String> {
+    let path = app_data.join(VAULT_SALT);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let salt = std::fs::read(&path).map_err(|e| e.to_string())?;
+    if salt.len() != SALT_LEN {
+        return Err("vault.salt bozuk".to_string());
+    }
+    Ok(Some(salt))
+}
+
+/// Persists the salt the current key was derived with; `None` removes a stale one (device key / legacy salt).
+fn write_salt(app_data: &Path, salt: Option<&[u8]>) -> Result<(), String> {
+    let path = app_data.join(VAULT_SALT);
+    match salt {
+        Some(s) => std::fs::write(&path, s).map_err(|e| e.to_string()),
+        None if path.exists() => std::fs::remove_file(&path).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+fn write_key_mode(app_data: &Path, passphrase: bool) -> Result<(), String> {
+    let mode = if passphrase { KEY_MODE_PASSPHRASE } else { KEY_MODE_DEVICE };
+    std::fs::write(app_data.join(VAULT_KEY_MODE), mode).map_err(|e| e.to_string())
+}
+
+fn uses_passphrase(app_data: &Path) -> Result<bool, String> {
+    match std::fs::read_to_string(app_data.join(VAULT_KEY_MODE)) {
+        Ok(mode) => Ok(mode.trim() == KEY_MODE_PASSPHRASE),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(read_salt(app_data)?.is_some()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Salt in the header of an existing encrypted file (None for device-key and legacy-salt files).
+fn file_salt(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    use std::io::Read;
+    let header_len = SALT_HEADER_MAGIC.len() + SALT_LEN;
+    let mut header = Vec::with_capacity(header_len);
+    std::fs::File::open(path)
+        .and_then(|f| f.take(header_len as u64).read_to_end(&mut header))
+        .map_err(|e| e.to_string())?;
+    Ok(split_salt_header(&header).0.map(<[u8]>::to_vec))
+}
+
+/// Splits an optional salt header off an encrypted file; files without one predate per-install salts.
+fn split_salt_header(data: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    let header_len = SALT_HEADER_MAGIC.len() + SALT_LEN;
+    if data.len() >= header_len && data.starts_with(SALT_HEADER_MAGIC) {
+        (Some(&data[SALT_HEADER_MAGIC.len()..header_len]), &data[header_len..])
+    } else {
+        (None, data)
+    }
+}
+
+/// Key for an encrypted file from a passphrase: the file's own salt, or the legacy fixed salt.
+fn derive_key_for_file(passphrase: &str, data: &[u8]) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+    match split_salt_header(data).0 {
+        Some(salt) => Ok((derive_key(passphrase, salt)?, Some(salt.to_vec()))),
+        None => Ok((derive_key(passphrase, LEGACY_SALT)?, None)),
+    }
+}
+
+fn encrypt_file(key: &[u8], salt: Option<&[u8]>, plaintext: &[u8]) -> Result<Vec<u8>, String> {
     let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     let mut nonce_bytes = [0u8; 12];
     OsRng.fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
     let ciphertext = cipher.encrypt(nonce, plaintext).map_err(|e| e.to_string())?;
-    let mut out = Vec::with_capacity(12 + ciphertext.len());
+    let mut out = Vec::with_capacity(SALT_HEADER_MAGIC.len() + SALT_LEN + 12 + ciphertext.len());
+    if let Some(salt) = salt {
+        out.extend_from_slice(SALT_HEADER_MAGIC);
+        out.extend_from_slice(salt);
+    }
     out.extend_from_slice(&nonce_bytes);
     out.extend_from_slice(&ciphertext);
     Ok(out)
 }
 
-fn decrypt_file(key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+/// User-facing errors the frontend matches on: re-prompt for the passphrase vs. damaged file.
+pub const ERR_WRONG_PASSPHRASE: &str = "Parola hatalı";
+pub const ERR_CORRUPTED_FILE: &str = "Şifreli dosya bozuk";
+
+#[derive(Debug, PartialEq)]
+enum DecryptError {
+    /// Structurally invalid (e.g. too short to hold a nonce).
+    Corrupted,
+    /// AES-GCM tag check failed: wrong key (wrong passphrase), or a tampered/damaged body.
+    WrongKey,
+}
+
+impl std::fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecryptError::Corrupted => write!(f, "{}", ERR_CORRUPTED_FILE),
+            DecryptError::WrongKey => write!(f, "{}", ERR_WRONG_PASSPHRASE),
+        }
+    }
+}
+
+fn decrypt_file(key: &[u8], data: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    let ciphertext = split_salt_header(data).1;
     if ciphertext.len() < 12 {
-        return Err("Encrypted payload too short".to_string());
+        return Err(DecryptError::Corrupted);
     }
     let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
     let nonce = Nonce::from_slice(&ciphertext[..12]);
-    cipher.decrypt(nonce, &ciphertext[12..]).map_err(|e| e.to_string())
+    cipher
+        .decrypt(nonce, &ciphertext[12..])
+        .map_err(|_| DecryptError::WrongKey)
 }
 
 pub struct DbState(pub Mutex<Option<Connection>>);
@@ -86,11 +222,17 @@ pub struct EncryptionSetupState(pub Mutex<Option<SetupReason>>);
 pub enum SetupReason {
     FirstRun,
     MigratePlain,
+    /// The keychain key no longer opens vault.db.encrypted (e.g. replaced by another device's copy).
+    WrongPassphrase,
+    /// lock_now / auto-lock closed the DB; encryption_setup_open_db unlocks it.
+    Locked,
 }
 
 #[derive(Debug)]
 pub enum InitDbError {
     NeedSetup(SetupReason),
+    WrongPassphrase,
+    Corrupted,
     Other(String),
 }
 
@@ -98,6 +240,8 @@ impl std::fmt::Display for InitDbError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             InitDbError::NeedSetup(r) => write!(f, "NeedSetup({:?})", r),
+            InitDbError::WrongPassphrase => write!(f, "{}", ERR_WRONG_PASSPHRASE),
+            InitDbError::Corrupted => write!(f, "{}", ERR_CORRUPTED_FILE),
             InitDbError::Other(s) => write!(f, "{}", s),
         }
     }
@@ -120,15 +264,26 @@ pub fn init_db(app: &AppHandle) -> Result<(Connection, Option<(PathBuf, PathBuf)
     let path_encrypted = app_data.join(VAULT_DB_ENCRYPTED);
     let path_tmp = app_data.join(VAULT_DB_TMP);
 
+    if app_data.join(VAULT_PLAINTEXT_MARKER).exists() && path_plain.exists() {
+        let conn = Connection::open(&path_plain).map_err(|e| InitDbError::Other(e.to_string()))?;
+        init_schema(&conn).map_err(|e| InitDbError::Other(e.to_string()))?;
+        return Ok((conn, None));
+    }
+
     let key = get_db_key().map_err(|e| InitDbError::Other(e))?;
 
     if let Some(key) = key {
         // Key exists — use encrypted DB.
         if path_encrypted.exists() {
             let ciphertext = std::fs::read(&path_encrypted).map_err(|e| InitDbError::Other(e.to_string()))?;
-            let plaintext = decrypt_file(&key, &ciphertext).map_err(|e| InitDbError::Other(e))?;
+            let plaintext = decrypt_file(&key, &ciphertext).map_err(|e| match e {
+                DecryptError::WrongKey => InitDbError::WrongPassphrase,
+                DecryptError::Corrupted => InitDbError::Corrupted,
+            })?;
             std::fs::write(&path_tmp, &plaintext).map_err(|e| InitDbError::Other(e.to_string()))?;
             let conn = Connection::open(&path_tmp).map_err(|e| InitDbError::Other(e.to_string()))?;
+            // Existing vaults: apply new tables/columns added since the file was created.
+            init_schema(&conn).map_err(|e| InitDbError::Other(e.to_string()))?;
             return Ok((conn, Some((path_tmp, path_encrypted))));
         }
         // Key exists but no encrypted file — treat as first run with key already stored (e.g. after setup_create_key).
@@ -138,7 +293,8 @@ pub fn init_db(app: &AppHandle) -> Result<(Connection, Option<(PathBuf, PathBuf)
         init_settings(&conn, &app_data).map_err(|e| InitDbError::Other(e.to_string()))?;
         conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);").ok();
         let plaintext = std::fs::read(&path_tmp).map_err(|e| InitDbError::Other(e.to_string()))?;
-        let ciphertext = encrypt_file(&key, &plaintext).map_err(|e| InitDbError::Other(e))?;
+        let salt = read_salt(&app_data).map_err(InitDbError::Other)?;
+        let ciphertext = encrypt_file(&key, salt.as_deref(), &plaintext).map_err(|e| InitDbError::Other(e))?;
         std::fs::write(&path_encrypted, &ciphertext).map_err(|e| InitDbError::Other(e.to_string()))?;
         return Ok((conn, Some((path_tmp, path_encrypted))));
     }
@@ -159,35 +315,91 @@ pub fn flush_encrypted_db(conn: &Connection, temp_path: &Path, encrypted_path: &
     let key = get_db_key()?
         .ok_or_else(|| "No key in keychain".to_string())?;
     let plaintext = std::fs::read(temp_path).map_err(|e| e.to_string())?;
-    let ciphertext = encrypt_file(&key, &plaintext)?;
+    // The key was derived with the current file's salt; vault.salt only matters before the first write.
+    let salt = if encrypted_path.exists() {
+        file_salt(encrypted_path)?
+    } else {
+        match encrypted_path.parent() {
+            Some(dir) => read_salt(dir)?,
+            None => None,
+        }
+    };
+    let ciphertext = encrypt_file(&key, salt.as_deref(), &plaintext)?;
     std::fs::write(encrypted_path, &ciphertext).map_err(|e| e.to_string())?;
     Ok(())
 }
 
-/// F1.3: First-run — create key (device or from passphrase), empty DB, encrypt, store key.
-pub fn setup_create_key(app: &AppHandle, passphrase: Option<String>) -> Result<(), String> {
+/// Overwrites the file with zeros before removing it, so decrypted data doesn't linger on disk.
+/// Best-effort: SSD wear levelling and copy-on-write filesystems may keep old blocks around.
+fn secure_remove(path: &Path) -> Result<(), String> {
+    use std::io::Write;
+    let Ok(meta) = std::fs::metadata(path) else { return Ok(()); };
+    {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| e.to_string())?;
+        let zeros = vec![0u8; 64 * 1024];
+        let mut left = meta.len();
+        while left > 0 {
+            let n = left.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..n]).map_err(|e| e.to_string())?;
+            left -= n as u64;
+        }
+        file.sync_all().map_err(|e| e.to_string())?;
+    }
+    std::fs::remove_file(path).map_err(|e| e.to_string())
+}
+
+/// Lock and exit: wipes the decrypted temp DB and its SQLite side files (-wal/-shm/-journal). The
+/// connection must be closed and the DB flushed to vault.db.encrypted first.
+pub fn wipe_temp_db(temp_path: &Path) -> Result<(), String> {
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let mut path = temp_path.as_os_str().to_owned();
+        path.push(suffix);
+        secure_remove(Path::new(&path))?;
+    }
+    Ok(())
+}
+
+/// True when the vault key comes from a passphrase rather than a device key (see VAULT_KEY_MODE).
+pub fn vault_uses_passphrase(app: &AppHandle) -> Result<bool, String> {
     let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
-    let path_encrypted = app_data.join(VAULT_DB_ENCRYPTED);
-    let path_tmp = app_data.join(VAULT_DB_TMP);
+    uses_passphrase(&app_data)
+}
 
-    let key = if let Some(p) = passphrase {
+/// F1.3: Passphrase → (key, fresh salt); no passphrase → random device key without salt.
+fn key_from_setup_passphrase(passphrase: Option<String>) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+    if let Some(p) = passphrase {
         if p.is_empty() {
             return Err("Passphrase boş olamaz".to_string());
         }
-        derive_key(&p)?
+        let salt = new_salt();
+        Ok((derive_key(&p, &salt)?, Some(salt.to_vec())))
     } else {
         let mut key = [0u8; 32];
         OsRng.fill_bytes(&mut key);
-        key.to_vec()
-    };
+        Ok((key.to_vec(), None))
+    }
+}
+
+/// F1.3: First-run — create key (device or from passphrase), empty DB, encrypt, store key.
+pub fn setup_create_key(app: &AppHandle, passphrase: Option<String>) -> Result<(), String> {
+    let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
+    let path_encrypted = app_data.join(VAULT_DB_ENCRYPTED);
+    let path_tmp = app_data.join(VAULT_DB_TMP);
+
+    let (key, salt) = key_from_setup_passphrase(passphrase)?;
 
     set_db_key(&key)?;
+    write_salt(&app_data, salt.as_deref())?;
+    write_key_mode(&app_data, salt.is_some())?;
     let conn = Connection::open(&path_tmp).map_err(|e| e.to_string())?;
     init_schema(&conn).map_err(|e| e.to_string())?;
     init_settings(&conn, &app_data).map_err(|e| e.to_string())?;
     conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);").ok();
     let plaintext = std::fs::read(&path_tmp).map_err(|e| e.to_string())?;
-    let ciphertext = encrypt_file(&key, &plaintext)?;
+    let ciphertext = encrypt_file(&key, salt.as_deref(), &plaintext)?;
     std::fs::write(&path_encrypted, &ciphertext).map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -207,10 +419,86 @@ pub fn open_from_sync_folder(app: &AppHandle, folder_path: &str, passphrase: &st
         return Err("Sync klasöründe vault-sync.encrypted bulunamadı".to_string());
     }
     let dest = app_data.join(VAULT_DB_ENCRYPTED);
+    let data = std::fs::read(&source).map_err(|e| e.to_string())?;
+    let (key, salt) = derive_key_for_file(passphrase, &data)?;
+    // Verify before touching the local vault or keychain.
+    decrypt_file(&key, &data).map_err(|e| e.to_string())?;
     std::fs::copy(&source, &dest).map_err(|e| e.to_string())?;
-    let key = derive_key(passphrase)?;
-    set_db_key(&key)?;
-    Ok(())
+    match salt {
+        Some(salt) => {
+            set_db_key(&key)?;
+            write_salt(&app_data, Some(&salt))?;
+            write_key_mode(&app_data, true)
+        }
+        None => migrate_legacy_salt(&app_data, &dest, &data, passphrase, get_db_key()?.as_deref(), set_db_key),
+    }
+}
+
+/// G1.3: Decrypt the sync folder's vault-sync.encrypted (passphrase key) into a temp file for merging;
+/// the schema is brought up to date so it matches the local DB. Caller deletes the returned file.
+pub fn decrypt_sync_snapshot(app: &AppHandle, folder_path: &str, passphrase: &str) -> Result<PathBuf, String> {
+    let folder_path = folder_path.trim();
+    if folder_path.is_empty() {
+        return Err("Klasör yolu boş".to_string());
+    }
+    if passphrase.len() < 8 {
+        return Err("Passphrase en az 8 karakter olmalı".to_string());
+    }
+    let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
+    let source = std::path::Path::new(folder_path).join(VAULT_SYNC_NAME);
+    if !source.exists() {
+        return Err("Sync klasöründe vault-sync.encrypted bulunamadı".to_string());
+    }
+    let ciphertext = std::fs::read(&source).map_err(|e| e.to_string())?;
+    let (key, _) = derive_key_for_file(passphrase, &ciphertext)?;
+    let plaintext = decrypt_file(&key, &ciphertext).map_err(|e| e.to_string())?;
+    let dest = app_data.join(VAULT_SYNC_MERGE_TMP);
+    std::fs::write(&dest, &plaintext).map_err(|e| e.to_string())?;
+    let conn = Connection::open(&dest).map_err(|e| e.to_string())?;
+    init_schema(&conn).map_err(|e| e.to_string())?;
+    drop(conn);
+    Ok(dest)
+}
+
+/// F1.3: Re-unlock the local vault with its passphrase (SetupReason::WrongPassphrase, or Locked).
+/// Keychain and salt are only updated once decryption succeeds.
+pub fn unlock_with_passphrase(app: &AppHandle, passphrase: &str) -> Result<(), String> {
+    let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
+    unlock_vault_dir(&app_data, passphrase, get_db_key()?.as_deref(), set_db_key)
+}
+
+fn unlock_vault_dir(
+    app_data: &Path,
+    passphrase: &str,
+    old_key: Option<&[u8]>,
+    set_key: impl Fn(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let path = app_data.join(VAULT_DB_ENCRYPTED);
+    let data = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let (key, salt) = derive_key_for_file(passphrase, &data)?;
+    decrypt_file(&key, &data).map_err(|e| e.to_string())?;
+    match salt {
+        Some(salt) => {
+            set_key(&key)?;
+            write_salt(app_data, Some(&salt))?;
+            write_key_mode(app_data, true)
+        }
+        None => migrate_legacy_salt(app_data, &path, &data, passphrase, old_key, set_key),
+    }
+}
+
+/// A legacy-salt file can't be told apart from a device-key vault, so once its passphrase is known
+/// it is re-encrypted under a per-install salt and recorded as a passphrase vault.
+fn migrate_legacy_salt(
+    app_data: &Path,
+    encrypted_path: &Path,
+    data: &[u8],
+    passphrase: &str,
+    old_key: Option<&[u8]>,
+    set_key: impl Fn(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let rekeyed = reencrypt_with_passphrase(data, None, passphrase, passphrase)?;
+    commit_rekeyed(app_data, encrypted_path, &rekeyed, old_key, set_key)
 }
 
 /// Migrate plain vault.db to encrypted: read plain, encrypt, write vault.db.encrypted, store key, backup plain.
@@ -222,26 +510,132 @@ pub fn migrate_plain_to_encrypted(app: &AppHandle, passphrase: Option<String>) -
         return Err("Plain vault.db bulunamadı".to_string());
     }
 
-    let key = if let Some(p) = passphrase {
-        if p.is_empty() {
-            return Err("Passphrase boş olamaz".to_string());
-        }
-        derive_key(&p)?
-    } else {
-        let mut key = [0u8; 32];
-        OsRng.fill_bytes(&mut key);
-        key.to_vec()
-    };
+    let (key, salt) = key_from_setup_passphrase(passphrase)?;
 
     set_db_key(&key)?;
+    write_salt(&app_data, salt.as_deref())?;
+    write_key_mode(&app_data, salt.is_some())?;
     let plaintext = std::fs::read(&path_plain).map_err(|e| e.to_string())?;
-    let ciphertext = encrypt_file(&key, &plaintext)?;
+    let ciphertext = encrypt_file(&key, salt.as_deref(), &plaintext)?;
     std::fs::write(&path_encrypted, &ciphertext).map_err(|e| e.to_string())?;
     let backup = app_data.join("vault.db.plain.backup");
     std::fs::rename(&path_plain, &backup).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// UNSAFE (developer mode): writes the live DB to a persistent plain vault.db and sets the marker so
+/// init_db opens it directly and nothing is flushed encrypted any more. vault.db.encrypted and the
+/// keychain key are left alone but go stale until encryption_enable.
+pub fn disable_encryption(app: &AppHandle, conn: &Connection, temp_path: &Path) -> Result<(), String> {
+    let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);").map_err(|e| e.to_string())?;
+    std::fs::copy(temp_path, app_data.join(VAULT_DB)).map_err(|e| e.to_string())?;
+    std::fs::write(
+        app_data.join(VAULT_PLAINTEXT_MARKER),
+        "Vault is stored UNENCRYPTED in vault.db (developer mode). Re-enable encryption from the app.\n",
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Leaves plaintext mode: re-encrypts vault.db under a new key (as migrate_plain_to_encrypted) and
+/// removes the plain file and its backup. The connection on vault.db must already be closed.
+pub fn enable_encryption(app: &AppHandle, passphrase: Option<String>) -> Result<(), String> {
+    let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
+    let marker = app_data.join(VAULT_PLAINTEXT_MARKER);
+    if !marker.exists() {
+        return Err("Şifreleme zaten açık".to_string());
+    }
+    migrate_plain_to_encrypted(app, passphrase)?;
+    std::fs::remove_file(&marker).map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(app_data.join("vault.db.plain.backup"));
+    Ok(())
+}
+
+struct Rekeyed {
+    key: Vec<u8>,
+    salt: Vec<u8>,
+    file: Vec<u8>,
+}
+
+/// Verifies `old` against an encrypted file and re-encrypts `plaintext` (or the file's own content)
+/// under `new` with a fresh salt.
+fn reencrypt_with_passphrase(
+    encrypted: &[u8],
+    plaintext: Option<&[u8]>,
+    old: &str,
+    new: &str,
+) -> Result<Rekeyed, String> {
+    if new.is_empty() {
+        return Err("Passphrase boş olamaz".to_string());
+    }
+    let (old_key, _) = derive_key_for_file(old, encrypted)?;
+    let current = decrypt_file(&old_key, encrypted).map_err(|e| match e {
+        DecryptError::WrongKey => "Mevcut parola hatalı".to_string(),
+        DecryptError::Corrupted => e.to_string(),
+    })?;
+    let salt = new_salt().to_vec();
+    let key = derive_key(new, &salt)?;
+    let file = encrypt_file(&key, Some(&salt), plaintext.unwrap_or(&current))?;
+    Ok(Rekeyed { key, salt, file })
+}
+
+/// F1.3: Rotate the passphrase. The old one is checked against vault.db.encrypted; on a mismatch nothing
+/// is touched. The live DB (temp file) is re-encrypted so unsaved changes are kept.
+pub fn change_passphrase(
+    app: &AppHandle,
+    conn: &Connection,
+    temp_path: &Path,
+    encrypted_path: &Path,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<(), String> {
+    let app_data = app_data_dir(app).map_err(|e| e.to_string())?;
+    let encrypted = std::fs::read(encrypted_path).map_err(|e| e.to_string())?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);").map_err(|e| e.to_string())?;
+    let plaintext = std::fs::read(temp_path).map_err(|e| e.to_string())?;
+    let rekeyed =
+        reencrypt_with_passphrase(&encrypted, Some(&plaintext), old_passphrase, new_passphrase)?;
+    commit_rekeyed(&app_data, encrypted_path, &rekeyed, get_db_key()?.as_deref(), set_db_key)
+}
+
+/// Swaps in a re-keyed vault file. File and salt are both staged first; the salt goes in before the
+/// file (flush takes the salt from the file header, so a new vault.salt beside the old file is
+/// harmless) and the keychain changes last, falling back to `old_key` if the file can't be moved.
+fn commit_rekeyed(
+    app_data: &Path,
+    encrypted_path: &Path,
+    rekeyed: &Rekeyed,
+    old_key: Option<&[u8]>,
+    set_key: impl Fn(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let staged = encrypted_path.with_extension("encrypted.new");
+    let staged_salt = app_data.join(VAULT_SALT_TMP);
+    let discard = || {
+        let _ = std::fs::remove_file(&staged);
+        let _ = std::fs::remove_file(&staged_salt);
+    };
+    let written = std::fs::write(&staged, &rekeyed.file)
+        .and_then(|_| std::fs::write(&staged_salt, &rekeyed.salt))
+        .and_then(|_| std::fs::rename(&staged_salt, app_data.join(VAULT_SALT)));
+    if let Err(e) = written {
+        discard();
+        return Err(e.to_string());
+    }
+    if let Err(e) = write_key_mode(app_data, true).and_then(|_| set_key(&rekeyed.key)) {
+        discard();
+        return Err(e);
+    }
+    if let Err(e) = std::fs::rename(&staged, encrypted_path) {
+        discard();
+        if let Some(old_key) = old_key {
+            let _ = set_key(old_key);
+        }
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
 fn init_schema(conn: &Connection) -> SqlResult<()> {
     conn.execute_batch(
         "
@@ -266,6 +660,10 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             domain TEXT,
             industry TEXT,
             notes TEXT,
+            headcount INTEGER,
+            hq_location TEXT,
+            funding_stage TEXT,
+            is_pinned INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         );
@@ -281,6 +679,8 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             country TEXT,
             email TEXT,
             phone TEXT,
+            phone_normalized TEXT,
+            phone_secondary_normalized TEXT,
             linkedin_url TEXT,
             twitter_url TEXT,
             website TEXT,
@@ -290,6 +690,15 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             notes TEXT,
             last_touched_at TEXT,
             next_touch_at TEXT,
+            do_not_contact INTEGER NOT NULL DEFAULT 0,
+            consent_status TEXT,
+            consent_at TEXT,
+            timezone TEXT,
+            email_verified INTEGER NOT NULL DEFAULT 0,
+            phone_verified INTEGER NOT NULL DEFAULT 0,
+            deleted_at TEXT,
+            import_batch_id TEXT,
+            is_pinned INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now'))
         );
@@ -301,6 +710,8 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             kind TEXT NOT NULL DEFAULT 'text',
             options TEXT,
             sort_order INTEGER NOT NULL DEFAULT 0,
+            scope TEXT NOT NULL DEFAULT 'contact',
+            required INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT (datetime('now'))
         );
 
@@ -312,6 +723,14 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             PRIMARY KEY (contact_id, field_id)
         );
 
+        -- Company custom values (custom_fields with scope = 'company')
+        CREATE TABLE IF NOT EXISTS company_custom_values (
+            company_id TEXT NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+            field_id TEXT NOT NULL REFERENCES custom_fields(id) ON DELETE CASCADE,
+            value TEXT,
+            PRIMARY KEY (company_id, field_id)
+        );
+
         -- Contact <-> Tag (many-to-many)
         CREATE TABLE IF NOT EXISTS contact_tags (
             contact_id TEXT NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
@@ -340,7 +759,9 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             snooze_until TEXT,
             recurring_days INTEGER,
             completed_at TEXT,
-            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            notify_before_minutes INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT
         );
 
         -- Interactions (meeting, call, email, DM) for timeline
@@ -350,8 +771,55 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             kind TEXT NOT NULL,
             happened_at TEXT NOT NULL,
             summary TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT
+        );
+
+        -- Contact snapshots (JSON of fields + related row keys, for diff since last review)
+        CREATE TABLE IF NOT EXISTS contact_snapshots (
+            id TEXT PRIMARY KEY,
+            contact_id TEXT NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+            data TEXT NOT NULL,
             created_at TEXT NOT NULL DEFAULT (datetime('now'))
         );
+        CREATE INDEX IF NOT EXISTS idx_contact_snapshots_contact ON contact_snapshots(contact_id);
+
+        -- Contact merges (A5): pre-merge rows + reassigned ids, so a merge can be undone
+        CREATE TABLE IF NOT EXISTS contact_merges (
+            id TEXT PRIMARY KEY,
+            primary_id TEXT NOT NULL,
+            secondary_id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            merged_at TEXT NOT NULL,
+            undone_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_contact_merges_primary ON contact_merges(primary_id);
+
+        -- Import batches: one row per import run; contacts.import_batch_id points here
+        CREATE TABLE IF NOT EXISTS import_batches (
+            id TEXT PRIMARY KEY,
+            source TEXT,
+            created_at TEXT NOT NULL,
+            row_count INTEGER NOT NULL DEFAULT 0,
+            undone_at TEXT
+        );
+
+        -- Contact pairs dismissed in dedup review (a_id < b_id)
+        CREATE TABLE IF NOT EXISTS dedup_ignored_pairs (
+            a_id TEXT NOT NULL,
+            b_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (a_id, b_id)
+        );
+
+        -- Saved global_search query + filters
+        CREATE TABLE IF NOT EXISTS segments (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            query TEXT NOT NULL DEFAULT '',
+            filters_json TEXT,
+            created_at TEXT NOT NULL
+        );
 
         -- Attachments (A6)
         CREATE TABLE IF NOT EXISTS attachments (
@@ -387,6 +855,26 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
             INSERT INTO contacts_fts(contacts_fts, rowid, first_name, last_name, company, notes)
             VALUES ('delete', old.rowid, old.first_name, old.last_name, old.company, old.notes);
         END;
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+            title, body,
+            content='notes',
+            content_rowid='rowid'
+        );
+        CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON notes BEGIN
+            INSERT INTO notes_fts(rowid, title, body)
+            VALUES (new.rowid, new.title, new.body);
+        END;
+        CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, body)
+            VALUES ('delete', old.rowid, old.title, old.body);
+            INSERT INTO notes_fts(rowid, title, body)
+            VALUES (new.rowid, new.title, new.body);
+        END;
+        CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, body)
+            VALUES ('delete', old.rowid, old.title, old.body);
+        END;
         ",
     )?;
     let alter_columns = [
@@ -394,14 +882,94 @@ fn init_schema(conn: &Connection) -> SqlResult<()> {
         "ALTER TABLE contacts ADD COLUMN email_secondary TEXT",
         "ALTER TABLE contacts ADD COLUMN phone_secondary TEXT",
         "ALTER TABLE contacts ADD COLUMN company_id TEXT",
+        "ALTER TABLE companies ADD COLUMN headcount INTEGER",
+        "ALTER TABLE companies ADD COLUMN hq_location TEXT",
+        "ALTER TABLE companies ADD COLUMN funding_stage TEXT",
+        "ALTER TABLE custom_fields ADD COLUMN scope TEXT NOT NULL DEFAULT 'contact'",
+        "ALTER TABLE reminders ADD COLUMN notify_before_minutes INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE companies ADD COLUMN is_pinned INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE contacts ADD COLUMN phone_normalized TEXT",
+        "ALTER TABLE contacts ADD COLUMN phone_secondary_normalized TEXT",
+        "ALTER TABLE contacts ADD COLUMN do_not_contact INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE contacts ADD COLUMN consent_status TEXT",
+        "ALTER TABLE contacts ADD COLUMN consent_at TEXT",
+        "ALTER TABLE contacts ADD COLUMN timezone TEXT",
+        "ALTER TABLE contacts ADD COLUMN email_verified INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE contacts ADD COLUMN phone_verified INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE contacts ADD COLUMN deleted_at TEXT",
+        "ALTER TABLE contacts ADD COLUMN import_batch_id TEXT",
+        "ALTER TABLE contacts ADD COLUMN is_pinned INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE custom_fields ADD COLUMN required INTEGER NOT NULL DEFAULT 0",
+        "ALTER TABLE reminders ADD COLUMN updated_at TEXT",
+        "ALTER TABLE interactions ADD COLUMN updated_at TEXT",
     ];
     for sql in alter_columns {
         if conn.execute(sql, []).is_err() {}
     }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_contacts_phone_normalized ON contacts(phone_normalized);
+         CREATE INDEX IF NOT EXISTS idx_contacts_phone_secondary_normalized ON contacts(phone_secondary_normalized);
+         CREATE INDEX IF NOT EXISTS idx_contacts_import_batch ON contacts(import_batch_id);
+         CREATE INDEX IF NOT EXISTS idx_contacts_email_lower ON contacts(LOWER(TRIM(email)));
+         CREATE INDEX IF NOT EXISTS idx_contacts_email_secondary_lower ON contacts(LOWER(TRIM(email_secondary)));",
+    )?;
+    backfill_phone_normalized(conn)?;
+    backfill_notes_fts(conn)?;
+    // Fields created before scoping existed are contact fields.
+    conn.execute(
+        "UPDATE custom_fields SET scope = 'contact' WHERE scope IS NULL OR scope = ''",
+        [],
+    )?;
     seed_default_custom_fields(conn)?;
     Ok(())
 }
 
+/// In-memory connection with the full app schema, for tests that go through CONTACT_SELECT and
+/// other real queries.
+#[cfg(test)]
+pub(crate) fn test_db() -> Connection {
+    let conn = Connection::open_in_memory().expect("open in-memory db");
+    init_schema(&conn).expect("init schema");
+    conn
+}
+
+/// Vaults created before notes_fts have notes but an empty index; build it once from the notes table.
+fn backfill_notes_fts(conn: &Connection) -> SqlResult<()> {
+    let indexed: i64 = conn.query_row("SELECT COUNT(*) FROM notes_fts_docsize", [], |r| r.get(0))?;
+    if indexed > 0 {
+        return Ok(());
+    }
+    let notes: i64 = conn.query_row("SELECT COUNT(*) FROM notes", [], |r| r.get(0))?;
+    if notes > 0 {
+        conn.execute("INSERT INTO notes_fts(notes_fts) VALUES ('rebuild')", [])?;
+    }
+    Ok(())
+}
+
+/// Fills phone_normalized / phone_secondary_normalized for rows written before the columns existed.
+fn backfill_phone_normalized(conn: &Connection) -> SqlResult<()> {
+    let mut stmt = conn.prepare(
+        "SELECT id, phone, phone_secondary FROM contacts
+         WHERE (phone IS NOT NULL AND phone_normalized IS NULL)
+            OR (phone_secondary IS NOT NULL AND phone_secondary_normalized IS NULL)",
+    )?;
+    let rows: Vec<(String, Option<String>, Option<String>)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    for (id, phone, phone_secondary) in rows {
+        conn.execute(
+            "UPDATE contacts SET phone_normalized = ?1, phone_secondary_normalized = ?2 WHERE id = ?3",
+            params![
+                crate::commands::normalize_phone(&phone),
+                crate::commands::normalize_phone(&phone_secondary),
+                id
+            ],
+        )?;
+    }
+    Ok(())
+}
+
 fn init_settings(conn: &Connection, app_data: &Path) -> SqlResult<()> {
     let app_data_str = app_data.to_string_lossy().to_string();
     conn.execute(
@@ -435,3 +1003,98 @@ fn seed_default_custom_fields(conn: &Connection) -> SqlResult<()> {
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wipes_temp_db_and_side_files() {
+        let dir = std::env::temp_dir().join(format!("vaultcrm-lock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let temp = dir.join(VAULT_DB_TMP);
+        std::fs::write(&temp, b"plaintext").unwrap();
+        std::fs::write(dir.join(format!("{}-wal", VAULT_DB_TMP)), b"wal").unwrap();
+        wipe_temp_db(&temp).unwrap();
+        assert!(!temp.exists());
+        assert!(!dir.join(format!("{}-wal", VAULT_DB_TMP)).exists());
+        // Nothing left to wipe is fine.
+        wipe_temp_db(&temp).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn derives_distinct_subkeys() {
+        let master = [7u8; 32];
+        let a = hkdf_subkey(&master, b"attachments").unwrap();
+        assert_eq!(a.len(), 32);
+        assert_eq!(a, hkdf_subkey(&master, b"attachments").unwrap());
+        assert_ne!(a, hkdf_subkey(&master, b"other").unwrap());
+        assert_ne!(a, hkdf_subkey(&[8u8; 32], b"attachments").unwrap());
+        assert_ne!(a, master.to_vec());
+    }
+
+    #[test]
+    fn change_passphrase_round_trip() {
+        let plaintext = b"SQLite format 3\0 vault contents";
+        let old_key = derive_key("old passphrase", LEGACY_SALT).expect("derive");
+        let encrypted = encrypt_file(&old_key, None, plaintext).expect("encrypt");
+
+        let err = reencrypt_with_passphrase(&encrypted, None, "wrong", "new passphrase")
+            .err()
+            .expect("wrong passphrase rejected");
+        assert_eq!(err, "Mevcut parola hatalı");
+
+        let Rekeyed { key, salt, file } =
+            reencrypt_with_passphrase(&encrypted, None, "old passphrase", "new passphrase").expect("rekey");
+        assert_eq!(split_salt_header(&file).0, Some(salt.as_slice()));
+        let (derived, _) = derive_key_for_file("new passphrase", &file).expect("derive new");
+        assert_eq!(derived, key);
+        assert_eq!(decrypt_file(&derived, &file).expect("decrypt"), plaintext.to_vec());
+
+        let (stale, _) = derive_key_for_file("old passphrase", &file).expect("derive old");
+        assert_eq!(decrypt_file(&stale, &file), Err(DecryptError::WrongKey));
+    }
+
+    #[test]
+    fn distinguishes_wrong_key_from_corrupted_file() {
+        let key = [7u8; 32];
+        let file = encrypt_file(&key, Some(&[1u8; SALT_LEN]), b"data").expect("encrypt");
+        assert_eq!(decrypt_file(&[8u8; 32], &file), Err(DecryptError::WrongKey));
+        assert_eq!(decrypt_file(&key, &file[..SALT_HEADER_MAGIC.len() + SALT_LEN + 5]), Err(DecryptError::Corrupted));
+        assert_eq!(decrypt_file(&key, b"short"), Err(DecryptError::Corrupted));
+    }
+
+    #[test]
+    fn legacy_passphrase_vault_moves_to_salt_on_unlock() {
+        let dir = std::env::temp_dir().join(format!("vaultcrm-legacy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let plaintext = b"SQLite format 3\0 legacy vault";
+        let legacy_key = derive_key("legacy passphrase", LEGACY_SALT).expect("derive");
+        let legacy = encrypt_file(&legacy_key, None, plaintext).expect("encrypt");
+        std::fs::write(dir.join(VAULT_DB_ENCRYPTED), &legacy).expect("write");
+        assert!(!uses_passphrase(&dir).expect("mode"));
+
+        let stored = std::cell::RefCell::new(Vec::new());
+        let set_key = |key: &[u8]| {
+            *stored.borrow_mut() = key.to_vec();
+            Ok(())
+        };
+        assert!(unlock_vault_dir(&dir, "wrong", Some(&legacy_key), set_key).is_err());
+        assert_eq!(std::fs::read(dir.join(VAULT_DB_ENCRYPTED)).expect("read"), legacy);
+        assert!(!uses_passphrase(&dir).expect("mode"));
+
+        unlock_vault_dir(&dir, "legacy passphrase", Some(&legacy_key), set_key).expect("unlock");
+        assert!(uses_passphrase(&dir).expect("mode"));
+        let path = dir.join(VAULT_DB_ENCRYPTED);
+        let file = std::fs::read(&path).expect("read");
+        assert_eq!(file_salt(&path).expect("header"), read_salt(&dir).expect("salt"));
+        assert!(file_salt(&path).expect("header").is_some());
+        let key = stored.borrow().clone();
+        assert_eq!(derive_key_for_file("legacy passphrase", &file).expect("derive").0, key);
+        assert_eq!(decrypt_file(&key, &file).expect("decrypt"), plaintext.to_vec());
+        assert!(!dir.join(VAULT_SALT_TMP).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -22,6 +22,14 @@ pub fn run() {
                     app.manage(EncryptedPathsState(std::sync::Mutex::new(None)));
                     app.manage(EncryptionSetupState(std::sync::Mutex::new(Some(reason))));
                 }
+                // Re-prompt for the passphrase instead of failing startup.
+                Err(db::InitDbError::WrongPassphrase) => {
+                    app.manage(DbState(std::sync::Mutex::new(None)));
+                    app.manage(EncryptedPathsState(std::sync::Mutex::new(None)));
+                    app.manage(EncryptionSetupState(std::sync::Mutex::new(Some(
+                        db::SetupReason::WrongPassphrase,
+                    ))));
+                }
                 Err(e) => return Err(e.to_string().into()),
             }
             Ok(())
@@ -31,14 +39,22 @@ pub fn run() {
                 let app = window.app_handle();
                 if let Some(db) = app.try_state::<DbState>() {
                     if let Some(paths) = app.try_state::<EncryptedPathsState>() {
-                        let guard_db = db.0.lock().unwrap();
-                        let guard_paths = paths.0.lock().unwrap();
+                        let mut guard_db = db.0.lock().unwrap();
+                        let mut guard_paths = paths.0.lock().unwrap();
+                        let mut flushed = false;
                         if let (Some(ref conn), Some((ref temp, ref enc))) =
                             (guard_db.as_ref(), guard_paths.as_ref())
                         {
-                            let _ = db::flush_encrypted_db(conn, temp.as_path(), enc.as_path());
+                            flushed = db::flush_encrypted_db(conn, temp.as_path(), enc.as_path()).is_ok();
                             let _ = commands::run_backup(&app, conn, enc.as_path());
                         }
+                        // Don't leave the decrypted vault.db.tmp behind; only once it is safely flushed.
+                        if flushed {
+                            if let (Some(conn), Some((temp, _))) = (guard_db.take(), guard_paths.take()) {
+                                let _ = conn.close();
+                                let _ = db::wipe_temp_db(&temp);
+                            }
+                        }
                     }
                 }
             }
@@ -46,49 +62,185 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::contact_list,
             commands::contact_get,
+            commands::contact_set_pinned,
+            commands::contacts_pinned,
             commands::contact_create,
             commands::contact_update,
             commands::contact_delete,
+            commands::contact_restore,
+            commands::contact_list_trashed,
+            commands::contact_purge,
+            commands::contact_clear_history,
+            commands::trash_empty,
+            commands::contact_set_dnc,
+            commands::contact_set_consent,
+            commands::contacts_by_consent,
+            commands::contact_set_verified,
+            commands::contacts_unverified,
+            commands::completeness,
+            commands::contacts_by_completeness,
+            commands::contact_set_timezone,
+            commands::contacts_bulk_set_location,
+            commands::contacts_delete_bulk,
+            commands::contacts_set_company_bulk,
+            commands::contacts_local_time,
+            commands::contact_qr_vcard,
+            commands::export_vcard,
+            commands::contacts_export_vcards,
+            commands::export_csv,
+            commands::contacts_similar_to,
+            commands::contacts_by_note_volume,
+            commands::contacts_canonicalize,
+            commands::contacts_resolve_company_names,
+            commands::contacts_find_by_phone_partial,
+            commands::contacts_find_by_phone,
+            commands::contact_by_email,
+            commands::contact_by_phone,
+            commands::default_country_code_get,
+            commands::default_country_code_set,
             commands::company_list,
             commands::company_get,
+            commands::company_did_you_mean,
             commands::company_create,
             commands::company_update,
+            commands::company_merge,
+            commands::company_set_pinned,
+            commands::funding_stage_options_get,
+            commands::funding_stage_options_set,
             commands::contact_list_by_company,
+            commands::contact_list_by_companies,
+            commands::tag_list,
+            commands::tag_create,
+            commands::tag_update,
+            commands::tag_delete,
+            commands::contact_tags_get,
+            commands::contact_tag_add,
+            commands::contact_tag_remove,
+            commands::contacts_tag_bulk,
+            commands::contact_ids_by_tag,
             commands::custom_field_list,
             commands::custom_field_create,
+            commands::custom_field_update,
+            commands::custom_field_set_required,
+            commands::custom_field_delete,
+            commands::custom_field_reorder,
+            commands::custom_field_usage,
             commands::contact_custom_values_get,
             commands::contact_custom_values_set,
             commands::contact_ids_by_custom_value,
+            commands::contacts_by_source,
+            commands::pipeline_export,
+            commands::pipeline_report,
+            commands::company_custom_values_get,
+            commands::company_custom_values_set,
             commands::note_list,
             commands::note_create,
+            commands::note_update,
+            commands::note_delete,
+            commands::notes_with_attachments,
             commands::interaction_list,
             commands::interaction_create,
+            commands::interaction_update,
+            commands::interaction_delete,
+            commands::interaction_stats,
+            commands::contact_timeline,
+            commands::contact_snapshot,
+            commands::contact_snapshots_list,
+            commands::contact_diff_since,
             commands::reminder_list,
             commands::reminder_create,
+            commands::reminder_create_business_days,
+            commands::reminder_holidays_get,
+            commands::reminder_holidays_set,
             commands::reminder_complete,
             commands::reminder_snooze,
+            commands::reminder_update,
+            commands::reminder_delete,
+            commands::reminders_due,
+            commands::reminders_reassign,
+            commands::reminder_set_notify_before,
+            commands::reminders_stale,
+            commands::recurring_forecast,
+            commands::weekly_digest,
+            commands::contacts_stale,
+            commands::review_due,
+            commands::review_mark_done,
             commands::attachments_dir_get,
             commands::attachments_dir_set,
+            commands::backup_now,
+            commands::backup_keep_count_get,
+            commands::backup_keep_count_set,
             commands::backup_dir_get,
             commands::backup_dir_set,
             commands::sync_folder_get,
             commands::sync_folder_set,
             commands::open_from_sync_folder,
+            commands::sync_merge,
             commands::attachment_list,
+            commands::attachments_usage,
+            commands::attachments_total_size,
+            commands::attachment_max_bytes_get,
+            commands::attachment_max_bytes_set,
+            commands::attachment_quota_get,
+            commands::attachment_quota_set,
+            commands::attachment_allowed_exts_get,
+            commands::attachment_allowed_exts_set,
             commands::attachment_add,
             commands::attachment_delete,
             commands::attachment_open,
+            commands::attachments_verify,
+            commands::attachments_key_status,
+            commands::attachments_key_use_derived,
+            commands::attachments_rotate_key,
             commands::import_contacts,
+            commands::import_contacts_mapped,
+            commands::import_preview,
+            commands::import_contacts_jsonl,
+            commands::import_vcard,
+            commands::import_batch_list,
+            commands::import_batch_undo,
             commands::search_contacts,
+            commands::search_index_audit,
+            commands::search_reindex,
             commands::global_search,
+            commands::segment_create,
+            commands::segment_list,
+            commands::segment_delete,
+            commands::segment_run,
             commands::contact_ids_with_hashtag,
             commands::dedup_candidates,
+            commands::dedup_settings_get,
+            commands::dedup_ignore,
+            commands::company_dedup_candidates,
             commands::contact_merge,
+            commands::contact_merge_preview,
+            commands::contact_merges_list,
+            commands::merge_log_list,
+            commands::contact_unmerge,
+            commands::referential_audit,
+            commands::referential_repair,
+            commands::view_prefs_get,
+            commands::view_prefs_set,
             commands::write_export_file,
+            commands::segment_export_html,
+            commands::export_json,
+            commands::import_json,
+            commands::diff_exports,
             commands::get_encryption_state,
             commands::encryption_setup_create_key,
             commands::encryption_migrate_plain_db,
             commands::encryption_setup_open_db,
+            commands::lock_now,
+            commands::is_locked,
+            commands::encryption_uses_passphrase,
+            commands::auto_lock_minutes_get,
+            commands::auto_lock_minutes_set,
+            commands::app_setting_get,
+            commands::app_setting_set,
+            commands::encryption_change_passphrase,
+            commands::encryption_unlock,
+            commands::encryption_disable,
+            commands::encryption_enable,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -13,9 +13,9 @@ import { Dedup } from "@/pages/Dedup";
 import { Export } from "@/pages/Export";
 import { EncryptionSetup } from "@/pages/EncryptionSetup";
 import { Inbox } from "@/pages/Inbox";
-import { api } from "@/lib/api";
+import { api, AUTO_LOCK_CHANGED_EVENT, type SetupReason } from "@/lib/api";
 
-type EncryptionState = "loading" | "ready" | { need_setup: "first_run" | "migrate_plain" };
+type EncryptionState = "loading" | "ready" | { need_setup: SetupReason };
 
 function App() {
   const [encryptionState, setEncryptionState] = useState<EncryptionState>("loading");
@@ -33,6 +33,45 @@ function App() {
       .catch(() => setEncryptionState("ready"));
   }, []);
 
+  // Auto-lock: drop the decrypted DB after auto_lock_minutes without mouse/keyboard activity.
+  useEffect(() => {
+    if (encryptionState !== "ready") return;
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    let minutes = 0;
+    let cancelled = false;
+    const lock = () => {
+      api
+        .lockNow()
+        .then(() => setEncryptionState({ need_setup: "locked" }))
+        .catch(console.error);
+    };
+    const reset = () => {
+      if (timer) clearTimeout(timer);
+      if (minutes > 0) timer = setTimeout(lock, minutes * 60_000);
+    };
+    const loadMinutes = () => {
+      api
+        .autoLockMinutesGet()
+        .then((m) => {
+          if (cancelled) return;
+          minutes = m;
+          reset();
+        })
+        .catch(console.error);
+    };
+    const events = ["mousemove", "mousedown", "keydown", "wheel", "touchstart"] as const;
+    events.forEach((e) => window.addEventListener(e, reset, { passive: true }));
+    // Settings fires this after saving a new value.
+    window.addEventListener(AUTO_LOCK_CHANGED_EVENT, loadMinutes);
+    loadMinutes();
+    return () => {
+      cancelled = true;
+      if (timer) clearTimeout(timer);
+      events.forEach((e) => window.removeEventListener(e, reset));
+      window.removeEventListener(AUTO_LOCK_CHANGED_EVENT, loadMinutes);
+    };
+  }, [encryptionState]);
+
   if (encryptionState === "loading") {
     return (
       <div className="flex min-h-screen items-center justify-center bg-background">
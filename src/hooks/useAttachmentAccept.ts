@@ -0,0 +1,30 @@
+/**
+ * A6 — dosya seçici için `accept` değeri; attachment_allowed_exts ayarından gelir.
+ */
+import { useEffect, useState } from "react";
+import { api } from "@/lib/api";
+
+const FALLBACK_EXTS = ["pdf", "doc", "docx", "ppt", "pptx", "png", "jpg", "jpeg", "xlsx", "xls", "txt"];
+
+function toAccept(exts: string[]): string {
+  return exts.map((e) => `.${e}`).join(",");
+}
+
+export function useAttachmentAccept(): string {
+  const [accept, setAccept] = useState(toAccept(FALLBACK_EXTS));
+
+  useEffect(() => {
+    let mounted = true;
+    api
+      .attachmentAllowedExtsGet()
+      .then((exts) => {
+        if (mounted && Array.isArray(exts) && exts.length > 0) setAccept(toAccept(exts));
+      })
+      .catch(() => {});
+    return () => {
+      mounted = false;
+    };
+  }, []);
+
+  return accept;
+}
@@ -12,6 +12,13 @@ function effectiveDueAt(r: Reminder): Date {
   return new Date(r.due_at);
 }
 
+/** Snooze fires at the snooze time; otherwise notify_before_minutes ahead of due_at. */
+function notifyAt(r: Reminder): Date {
+  const due = effectiveDueAt(r);
+  if (r.snooze_until && r.snooze_until.trim()) return due;
+  return new Date(due.getTime() - (r.notify_before_minutes ?? 0) * 60_000);
+}
+
 export function useReminderNotifications() {
   const notifiedIds = useRef<Set<string>>(new Set());
 
@@ -36,8 +43,7 @@ export function useReminderNotifications() {
         if (!Array.isArray(list)) return;
         const now = new Date();
         for (const r of list) {
-          const due = effectiveDueAt(r);
-          if (due <= now && !notifiedIds.current.has(r.id)) {
+          if (notifyAt(r) <= now && !notifiedIds.current.has(r.id)) {
             notifiedIds.current.add(r.id);
             sendNotification({ title: "VaultCRM: Hatırlatıcı", body: r.title });
           }
@@ -22,8 +22,44 @@ export interface Contact {
   next_touch_at: string | null;
   created_at: string;
   updated_at: string;
+  /** Opt-out: bu kişiyle iletişime geçilmez */
+  do_not_contact: boolean;
+  /** GDPR: opt_in / opt_out / unknown */
+  consent_status: ConsentStatus | null;
+  consent_at: string | null;
+  /** IANA timezone, e.g. "Europe/Istanbul" */
+  timezone: string | null;
+  /** Manually confirmed; reset when the value is edited */
+  email_verified: boolean;
+  phone_verified: boolean;
+  /** Set while in the trash */
+  deleted_at: string | null;
+  /** Favorite; listed first in contactList */
+  is_pinned: boolean;
 }
 
+export interface ContactLocalTime {
+  timezone: string;
+  local_time: string;
+  utc_offset_minutes: number;
+}
+
+export type ContactSortBy = "updated_at" | "created_at" | "last_touched_at" | "last_name" | "first_name";
+
+export interface ContactListParams {
+  limit?: number;
+  offset?: number;
+  sort_by?: ContactSortBy;
+  sort_dir?: "asc" | "desc";
+}
+
+export interface ContactListPage {
+  items: Contact[];
+  total: number;
+}
+
+export type ConsentStatus = "opt_in" | "opt_out" | "unknown";
+
 export interface CreateContactInput {
   first_name: string;
   last_name: string;
@@ -50,6 +86,10 @@ export interface Company {
   domain: string | null;
   industry: string | null;
   notes: string | null;
+  headcount: number | null;
+  hq_location: string | null;
+  funding_stage: string | null;
+  is_pinned: boolean;
   created_at: string;
   updated_at: string;
 }
@@ -59,6 +99,10 @@ export interface CreateCompanyInput {
   domain?: string | null;
   industry?: string | null;
   notes?: string | null;
+  headcount?: number | null;
+  hq_location?: string | null;
+  /** Must be one of fundingStageOptionsGet() */
+  funding_stage?: string | null;
 }
 
 export interface UpdateCompanyInput {
@@ -66,6 +110,46 @@ export interface UpdateCompanyInput {
   domain?: string | null;
   industry?: string | null;
   notes?: string | null;
+  headcount?: number | null;
+  hq_location?: string | null;
+  /** Must be one of fundingStageOptionsGet() */
+  funding_stage?: string | null;
+}
+
+export interface AttachmentQuota {
+  max_count: number | null;
+  max_bytes: number | null;
+}
+
+export interface AttachmentUsage {
+  count: number;
+  total_bytes: number;
+  quota: AttachmentQuota;
+}
+
+export type SetupReason = "first_run" | "migrate_plain" | "wrong_passphrase" | "locked";
+
+/** Error strings from the backend: re-prompt for the passphrase vs. damaged encrypted file */
+export const ERR_WRONG_PASSPHRASE = "Parola hatalı";
+export const ERR_CORRUPTED_FILE = "Şifreli dosya bozuk";
+/** Mirrors UI_SETTING_KEYS in commands.rs */
+export type UiSettingKey =
+  | "theme"
+  | "locale"
+  | "date_format"
+  | "time_format"
+  | "week_start"
+  | "default_contact_sort"
+  | "sidebar_collapsed";
+
+/** Window event fired after autoLockMinutesSet so the idle timer picks up the new value */
+export const AUTO_LOCK_CHANGED_EVENT = "vault:auto-lock-changed";
+/** Must be typed verbatim to disable encryption (developer mode) */
+export const PLAINTEXT_CONFIRM = "DISABLE ENCRYPTION";
+
+export interface NoteWithAttachments {
+  note: Note;
+  attachment_names: string[];
 }
 
 export interface Note {
@@ -85,6 +169,13 @@ export interface CreateNoteInput {
   body: string;
 }
 
+export interface InteractionStatsBucket {
+  /** YYYY-MM-DD (day, Monday for week) or YYYY-MM (month) */
+  bucket: string;
+  total: number;
+  by_kind: Record<string, number>;
+}
+
 export interface Interaction {
   id: string;
   contact_id: string;
@@ -94,6 +185,31 @@ export interface Interaction {
   created_at: string;
 }
 
+/** Unified contact timeline entry; `at` is the sort key, newest first. */
+export interface TimelineEntry {
+  type: "note" | "interaction" | "reminder";
+  id: string;
+  /** note created_at, interaction happened_at, reminder completed_at */
+  at: string;
+  title: string;
+  snippet: string | null;
+}
+
+export interface ContactSnapshotInfo {
+  id: string;
+  contact_id: string;
+  created_at: string;
+}
+
+export interface ContactDiff {
+  snapshot_id: string;
+  snapshot_at: string;
+  /** Contact fields that changed (updated_at skipped) */
+  fields: { field: string; before: unknown; after: unknown }[];
+  /** Related rows per table; keys are row ids (field_id / tag_id for custom values / tags) */
+  related: { table: string; added: string[]; removed: string[]; changed: string[] }[];
+}
+
 export interface CreateInteractionInput {
   contact_id: string;
   kind: string;
@@ -110,15 +226,22 @@ export interface Reminder {
   snooze_until: string | null;
   recurring_days: number | null;
   completed_at: string | null;
+  /** D1.1: Bildirim due_at'ten bu kadar dakika önce */
+  notify_before_minutes: number;
   created_at: string;
+  /** Sadece reminderCreate: örn. kişi "do not contact" */
+  warning?: string;
 }
 
+export type ReminderWindow = "overdue" | "today" | "this_week" | { from: string; to: string };
+
 export interface CreateReminderInput {
   contact_id: string;
   note_id?: string | null;
   title: string;
   due_at: string;
   recurring_days?: number | null;
+  notify_before_minutes?: number | null;
 }
 
 /** C2.1 — Global arama sonucu */
@@ -130,12 +253,74 @@ export interface GlobalSearchNoteHit {
   created_at: string;
 }
 
+export interface GlobalSearchFilters {
+  tag_id?: string | null;
+  company_id?: string | null;
+  city?: string | null;
+  country?: string | null;
+  /** cf_stage value */
+  stage?: string | null;
+}
+
+export interface Segment {
+  id: string;
+  name: string;
+  query: string;
+  filters: GlobalSearchFilters;
+  created_at: string;
+}
+
+export interface SegmentCreateInput {
+  name: string;
+  query?: string | null;
+  filters?: GlobalSearchFilters | null;
+}
+
 export interface GlobalSearchResult {
   contacts: Contact[];
   companies: Company[];
   note_hits: GlobalSearchNoteHit[];
 }
 
+/** D1.4 — Tekrarlayan hatırlatıcı projeksiyonu (DB'ye yazılmaz) */
+export interface RecurringOccurrence {
+  reminder_id: string;
+  contact_id: string;
+  title: string;
+  due_at: string;
+  recurring_days: number;
+}
+
+/** D2.2 — Haftalık review özeti */
+export interface UpcomingDate {
+  contact_id: string;
+  contact_name: string;
+  field_id: string;
+  field_name: string;
+  value: string;
+  next_occurrence: string;
+}
+
+export interface WeeklyDigest {
+  overdue_reminders: Reminder[];
+  going_cold: Contact[];
+  upcoming_dates: UpcomingDate[];
+  new_contacts: Contact[];
+  recent_interactions: Interaction[];
+}
+
+export interface StaleContact extends Contact {
+  /** null = never touched */
+  days_since_touch: number | null;
+}
+
+export interface ReviewStatus {
+  next_review_at: string | null;
+  cadence_days: number;
+  due: boolean;
+  digest: WeeklyDigest;
+}
+
 export interface ImportRow {
   first_name?: string | null;
   last_name?: string | null;
@@ -144,18 +329,133 @@ export interface ImportRow {
   city?: string | null;
   country?: string | null;
   email?: string | null;
+  email_secondary?: string | null;
   phone?: string | null;
+  phone_secondary?: string | null;
   linkedin_url?: string | null;
+  twitter_url?: string | null;
   website?: string | null;
 }
 
+export type ImportPreviewRow = { row_index: number } & (
+  | { status: "new" }
+  | {
+      status: "duplicate_of";
+      /** Existing contact; null when the match is an earlier new row of the same file */
+      contact_id: string | null;
+      earlier_row: number | null;
+      matched_by: "email" | "phone" | "name";
+    }
+  | { status: "invalid"; reason: string }
+);
+
+/** What import_contacts does with rows matching an existing contact (email, then phone) */
+export type ImportMode = "create_all" | "skip_duplicates" | "update_existing";
+
+export interface ImportResult {
+  inserted: number;
+  /** Existing contacts whose empty fields were filled (update_existing) */
+  updated: number;
+  /** row_index is 0-based into the rows sent */
+  skipped: { row_index: number; reason: string }[];
+  /** null when nothing was inserted */
+  batch_id: string | null;
+  /** Company cards created for unknown company names */
+  companies_created: number;
+}
+
+/** Every given criterion must match; trashed contacts are always excluded */
+export interface SegmentFilter {
+  contact_ids?: string[] | null;
+  tag_id?: string | null;
+  company_id?: string | null;
+  city?: string | null;
+  country?: string | null;
+  /** Pipeline stage value */
+  stage?: string | null;
+  /** Full-text query, as in globalSearch */
+  query?: string | null;
+  /** Saved segment whose query and filters are used instead */
+  segment_id?: string | null;
+  exclude_dnc?: boolean;
+  /** Page heading; defaults to the saved segment's name, then "Kişi listesi" */
+  title?: string | null;
+}
+
+export interface JsonImportCount {
+  table: string;
+  inserted: number;
+  /** rows that already existed */
+  ignored: number;
+}
+
+export interface FieldChange {
+  field: string;
+  before: unknown;
+  after: unknown;
+}
+
+export interface TableDiff {
+  table: string;
+  /** ids only in b */
+  added: string[];
+  /** ids only in a */
+  removed: string[];
+  changed: { id: string; fields: FieldChange[] }[];
+}
+
+export interface ImportBatch {
+  id: string;
+  source: string | null;
+  created_at: string;
+  row_count: number;
+  /** contacts from the batch that are not in the trash */
+  active_count: number;
+  undone_at: string | null;
+}
+
+export interface ImportProgress {
+  lines_read: number;
+  inserted: number;
+  skipped: number;
+  done: boolean;
+}
+
+export interface Tag {
+  id: string;
+  name: string;
+  color: string | null;
+  created_at: string;
+}
+
 export interface CustomField {
   id: string;
   name: string;
   kind: string;
   options: string | null;
   sort_order: number;
+  scope: "contact" | "company";
   created_at: string;
+  /** Counts towards contact completeness */
+  required: boolean;
+}
+
+export interface Completeness {
+  /** 0–100 */
+  score: number;
+  missing: string[];
+}
+
+export interface ContactCompleteness extends Completeness {
+  contact: Contact;
+}
+
+export interface CustomFieldUsage {
+  field: CustomField;
+  /** Records with a non-empty value */
+  used_count: number;
+  /** Contacts (or companies, for company fields) the field applies to */
+  total_count: number;
 }
 
 export interface CustomValue {
@@ -166,10 +466,23 @@ export interface CustomValue {
   value: string | null;
 }
 
+export interface SourceCount {
+  source: string;
+  count: number;
+}
+
+export interface SourceContacts {
+  contacts: Contact[];
+  counts: SourceCount[];
+}
+
 export interface CreateCustomFieldInput {
   name: string;
   kind: string;
   options?: string | null;
+  /** Defaults to "contact" */
+  scope?: "contact" | "company" | null;
+  required?: boolean;
 }
 
 export interface CustomValueInput {
@@ -177,6 +490,8 @@ export interface CustomValueInput {
   value?: string | null;
 }
 
+export type AttachmentOwnerType = "contact" | "company" | "note" | "interaction";
+
 export interface Attachment {
   id: string;
   owner_type: string;
@@ -189,17 +504,161 @@ export interface Attachment {
 }
 
 export interface AttachmentCreateInput {
-  owner_type: "contact" | "company";
+  owner_type: AttachmentOwnerType;
   owner_id: string;
   file_name: string;
   mime?: string | null;
   bytes: number[];
 }
 
+/** G1.3 — sync_merge sonucu */
+export interface SyncMergeSummary {
+  inserted: number;
+  updated: number;
+  conflicts: number;
+}
+
+/** A6.3 — Attachment bütünlük taraması */
+export interface AttachmentVerifyReport {
+  healthy: number;
+  failed_ids: string[];
+}
+
+export interface AttachmentsKeyStatus {
+  source: "stored" | "derived";
+  exists: boolean;
+  /** key is kept base64 in app_settings, inside the DB */
+  stored_in_db: boolean;
+}
+
+export interface AttachmentsRotateReport {
+  total: number;
+  reencrypted: number;
+  /** missing or unreadable files, left untouched */
+  skipped: number;
+}
+
+export interface PipelineEntry {
+  contact: Contact;
+  next_action: string | null;
+  next_action_at: string | null;
+}
+
+export interface PipelineCount {
+  /** null = no value */
+  option: string | null;
+  count: number;
+}
+
+export interface PipelineStage {
+  stage: string;
+  contacts: PipelineEntry[];
+}
+
+export interface CompanySuggestion {
+  company: Company;
+  distance: number;
+}
+
+export interface SearchIndexAudit {
+  contacts_count: number;
+  indexed_count: number;
+  missing_ids: string[];
+  orphan_rowids: number[];
+  content_mismatch: boolean;
+  needs_reindex: boolean;
+}
+
+export interface OrphanReport {
+  table: string;
+  count: number;
+  sample_ids: string[];
+}
+
+export type ViewPrefsView =
+  | "contacts"
+  | "companies"
+  | "inbox"
+  | "dashboard"
+  | "contact_detail"
+  | "company_detail";
+
+export interface ContactNoteVolume {
+  contact: Contact;
+  note_count: number;
+  total_chars: number;
+  latest_note_snippet: string | null;
+}
+
+export interface SimilarContact {
+  contact: Contact;
+  shared_tags: number;
+  same_company: boolean;
+}
+
+export interface ClearHistoryInclude {
+  notes?: boolean;
+  interactions?: boolean;
+  reminders?: boolean;
+}
+
+/** Rows removed per table */
+export interface ClearHistoryResult {
+  notes: number;
+  interactions: number;
+  reminders: number;
+}
+
+export interface CanonicalizeResult {
+  scanned: number;
+  changed: number;
+}
+
 export interface DedupCandidate {
   a: Contact;
   b: Contact;
   reasons: string[];
+  /** 0..1; list is sorted highest first */
+  confidence: number;
+}
+
+export interface DedupSettings {
+  /** 0.5..1, default 0.85 */
+  name_threshold?: number | null;
+  /** "phonetic" reason for similar-sounding names, default true */
+  phonetic?: boolean | null;
+}
+
+export interface CompanyDedupCandidate {
+  a: Company;
+  b: Company;
+  /** "name" and/or "domain" */
+  reasons: string[];
+  confidence: number;
+}
+
+export interface ContactMergeInfo {
+  id: string;
+  primary_id: string;
+  secondary_id: string;
+  secondary_name: string;
+  merged_at: string;
+  undone_at: string | null;
+}
+
+export interface MergeLogEntry {
+  id: string;
+  primary_id: string;
+  /** empty if the kept contact no longer exists */
+  primary_name: string;
+  secondary_id: string;
+  /** absorbed contact row as it was before the merge */
+  secondary: Record<string, unknown>;
+  notes: number;
+  reminders: number;
+  interactions: number;
+  merged_at: string;
+  undone_at: string | null;
 }
 
 export interface MergeContactInput {
@@ -209,69 +668,373 @@ export interface MergeContactInput {
   custom_values?: CustomValueInput[];
 }
 
+export interface MergeFieldPreview {
+  field: string;
+  primary: string | null;
+  secondary: string | null;
+  /** primary's value, else the secondary's */
+  proposed: string | null;
+  conflict: boolean;
+}
+
+/** contactMergePreview: nothing is written */
+export interface MergePreview {
+  fields: MergeFieldPreview[];
+  last_touched_at: string | null;
+  next_touch_at: string | null;
+  notes: number;
+  reminders: number;
+  interactions: number;
+  /** files on the secondary and its notes and interactions */
+  attachments: number;
+  tag_ids: string[];
+  custom_values: CustomValueInput[];
+}
+
 export const api = {
-  contactList: () => invoke<Contact[]>("contact_list"),
+  contactList: () => invoke<ContactListPage>("contact_list").then((page) => page.items),
+  contactListPage: (params?: ContactListParams) =>
+    invoke<ContactListPage>("contact_list", { params: params ?? null }),
   contactGet: (id: string) => invoke<Contact | null>("contact_get", { id }),
   contactCreate: (input: CreateContactInput) =>
     invoke<Contact>("contact_create", { input }),
   contactUpdate: (id: string, input: CreateContactInput) =>
     invoke<Contact>("contact_update", { id, input }),
+  /** Moves to trash; see contactRestore / contactPurge */
   contactDelete: (id: string) => invoke<void>("contact_delete", { id }),
+  contactRestore: (id: string) => invoke<Contact>("contact_restore", { id }),
+  contactListTrashed: () => invoke<Contact[]>("contact_list_trashed"),
+  /** Permanent: removes the contact, its notes/reminders/interactions and attachment files */
+  contactPurge: (id: string) => invoke<void>("contact_purge", { id }),
+  contactClearHistory: (contactId: string, include: ClearHistoryInclude) =>
+    invoke<ClearHistoryResult>("contact_clear_history", { contactId, include }),
+  /** Purges contacts trashed at least olderThanDays ago (omit for the whole trash); returns count */
+  trashEmpty: (olderThanDays?: number) =>
+    invoke<number>("trash_empty", { olderThanDays: olderThanDays ?? null }),
+  contactSetDnc: (id: string, flag: boolean) =>
+    invoke<void>("contact_set_dnc", { id, flag }),
+  contactSetConsent: (id: string, status: ConsentStatus, consentAt?: string | null) =>
+    invoke<Contact>("contact_set_consent", { id, status, consentAt: consentAt ?? null }),
+  contactsByConsent: (status: ConsentStatus) =>
+    invoke<Contact[]>("contacts_by_consent", { status }),
+  contactSetVerified: (id: string, field: "email" | "phone", verified: boolean) =>
+    invoke<void>("contact_set_verified", { id, field, verified }),
+  /** Contacts with a non-empty, unverified email/phone */
+  contactsUnverified: (field: "email" | "phone") =>
+    invoke<Contact[]>("contacts_unverified", { field }),
+  completeness: (contactId: string) => invoke<Completeness>("completeness", { contactId }),
+  /** ascending (default) lists the most incomplete first */
+  contactsByCompleteness: (ascending = true, limit?: number | null) =>
+    invoke<ContactCompleteness[]>("contacts_by_completeness", { ascending, limit: limit ?? null }),
+  contactSetTimezone: (id: string, timezone: string | null) =>
+    invoke<void>("contact_set_timezone", { id, timezone }),
+  /** null leaves the field unchanged, "" clears it; returns the number updated */
+  contactsBulkSetLocation: (ids: string[], city: string | null, country: string | null) =>
+    invoke<number>("contacts_bulk_set_location", { ids, city, country }),
+  /** Moves contacts to the trash; returns how many were trashed */
+  contactsDeleteBulk: (ids: string[]) => invoke<number>("contacts_delete_bulk", { ids }),
+  /** null unlinks the contacts from their company */
+  contactsSetCompanyBulk: (ids: string[], companyId: string | null) =>
+    invoke<number>("contacts_set_company_bulk", { ids, companyId }),
+  contactsLocalTime: (contactId: string) =>
+    invoke<ContactLocalTime | null>("contacts_local_time", { contactId }),
+  /** vCard 3.0 text for rendering a QR code */
+  contactQrVcard: (id: string) => invoke<string>("contact_qr_vcard", { id }),
+  /** contactIds omitted/null exports every contact not in the trash */
+  exportVcard: (contactIds?: string[] | null) =>
+    invoke<string>("export_vcard", { contactIds: contactIds ?? null }),
+  /** Empty ids exports every contact not in the trash. */
+  contactsExportVcards: (ids: string[] = []) => invoke<string>("contacts_export_vcards", { ids }),
+  /** CSV with the ImportRow columns, so it imports back unchanged */
+  exportCsv: (contactIds?: string[] | null) =>
+    invoke<string>("export_csv", { contactIds: contactIds ?? null }),
+  /** "Benzer kişiler": ortak tag sayısı, sonra aynı şirket */
+  contactsSimilarTo: (contactId: string, limit?: number | null) =>
+    invoke<SimilarContact[]>("contacts_similar_to", { contactId, limit: limit ?? null }),
+  contactsByNoteVolume: (limit?: number) =>
+    invoke<ContactNoteVolume[]>("contacts_by_note_volume", { limit: limit ?? null }),
+  /** A5.1: Email/telefonları kanonik forma getir (tek seferlik bakım) */
+  contactsCanonicalize: () => invoke<CanonicalizeResult>("contacts_canonicalize"),
+  /** Refresh cached company names from linked companies; returns number of contacts updated */
+  contactsResolveCompanyNames: () => invoke<number>("contacts_resolve_company_names"),
+  /** Telefonda kısmi rakam araması (format bağımsız, en az 3 rakam) */
+  contactsFindByPhonePartial: (digits: string) =>
+    invoke<Contact[]>("contacts_find_by_phone_partial", { digits }),
+  contactsFindByPhone: (phone: string) => invoke<Contact[]>("contacts_find_by_phone", { phone }),
+  /** Exact email match (case-insensitive, primary or secondary) */
+  contactByEmail: (email: string) => invoke<Contact[]>("contact_by_email", { email }),
+  /** Incoming-call match (primary or secondary phone); national and +country forms match */
+  contactByPhone: (phone: string) => invoke<Contact[]>("contact_by_phone", { phone }),
+  defaultCountryCodeGet: () => invoke<string | null>("default_country_code_get"),
+  defaultCountryCodeSet: (code: string) => invoke<void>("default_country_code_set", { code }),
   companyList: () => invoke<Company[]>("company_list"),
   companyGet: (id: string) => invoke<Company | null>("company_get", { id }),
+  companyDidYouMean: (name: string, maxDistance?: number) =>
+    invoke<CompanySuggestion[]>("company_did_you_mean", { name, maxDistance: maxDistance ?? null }),
   companyCreate: (input: CreateCompanyInput) =>
     invoke<Company>("company_create", { input }),
   companyUpdate: (id: string, input: UpdateCompanyInput) =>
     invoke<Company>("company_update", { id, input }),
+  /** Moves contacts, custom values and attachments to primaryId and deletes secondaryId */
+  companyMerge: (primaryId: string, secondaryId: string, merged: UpdateCompanyInput) =>
+    invoke<Company>("company_merge", { primaryId, secondaryId, merged }),
+  companySetPinned: (id: string, pinned: boolean) =>
+    invoke<void>("company_set_pinned", { id, pinned }),
+  contactSetPinned: (id: string, pinned: boolean) =>
+    invoke<void>("contact_set_pinned", { id, pinned }),
+  /** Pinned contacts by name, for the sidebar */
+  contactsPinned: () => invoke<Contact[]>("contacts_pinned"),
+  fundingStageOptionsGet: () => invoke<string[]>("funding_stage_options_get"),
+  fundingStageOptionsSet: (options: string[]) =>
+    invoke<void>("funding_stage_options_set", { options }),
   contactListByCompany: (companyId: string) =>
     invoke<Contact[]>("contact_list_by_company", { companyId }),
-  customFieldList: () => invoke<CustomField[]>("custom_field_list"),
+  /** Ordered by company, then name; each contact's company/company_id labels its group */
+  contactListByCompanies: (companyIds: string[]) =>
+    invoke<Contact[]>("contact_list_by_companies", { companyIds }),
+  tagList: () => invoke<Tag[]>("tag_list"),
+  tagCreate: (name: string, color?: string | null) =>
+    invoke<Tag>("tag_create", { name, color: color ?? null }),
+  /** null/undefined leaves a field unchanged; empty color clears it */
+  tagUpdate: (id: string, name?: string | null, color?: string | null) =>
+    invoke<Tag>("tag_update", { id, name: name ?? null, color: color ?? null }),
+  tagDelete: (id: string) => invoke<void>("tag_delete", { id }),
+  contactTagsGet: (contactId: string) => invoke<Tag[]>("contact_tags_get", { contactId }),
+  contactTagAdd: (contactId: string, tagId: string) =>
+    invoke<void>("contact_tag_add", { contactId, tagId }),
+  contactTagRemove: (contactId: string, tagId: string) =>
+    invoke<void>("contact_tag_remove", { contactId, tagId }),
+  /** Returns the number of contacts whose tags changed */
+  contactsTagBulk: (contactIds: string[], tagId: string, mode: "add" | "remove") =>
+    invoke<number>("contacts_tag_bulk", { contactIds, tagId, mode }),
+  contactIdsByTag: (tagId: string) => invoke<string[]>("contact_ids_by_tag", { tagId }),
+  customFieldList: (scope?: "contact" | "company" | null) =>
+    invoke<CustomField[]>("custom_field_list", { scope: scope ?? null }),
   customFieldCreate: (input: CreateCustomFieldInput) =>
     invoke<CustomField>("custom_field_create", { input }),
+  /** options: JSON array string, e.g. '["A","B"]'; null clears */
+  customFieldUpdate: (id: string, name: string, options: string | null) =>
+    invoke<CustomField>("custom_field_update", { id, name, options }),
+  customFieldSetRequired: (id: string, required: boolean) =>
+    invoke<void>("custom_field_set_required", { id, required }),
+  /** Also deletes every stored value of the field */
+  customFieldDelete: (id: string) => invoke<void>("custom_field_delete", { id }),
+  customFieldReorder: (orderedIds: string[]) =>
+    invoke<void>("custom_field_reorder", { orderedIds }),
+  /** Least used first */
+  customFieldUsage: () => invoke<CustomFieldUsage[]>("custom_field_usage"),
   contactCustomValuesGet: (contactId: string) =>
     invoke<CustomValue[]>("contact_custom_values_get", { contactId }),
   contactCustomValuesSet: (contactId: string, values: CustomValueInput[]) =>
     invoke<void>("contact_custom_values_set", { contactId, values }),
+  pipelineExport: (stage?: string | null) =>
+    invoke<PipelineStage[]>("pipeline_export", { stage: stage ?? null }),
+  /** Contact counts per option of a single-select field (default cf_stage), plus a null bucket */
+  pipelineReport: (fieldId?: string | null) =>
+    invoke<PipelineCount[]>("pipeline_report", { fieldId: fieldId ?? null }),
   contactIdsByCustomValue: (fieldId: string, value: string) =>
     invoke<string[]>("contact_ids_by_custom_value", { fieldId, value }),
+  contactsBySource: (source: string) =>
+    invoke<SourceContacts>("contacts_by_source", { source }),
+  companyCustomValuesGet: (companyId: string) =>
+    invoke<CustomValue[]>("company_custom_values_get", { companyId }),
+  companyCustomValuesSet: (companyId: string, values: CustomValueInput[]) =>
+    invoke<void>("company_custom_values_set", { companyId, values }),
   noteList: (contactId: string) => invoke<Note[]>("note_list", { contactId }),
   noteCreate: (input: CreateNoteInput) => invoke<Note>("note_create", { input }),
+  noteUpdate: (id: string, body: string, title?: string | null, kind?: string | null) =>
+    invoke<Note>("note_update", { id, title: title ?? null, body, kind: kind ?? null }),
+  noteDelete: (id: string) => invoke<void>("note_delete", { id }),
+  notesWithAttachments: (contactId?: string | null) =>
+    invoke<NoteWithAttachments[]>("notes_with_attachments", { contactId: contactId ?? null }),
   interactionList: (contactId: string) =>
     invoke<Interaction[]>("interaction_list", { contactId }),
   interactionCreate: (input: CreateInteractionInput) =>
     invoke<Interaction>("interaction_create", { input }),
-  reminderList: () => invoke<Reminder[]>("reminder_list"),
+  /** Both recompute the contact's last_touched_at from remaining interactions, notes and completed reminders */
+  interactionUpdate: (id: string, kind: string, happenedAt: string, summary: string | null) =>
+    invoke<Interaction>("interaction_update", { id, kind, happenedAt, summary }),
+  interactionDelete: (id: string) => invoke<void>("interaction_delete", { id }),
+  /** Counts per bucket and kind between from and to (inclusive dates) */
+  interactionStats: (from: string, to: string, bucket: "day" | "week" | "month") =>
+    invoke<InteractionStatsBucket[]>("interaction_stats", { from, to, bucket }),
+  /** Newest first; pass the last entry's `at` and `id` as `before` / `beforeId` for the next page */
+  contactTimeline: (
+    contactId: string,
+    limit?: number | null,
+    before?: string | null,
+    beforeId?: string | null
+  ) =>
+    invoke<TimelineEntry[]>("contact_timeline", {
+      contactId,
+      limit: limit ?? null,
+      before: before ?? null,
+      beforeId: beforeId ?? null,
+    }),
+  contactSnapshot: (contactId: string) =>
+    invoke<ContactSnapshotInfo>("contact_snapshot", { contactId }),
+  contactSnapshotsList: (contactId: string) =>
+    invoke<ContactSnapshotInfo[]>("contact_snapshots_list", { contactId }),
+  contactDiffSince: (contactId: string, snapshotId: string) =>
+    invoke<ContactDiff>("contact_diff_since", { contactId, snapshotId }),
+  reminderList: (excludeDnc?: boolean) =>
+    invoke<Reminder[]>("reminder_list", { excludeDnc: excludeDnc ?? null }),
   reminderCreate: (input: CreateReminderInput) =>
     invoke<Reminder>("reminder_create", { input }),
-  reminderComplete: (id: string) => invoke<void>("reminder_complete", { id }),
+  reminderCreateBusinessDays: (contactId: string, title: string, businessDays: number) =>
+    invoke<Reminder>("reminder_create_business_days", { contactId, title, businessDays }),
+  reminderHolidaysGet: () => invoke<string[]>("reminder_holidays_get"),
+  reminderHolidaysSet: (dates: string[]) => invoke<void>("reminder_holidays_set", { dates }),
+  reminderComplete: (id: string, completedAt?: string | null) =>
+    invoke<void>("reminder_complete", { id, completedAt: completedAt ?? null }),
   reminderSnooze: (id: string, until: string) =>
     invoke<void>("reminder_snooze", { id, until }),
+  /** recurringDays null makes the reminder one-off */
+  reminderUpdate: (id: string, title: string, dueAt: string, recurringDays: number | null) =>
+    invoke<Reminder>("reminder_update", { id, title, dueAt, recurringDays }),
+  reminderDelete: (id: string) => invoke<void>("reminder_delete", { id }),
+  /** Open reminders by effective due date (snooze_until when snoozed); this_week = today + 6 days (UTC) */
+  remindersDue: (window: ReminderWindow) => invoke<Reminder[]>("reminders_due", { window }),
+  remindersReassign: (fromContactId: string, toContactId: string) =>
+    invoke<number>("reminders_reassign", { fromContactId, toContactId }),
+  reminderSetNotifyBefore: (id: string, minutes: number) =>
+    invoke<void>("reminder_set_notify_before", { id, minutes }),
+  /** D2.2: Uncompleted reminders due more than `days` days ago (cleanup) */
+  remindersStale: (days: number) =>
+    invoke<Reminder[]>("reminders_stale", { days }),
+  /** D1.4: Projected recurring occurrences for the next `days` days */
+  recurringForecast: (days: number) =>
+    invoke<RecurringOccurrence[]>("recurring_forecast", { days }),
+  /** D2.2: Weekly review digest; coldDays defaults to 30 */
+  weeklyDigest: (coldDays?: number | null, excludeDnc?: boolean) =>
+    invoke<WeeklyDigest>("weekly_digest", { coldDays: coldDays ?? null, excludeDnc: excludeDnc ?? null }),
+  /** Contacts untouched for more than `days` days (never-touched first), optionally with one tag */
+  contactsStale: (days: number, tagId?: string | null) =>
+    invoke<StaleContact[]>("contacts_stale", { days, tagId: tagId ?? null }),
+  reviewDue: (coldDays?: number | null, excludeDnc?: boolean) =>
+    invoke<ReviewStatus>("review_due", { coldDays: coldDays ?? null, excludeDnc: excludeDnc ?? null }),
+  reviewMarkDone: (cadenceDays?: number | null) =>
+    invoke<string>("review_mark_done", { cadenceDays: cadenceDays ?? null }),
   attachmentsDirGet: () => invoke<string>("attachments_dir_get"),
   attachmentsDirSet: (path: string) =>
     invoke<void>("attachments_dir_set", { path }),
-  attachmentList: (ownerType: "contact" | "company", ownerId: string) =>
+  attachmentList: (ownerType: AttachmentOwnerType, ownerId: string) =>
     invoke<Attachment[]>("attachment_list", { ownerType, ownerId }),
+  /** Without an owner, usage across the whole vault */
+  attachmentsUsage: (ownerType?: AttachmentOwnerType, ownerId?: string) =>
+    invoke<AttachmentUsage>("attachments_usage", { ownerType: ownerType ?? null, ownerId: ownerId ?? null }),
+  attachmentsTotalSize: (ownerType: AttachmentOwnerType, ownerId: string) =>
+    invoke<number>("attachments_total_size", { ownerType, ownerId }),
+  attachmentMaxBytesGet: () => invoke<number>("attachment_max_bytes_get"),
+  /** null or 0 restores the 25 MB default */
+  attachmentMaxBytesSet: (maxBytes: number | null) =>
+    invoke<number>("attachment_max_bytes_set", { maxBytes }),
+  attachmentQuotaGet: () => invoke<AttachmentQuota>("attachment_quota_get"),
+  /** null or 0 removes a limit */
+  attachmentQuotaSet: (quota: AttachmentQuota) => invoke<void>("attachment_quota_set", { quota }),
+  attachmentAllowedExtsGet: () => invoke<string[]>("attachment_allowed_exts_get"),
+  /** Extensions without the dot; an empty list restores the defaults. Executables are rejected. */
+  attachmentAllowedExtsSet: (exts: string[]) =>
+    invoke<string[]>("attachment_allowed_exts_set", { exts }),
   attachmentAdd: (input: AttachmentCreateInput) =>
     invoke<Attachment>("attachment_add", { input }),
   attachmentDelete: (id: string) => invoke<void>("attachment_delete", { id }),
   attachmentOpen: (id: string) => invoke<string>("attachment_open", { id }),
-  importContacts: (rows: ImportRow[]) =>
-    invoke<number>("import_contacts", { rows }),
-  searchContacts: (q: string) => invoke<string[]>("search_contacts", { q }),
-  globalSearch: (q: string) =>
-    invoke<GlobalSearchResult>("global_search", { q }),
+  attachmentsVerify: () => invoke<AttachmentVerifyReport>("attachments_verify"),
+  attachmentsKeyStatus: () => invoke<AttachmentsKeyStatus>("attachments_key_status"),
+  /** Derives the attachments key from the master key; returns how many files were re-encrypted */
+  attachmentsKeyUseDerived: () => invoke<number>("attachments_key_use_derived"),
+  /** New random attachments key (stored mode only); every file is re-encrypted */
+  attachmentsRotateKey: () => invoke<AttachmentsRotateReport>("attachments_rotate_key"),
+  /** Dry run: which rows are new, duplicates of existing contacts, or invalid */
+  importPreview: (rows: ImportRow[]) => invoke<ImportPreviewRow[]>("import_preview", { rows }),
+  /** linkCompanies (default true) links rows to companies by name, creating missing ones */
+  importContacts: (
+    rows: ImportRow[],
+    source?: string | null,
+    mode?: ImportMode,
+    linkCompanies?: boolean
+  ) =>
+    invoke<ImportResult>("import_contacts", {
+      rows,
+      source: source ?? null,
+      mode: mode ?? null,
+      linkCompanies: linkCompanies ?? null,
+    }),
+  /** Raw CSV rows; columnMap sends each header to an ImportRow field or "custom:<field_id>" */
+  importContactsMapped: (
+    rows: Record<string, string>[],
+    columnMap: Record<string, string>,
+    source?: string | null,
+    mode?: ImportMode,
+    linkCompanies?: boolean
+  ) =>
+    invoke<ImportResult>("import_contacts_mapped", {
+      rows,
+      columnMap,
+      source: source ?? null,
+      mode: mode ?? null,
+      linkCompanies: linkCompanies ?? null,
+    }),
+  /** Streams a JSONL file (one ImportRow per line); listen to "import-progress" for ImportProgress */
+  importContactsJsonl: (path: string) => invoke<number>("import_contacts_jsonl", { path }),
+  /** Parses a .vcf (one or more cards); row_index in skipped is the card index */
+  importVcard: (content: string) => invoke<ImportResult>("import_vcard", { content }),
+  importBatchList: () => invoke<ImportBatch[]>("import_batch_list"),
+  /** Moves the batch's contacts to the trash; returns how many */
+  importBatchUndo: (batchId: string) => invoke<number>("import_batch_undo", { batchId }),
+  /** matchMode: "all" (default) requires every term, "any" matches either */
+  searchContacts: (q: string, matchMode?: "all" | "any") =>
+    invoke<string[]>("search_contacts", { q, matchMode: matchMode ?? null }),
+  searchIndexAudit: () => invoke<SearchIndexAudit>("search_index_audit"),
+  searchReindex: () => invoke<void>("search_reindex"),
+  /** With filters and an empty q, returns every matching contact */
+  globalSearch: (q: string, filters?: GlobalSearchFilters | null) =>
+    invoke<GlobalSearchResult>("global_search", { q, filters: filters ?? null }),
+  segmentCreate: (input: SegmentCreateInput) => invoke<Segment>("segment_create", { input }),
+  segmentList: () => invoke<Segment[]>("segment_list"),
+  segmentDelete: (id: string) => invoke<void>("segment_delete", { id }),
+  /** Re-runs the saved query + filters; returns every matching contact */
+  segmentRun: (id: string) => invoke<GlobalSearchResult>("segment_run", { id }),
   contactIdsWithHashtag: (hashtag: string) =>
     invoke<string[]>("contact_ids_with_hashtag", { hashtag }),
-  dedupCandidates: () => invoke<DedupCandidate[]>("dedup_candidates"),
+  /** options.name_threshold is saved for later calls */
+  dedupCandidates: (options?: DedupSettings) =>
+    invoke<DedupCandidate[]>("dedup_candidates", { options: options ?? null }),
+  dedupSettingsGet: () => invoke<DedupSettings>("dedup_settings_get"),
+  dedupIgnore: (aId: string, bId: string) => invoke<void>("dedup_ignore", { aId, bId }),
+  companyDedupCandidates: () => invoke<CompanyDedupCandidate[]>("company_dedup_candidates"),
   contactMerge: (input: MergeContactInput) =>
     invoke<Contact>("contact_merge", { input }),
+  contactMergePreview: (primaryId: string, secondaryId: string) =>
+    invoke<MergePreview>("contact_merge_preview", { primaryId, secondaryId }),
+  contactMergesList: (contactId: string) =>
+    invoke<ContactMergeInfo[]>("contact_merges_list", { contactId }),
+  mergeLogList: (limit?: number) => invoke<MergeLogEntry[]>("merge_log_list", { limit: limit ?? null }),
+  /** Recreates the merged-away contact; returns it */
+  contactUnmerge: (mergeId: string) => invoke<Contact>("contact_unmerge", { mergeId }),
+  referentialAudit: () => invoke<OrphanReport[]>("referential_audit"),
+  /** Reattach orphans to a contact, or delete them when reattachTo is null; returns the pre-repair report */
+  referentialRepair: (reattachTo?: string | null) =>
+    invoke<OrphanReport[]>("referential_repair", { reattachTo: reattachTo ?? null }),
+  viewPrefsGet: (view: ViewPrefsView) => invoke<string | null>("view_prefs_get", { view }),
+  viewPrefsSet: (view: ViewPrefsView, json: string) =>
+    invoke<void>("view_prefs_set", { view, json }),
   /** E3: Write export file to user-chosen path (local only, no server) */
   writeExportFile: (path: string, content: string) =>
     invoke<void>("write_export_file", { path, content }),
+  /** Self-contained HTML page of the matching contacts, for sharing */
+  segmentExportHtml: (filter: SegmentFilter) => invoke<string>("segment_export_html", { filter }),
+  /** Versioned JSON of the whole database (version 1) */
+  exportJson: () => invoke<string>("export_json"),
+  /** Restores an exportJson document; existing rows are kept (INSERT OR IGNORE) */
+  importJson: (content: string) => invoke<JsonImportCount[]>("import_json", { content }),
+  diffExports: (aJson: string, bJson: string) =>
+    invoke<TableDiff[]>("diff_exports", { aJson, bJson }),
 
-  /** F1: Encryption state — "ready" or need_setup (first_run / migrate_plain) */
+  /** F1: Encryption state — "ready" or need_setup (first_run / migrate_plain / wrong_passphrase / locked) */
   getEncryptionState: () =>
-    invoke<{ ready?: void; need_setup?: { reason: "first_run" | "migrate_plain" } }>("get_encryption_state"),
+    invoke<{ ready?: void; need_setup?: { reason: SetupReason } }>("get_encryption_state"),
   /** F1.3: First-run — create key (device or passphrase), store in keychain */
   encryptionSetupCreateKey: (passphrase?: string | null) =>
     invoke<void>("encryption_setup_create_key", { passphrase: passphrase ?? null }),
@@ -279,9 +1042,36 @@ export const api = {
   encryptionMigratePlainDb: (passphrase?: string | null) =>
     invoke<void>("encryption_migrate_plain_db", { passphrase: passphrase ?? null }),
   /** After setup/migrate: open DB and clear setup state */
-  encryptionSetupOpenDb: () => invoke<void>("encryption_setup_open_db"),
+  /** Also unlocks after lockNow; passphrase vaults must pass the passphrase then */
+  encryptionSetupOpenDb: (passphrase?: string) =>
+    invoke<void>("encryption_setup_open_db", { passphrase: passphrase ?? null }),
+  /** Flushes, closes the DB and wipes the decrypted temp file; state becomes need_setup "locked" */
+  lockNow: () => invoke<void>("lock_now"),
+  isLocked: () => invoke<boolean>("is_locked"),
+  encryptionUsesPassphrase: () => invoke<boolean>("encryption_uses_passphrase"),
+  /** 0 = never */
+  autoLockMinutesGet: () => invoke<number>("auto_lock_minutes_get"),
+  autoLockMinutesSet: (minutes: number) => invoke<void>("auto_lock_minutes_set", { minutes }),
+  /** UI preferences only; the backend rejects keys outside its allowlist */
+  appSettingGet: (key: UiSettingKey) => invoke<string | null>("app_setting_get", { key }),
+  /** null removes the preference */
+  appSettingSet: (key: UiSettingKey, value: string | null) =>
+    invoke<void>("app_setting_set", { key, value }),
+  /** F1.3: Re-enter the vault passphrase (wrong_passphrase state), then call encryptionSetupOpenDb */
+  encryptionUnlock: (passphrase: string) => invoke<void>("encryption_unlock", { passphrase }),
+  /** UNSAFE developer mode: stores the vault as plain vault.db until encryptionEnable */
+  encryptionDisable: (confirm: string) => invoke<void>("encryption_disable", { confirm }),
+  /** passphrase null = device key in keychain */
+  encryptionEnable: (passphrase: string | null) =>
+    invoke<void>("encryption_enable", { passphrase }),
+  /** F1.3: Rotate passphrase; rejects with "Mevcut parola hatalı" if the old one is wrong */
+  encryptionChangePassphrase: (oldPassphrase: string, newPassphrase: string) =>
+    invoke<void>("encryption_change_passphrase", { oldPassphrase, newPassphrase }),
 
   /** F3.2: User backup folder — "Yedekleri buraya da kopyala" */
+  backupNow: () => invoke<string>("backup_now"),
+  backupKeepCountGet: () => invoke<number>("backup_keep_count_get"),
+  backupKeepCountSet: (count: number) => invoke<void>("backup_keep_count_set", { count }),
   backupDirGet: () => invoke<string>("backup_dir_get"),
   backupDirSet: (path: string) => invoke<void>("backup_dir_set", { path }),
 
@@ -291,4 +1081,7 @@ export const api = {
   /** G1.3: Open from sync folder — copy file + unlock with passphrase */
   openFromSyncFolder: (folderPath: string, passphrase: string) =>
     invoke<void>("open_from_sync_folder", { folderPath, passphrase }),
+  /** G1.3: Merge sync folder vault into local DB (newest edit wins per row) */
+  syncMerge: (folderPath: string, passphrase: string) =>
+    invoke<SyncMergeSummary>("sync_merge", { folderPath, passphrase }),
 };
@@ -9,6 +9,7 @@ import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { ArrowLeft, Pencil, Save, X, User } from "lucide-react";
 import { DomainAvatar } from "@/components/DomainAvatar";
 import { MarkdownView } from "@/components/MarkdownView";
+import { useAttachmentAccept } from "@/hooks/useAttachmentAccept";
 import { open } from "@tauri-apps/plugin-shell";
 
 export function CompanyDetail() {
@@ -23,6 +24,7 @@ export function CompanyDetail() {
   const [attachFile, setAttachFile] = useState<File | null>(null);
   const [attachError, setAttachError] = useState<string | null>(null);
   const [attachUploading, setAttachUploading] = useState(false);
+  const attachAccept = useAttachmentAccept();
   const [notesPreview, setNotesPreview] = useState(false);
 
   const load = () => {
@@ -265,7 +267,7 @@ export function CompanyDetail() {
         <CardContent className="space-y-3">
           <input
             type="file"
-            accept=".pdf,.doc,.docx,.ppt,.pptx"
+            accept={attachAccept}
             onChange={(e) => setAttachFile(e.target.files?.[0] ?? null)}
             className="block w-full text-sm"
           />
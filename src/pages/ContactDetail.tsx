@@ -19,6 +19,7 @@ import { ArrowLeft, StickyNote, Bell, Pencil, Save, X, Calendar, Phone, Mail, Me
 import { open } from "@tauri-apps/plugin-shell";
 import { getRelationshipHealth, HEALTH_COLORS, type HealthStatus } from "@/lib/relationshipHealth";
 import { MarkdownView } from "@/components/MarkdownView";
+import { useAttachmentAccept } from "@/hooks/useAttachmentAccept";
 import { NOTE_TEMPLATES, getTemplateById } from "@/lib/noteTemplates";
 
 function formatDate(s: string | null) {
@@ -92,6 +93,7 @@ export function ContactDetail() {
   const [reminders, setReminders] = useState<Reminder[]>([]);
   const [attachments, setAttachments] = useState<Attachment[]>([]);
   const [attachFile, setAttachFile] = useState<File | null>(null);
+  const attachAccept = useAttachmentAccept();
   const [attachError, setAttachError] = useState<string | null>(null);
   const [attachUploading, setAttachUploading] = useState(false);
   const [loading, setLoading] = useState(true);
@@ -1017,7 +1019,7 @@ export function ContactDetail() {
             <CardContent className="space-y-3">
               <input
                 type="file"
-                accept=".pdf,.doc,.docx,.ppt,.pptx"
+                accept={attachAccept}
                 onChange={(e) => setAttachFile(e.target.files?.[0] ?? null)}
                 className="block w-full text-sm"
               />
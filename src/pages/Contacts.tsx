@@ -110,7 +110,7 @@ export function Contacts() {
 
   useEffect(() => {
     setLoading(true);
-    Promise.all([api.contactList(), api.customFieldList(), api.companyList()])
+    Promise.all([api.contactList(), api.customFieldList("contact"), api.companyList()])
       .then(([c, f, co]) => {
         try {
           setContacts(Array.isArray(c) ? c : []);
@@ -46,15 +46,6 @@ function formatDate(s: string | null) {
   }
 }
 
-function parseOptions(options: string | null): string[] {
-  if (!options) return [];
-  try {
-    return JSON.parse(options) as string[];
-  } catch {
-    return [];
-  }
-}
-
 export function Dashboard() {
   const [contacts, setContacts] = useState<Contact[]>([]);
   const [reminders, setReminders] = useState<Reminder[]>([]);
@@ -63,7 +54,7 @@ export function Dashboard() {
 
   const notifiedDue = useRef<Set<string>>(new Set());
   useEffect(() => {
-    Promise.all([api.contactList(), api.reminderList(), api.customFieldList()])
+    Promise.all([api.contactList(), api.reminderList(), api.customFieldList("contact")])
       .then(async ([c, r, fields]) => {
         setContacts(c);
         setReminders(r);
@@ -77,14 +68,8 @@ export function Dashboard() {
           (f: CustomField) => f.id === "cf_stage" || f.name === "Stage"
         );
         if (stageField) {
-          const options = parseOptions(stageField.options);
-          const counts = await Promise.all(
-            options.map(async (label) => {
-              const ids = await api.contactIdsByCustomValue(stageField.id, label);
-              return { label, count: ids.length };
-            })
-          );
-          setStageStats(counts);
+          const report = await api.pipelineReport(stageField.id);
+          setStageStats(report.map((r) => ({ label: r.option ?? "Stage yok", count: r.count })));
         } else {
           setStageStats([]);
         }
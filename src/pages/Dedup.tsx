@@ -44,6 +44,7 @@ function reasonLabel(r: string) {
   if (r === "email") return "Email eşleşmesi";
   if (r === "phone") return "Telefon eşleşmesi";
   if (r === "name") return "İsim benzerliği";
+  if (r === "phonetic") return "Okunuş benzerliği";
   return r;
 }
 
@@ -86,6 +87,8 @@ export function Dedup() {
   const [customA, setCustomA] = useState<CustomValue[]>([]);
   const [customB, setCustomB] = useState<CustomValue[]>([]);
   const [merging, setMerging] = useState(false);
+  const [threshold, setThreshold] = useState("");
+  const [phonetic, setPhonetic] = useState(true);
 
   const load = () => {
     setLoading(true);
@@ -98,8 +101,36 @@ export function Dedup() {
 
   useEffect(() => {
     load();
+    api
+      .dedupSettingsGet()
+      .then((s) => {
+        setThreshold(s.name_threshold != null ? String(s.name_threshold) : "");
+        setPhonetic(s.phonetic ?? true);
+      })
+      .catch(console.error);
   }, []);
 
+  const applySettings = () => {
+    const value = Number(threshold);
+    if (!Number.isFinite(value)) return;
+    setLoading(true);
+    api
+      .dedupCandidates({ name_threshold: value, phonetic })
+      .then(setCandidates)
+      .catch(console.error)
+      .finally(() => setLoading(false));
+  };
+
+  const ignorePair = (c: DedupCandidate) => {
+    api
+      .dedupIgnore(c.a.id, c.b.id)
+      .then(() => {
+        if (selected === c) setSelected(null);
+        setCandidates((list) => list.filter((x) => x !== c));
+      })
+      .catch(console.error);
+  };
+
   const selectCandidate = (c: DedupCandidate) => {
     setSelected(c);
     setKeep("a");
@@ -220,6 +251,26 @@ export function Dedup() {
           <CardTitle className="text-base">{candidates.length} aday</CardTitle>
         </CardHeader>
         <CardContent>
+          <div className="mb-4 flex items-center gap-2 text-sm">
+            <Label htmlFor="dedup-threshold">İsim benzerlik eşiği</Label>
+            <input
+              id="dedup-threshold"
+              type="number"
+              min={0.5}
+              max={1}
+              step={0.01}
+              value={threshold}
+              onChange={(e) => setThreshold(e.target.value)}
+              className="w-20 rounded border px-2 py-1"
+            />
+            <label className="flex items-center gap-1">
+              <input type="checkbox" checked={phonetic} onChange={(e) => setPhonetic(e.target.checked)} />
+              Okunuş benzerliği
+            </label>
+            <Button size="sm" variant="outline" onClick={applySettings}>
+              Uygula
+            </Button>
+          </div>
           {candidates.length === 0 && (
             <p className="text-sm text-muted-foreground">Şimdilik eşleşme bulunamadı.</p>
           )}
@@ -240,12 +291,18 @@ export function Dedup() {
                   </div>
                   <div className="flex items-center gap-2">
                     <div className="flex flex-wrap gap-1 text-xs">
+                      <span className="rounded bg-muted px-2 py-1 font-medium">
+                        %{Math.round(c.confidence * 100)}
+                      </span>
                       {c.reasons.map((r) => (
                         <span key={r} className="rounded bg-muted px-2 py-1 text-muted-foreground">
                           {reasonLabel(r)}
                         </span>
                       ))}
                     </div>
+                    <Button size="sm" variant="ghost" onClick={() => ignorePair(c)}>
+                      Duplikat değil
+                    </Button>
                     <Button size="sm" variant="outline" onClick={() => selectCandidate(c)}>
                       Birleştir
                     </Button>
@@ -1,5 +1,5 @@
-import { useState } from "react";
-import { api } from "@/lib/api";
+import { useEffect, useState } from "react";
+import { api, ERR_WRONG_PASSPHRASE, type SetupReason } from "@/lib/api";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Input } from "@/components/ui/input";
@@ -7,8 +7,6 @@ import { Label } from "@/components/ui/label";
 import { KeyRound, AlertTriangle, FolderOpen } from "lucide-react";
 import { open as openDialog } from "@tauri-apps/plugin-dialog";
 
-type SetupReason = "first_run" | "migrate_plain";
-
 const RECOVERY_WARNING =
   "Anahtarı veya passphrase'ı unutursanız verilerinize tekrar erişemezsiniz. " +
   "Düzenli yedek (Export) almanızı öneririz.";
@@ -30,9 +28,37 @@ export function EncryptionSetup({
   const [syncPassphrase, setSyncPassphrase] = useState("");
 
   const isFirstRun = reason === "first_run";
+  const isLocked = reason === "locked";
+  const isUnlock = reason === "wrong_passphrase" || isLocked;
+  // Device-key vaults unlock without a passphrase after lockNow.
+  const [needsPassphrase, setNeedsPassphrase] = useState(!isLocked);
+
+  useEffect(() => {
+    if (!isLocked) return;
+    api.encryptionUsesPassphrase().then(setNeedsPassphrase).catch(() => setNeedsPassphrase(true));
+  }, [isLocked]);
 
   const submit = async () => {
     setError(null);
+    if (isUnlock) {
+      setLoading(true);
+      try {
+        if (isLocked) {
+          await api.encryptionSetupOpenDb(needsPassphrase ? passphrase : undefined);
+          onComplete();
+          return;
+        }
+        await api.encryptionUnlock(passphrase);
+        await api.encryptionSetupOpenDb();
+        onComplete();
+      } catch (e) {
+        const msg = String(e);
+        setError(msg === ERR_WRONG_PASSPHRASE ? "Parola hatalı, tekrar deneyin." : msg);
+      } finally {
+        setLoading(false);
+      }
+      return;
+    }
     if (openFromSync) {
       if (!syncFolderPath.trim()) {
         setError("Sync klasörü seçin.");
@@ -81,6 +107,48 @@ export function EncryptionSetup({
     }
   };
 
+  if (isUnlock) {
+    return (
+      <div className="flex min-h-screen items-center justify-center bg-background p-6">
+        <Card className="w-full max-w-md">
+          <CardHeader>
+            <CardTitle className="flex items-center gap-2 text-xl">
+              <KeyRound className="h-6 w-6" />
+              Kasanın kilidini aç
+            </CardTitle>
+            <p className="text-sm text-muted-foreground">
+              {isLocked
+                ? "Kasa kilitlendi."
+                : "Kayıtlı anahtar veritabanını açamadı. Kasanın passphrase'ını girin."}
+            </p>
+          </CardHeader>
+          <CardContent className="space-y-4">
+            {needsPassphrase && (
+              <div className="space-y-2">
+                <Label htmlFor="unlock-passphrase">Passphrase</Label>
+                <Input
+                  id="unlock-passphrase"
+                  type="password"
+                  value={passphrase}
+                  onChange={(e) => setPassphrase(e.target.value)}
+                  autoComplete="current-password"
+                />
+              </div>
+            )}
+            {error && (
+              <p className="rounded border border-destructive/50 bg-destructive/10 p-2 text-sm text-destructive">
+                {error}
+              </p>
+            )}
+            <Button onClick={submit} disabled={loading || (needsPassphrase && !passphrase)} className="w-full">
+              {loading ? "İşleniyor…" : "Kilidi aç"}
+            </Button>
+          </CardContent>
+        </Card>
+      </div>
+    );
+  }
+
   return (
     <div className="flex min-h-screen items-center justify-center bg-background p-6">
       <Card className="w-full max-w-md">
@@ -6,7 +6,7 @@ import { api, type Contact } from "@/lib/api";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Label } from "@/components/ui/label";
-import { Download, FileJson, FileSpreadsheet } from "lucide-react";
+import { Contact as ContactIcon, Download, FileJson, FileSpreadsheet, FileText } from "lucide-react";
 
 const CSV_FIELDS: { key: keyof Contact; label: string }[] = [
   { key: "first_name", label: "Ad" },
@@ -25,6 +25,8 @@ const CSV_FIELDS: { key: keyof Contact; label: string }[] = [
   { key: "notes", label: "Notlar" },
   { key: "last_touched_at", label: "Son temas" },
   { key: "next_touch_at", label: "Sonraki temas" },
+  { key: "consent_status", label: "Consent (GDPR)" },
+  { key: "consent_at", label: "Consent tarihi" },
   { key: "created_at", label: "Oluşturulma" },
   { key: "updated_at", label: "Güncellenme" },
 ];
@@ -68,7 +70,7 @@ export function Export() {
   const [csvSelected, setCsvSelected] = useState<Set<keyof Contact>>(
     () => new Set(DEFAULT_CSV_KEYS)
   );
-  const [exporting, setExporting] = useState<"csv" | "json" | null>(null);
+  const [exporting, setExporting] = useState<"csv" | "json" | "full" | "vcf" | "html" | null>(null);
   const [error, setError] = useState<string | null>(null);
 
   const isFromContacts = passedContacts != null && passedContacts.length >= 0;
@@ -149,6 +151,73 @@ export function Export() {
     }
   };
 
+  const runExportFullJson = async () => {
+    setError(null);
+    setExporting("full");
+    try {
+      const path = await save({
+        defaultPath: `vaultcrm-full-${new Date().toISOString().slice(0, 10)}.json`,
+        filters: [{ name: "JSON", extensions: ["json"] }],
+      });
+      if (path) {
+        const content = await api.exportJson();
+        await api.writeExportFile(path, content);
+      }
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setExporting(null);
+    }
+  };
+
+  const runExportHtml = async () => {
+    if (contacts.length === 0) {
+      setError("Dışa aktarılacak kişi yok.");
+      return;
+    }
+    setError(null);
+    setExporting("html");
+    try {
+      const path = await save({
+        defaultPath: `vaultcrm-list-${new Date().toISOString().slice(0, 10)}.html`,
+        filters: [{ name: "HTML", extensions: ["html"] }],
+      });
+      if (path) {
+        const content = await api.segmentExportHtml(
+          isFromContacts ? { contact_ids: contacts.map((c) => c.id) } : {}
+        );
+        await api.writeExportFile(path, content);
+      }
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setExporting(null);
+    }
+  };
+
+  const runExportVcf = async () => {
+    if (contacts.length === 0) {
+      setError("Dışa aktarılacak kişi yok.");
+      return;
+    }
+    setError(null);
+    setExporting("vcf");
+    try {
+      const path = await save({
+        defaultPath: `vaultcrm-contacts-${new Date().toISOString().slice(0, 10)}.vcf`,
+        filters: [{ name: "vCard", extensions: ["vcf"] }],
+      });
+      if (path) {
+        const content = await api.exportVcard(contacts.map((c) => c.id));
+        await api.writeExportFile(path, content);
+      }
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setExporting(null);
+    }
+  };
+
   if (loading) {
     return (
       <div className="flex h-full items-center justify-center p-8">
@@ -242,7 +311,7 @@ export function Export() {
               Tam veri seti; yedek veya taşıma için.
             </p>
           </CardHeader>
-          <CardContent>
+          <CardContent className="flex flex-wrap gap-2">
             <Button
               onClick={runExportJson}
               disabled={exporting !== null}
@@ -250,6 +319,56 @@ export function Export() {
               <Download className="mr-2 h-4 w-4" />
               {exporting === "json" ? "Kaydediliyor…" : "JSON indir"}
             </Button>
+            <Button
+              variant="outline"
+              onClick={runExportFullJson}
+              disabled={exporting !== null}
+            >
+              <Download className="mr-2 h-4 w-4" />
+              {exporting === "full" ? "Kaydediliyor…" : "Tüm veritabanı (JSON)"}
+            </Button>
+          </CardContent>
+        </Card>
+
+        <Card>
+          <CardHeader>
+            <CardTitle className="flex items-center gap-2 text-base">
+              <ContactIcon className="h-4 w-4" />
+              vCard
+            </CardTitle>
+            <p className="text-sm text-muted-foreground">
+              Telefon rehberine aktarmak için; ikincil e-posta ve telefonlar da dahil.
+            </p>
+          </CardHeader>
+          <CardContent>
+            <Button
+              onClick={runExportVcf}
+              disabled={exporting !== null}
+            >
+              <Download className="mr-2 h-4 w-4" />
+              {exporting === "vcf" ? "Kaydediliyor…" : "VCF indir"}
+            </Button>
+          </CardContent>
+        </Card>
+
+        <Card>
+          <CardHeader>
+            <CardTitle className="flex items-center gap-2 text-base">
+              <FileText className="h-4 w-4" />
+              HTML
+            </CardTitle>
+            <p className="text-sm text-muted-foreground">
+              VaultCRM kullanmayan biriyle paylaşmak için salt okunur liste; her tarayıcıda açılır.
+            </p>
+          </CardHeader>
+          <CardContent>
+            <Button
+              onClick={runExportHtml}
+              disabled={exporting !== null}
+            >
+              <Download className="mr-2 h-4 w-4" />
+              {exporting === "html" ? "Kaydediliyor…" : "HTML indir"}
+            </Button>
           </CardContent>
         </Card>
       </div>
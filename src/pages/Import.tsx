@@ -1,7 +1,7 @@
 import { useState, useCallback } from "react";
 import { useNavigate } from "react-router-dom";
 import Papa from "papaparse";
-import { api, type ImportRow } from "@/lib/api";
+import { api, type ImportMode, type ImportPreviewRow, type ImportResult, type ImportRow } from "@/lib/api";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Label } from "@/components/ui/label";
@@ -26,13 +26,16 @@ const GENERIC_MAP: Record<string, keyof ImportRow> = {
   last_name: "last_name",
   lastname: "last_name",
   email: "email",
+  email_secondary: "email_secondary",
   company: "company",
   title: "title",
   city: "city",
   country: "country",
   phone: "phone",
+  phone_secondary: "phone_secondary",
   linkedin: "linkedin_url",
   linkedin_url: "linkedin_url",
+  twitter_url: "twitter_url",
   website: "website",
 };
 
@@ -54,13 +57,21 @@ function mapRow(cols: Record<string, string>, headers: string[]): ImportRow {
   return row;
 }
 
+function isVcf(file: File): boolean {
+  return file.name.toLowerCase().endsWith(".vcf");
+}
+
 export function Import() {
   const navigate = useNavigate();
   const [file, setFile] = useState<File | null>(null);
   const [preview, setPreview] = useState<ImportRow[]>([]);
+  const [matches, setMatches] = useState<ImportPreviewRow[] | null>(null);
+  const [mode, setMode] = useState<ImportMode>("create_all");
+  const [linkCompanies, setLinkCompanies] = useState(true);
   const [importing, setImporting] = useState(false);
-  const [done, setDone] = useState<number | null>(null);
+  const [done, setDone] = useState<ImportResult | null>(null);
   const [dedupCount, setDedupCount] = useState<number | null>(null);
+  const [undoneCount, setUndoneCount] = useState<number | null>(null);
   const [error, setError] = useState<string | null>(null);
 
   const onFileChange = useCallback((e: React.ChangeEvent<HTMLInputElement>) => {
@@ -68,12 +79,18 @@ export function Import() {
     setError(null);
     setDone(null);
     setDedupCount(null);
+    setUndoneCount(null);
+    setMatches(null);
     if (!f) {
       setFile(null);
       setPreview([]);
       return;
     }
     setFile(f);
+    if (isVcf(f)) {
+      setPreview([]);
+      return;
+    }
     Papa.parse(f, {
       header: true,
       skipEmptyLines: true,
@@ -83,13 +100,34 @@ export function Import() {
           (r) => mapRow(r, headers)
         );
         setPreview(rows.slice(0, 10));
+        api.importPreview(rows).then(setMatches).catch(() => setMatches(null));
       },
       error: (err) => setError(err.message),
     });
   }, []);
 
+  const finishImport = (result: ImportResult) => {
+    setDone(result);
+    setFile(null);
+    setPreview([]);
+    setMatches(null);
+    return api.dedupCandidates().then((candidates) => setDedupCount(candidates.length));
+  };
+
   const runImport = () => {
-    if (!file || preview.length === 0) return;
+    if (!file) return;
+    if (isVcf(file)) {
+      setError(null);
+      setImporting(true);
+      file
+        .text()
+        .then((content) => api.importVcard(content))
+        .then(finishImport)
+        .catch((e) => setError(String(e)))
+        .finally(() => setImporting(false));
+      return;
+    }
+    if (preview.length === 0) return;
     setError(null);
     setImporting(true);
     Papa.parse(file, {
@@ -101,16 +139,8 @@ export function Import() {
           (r) => mapRow(r, headers)
         );
         api
-          .importContacts(rows)
-          .then((count) => {
-            setDone(count);
-            setFile(null);
-            setPreview([]);
-            return api.dedupCandidates();
-          })
-          .then((candidates) => {
-            setDedupCount(candidates.length);
-          })
+          .importContacts(rows, file.name, mode, linkCompanies)
+          .then(finishImport)
           .catch((e) => setError(String(e)))
           .finally(() => setImporting(false));
       },
@@ -121,6 +151,19 @@ export function Import() {
     });
   };
 
+  const undoImport = () => {
+    if (!done?.batch_id) return;
+    setError(null);
+    api
+      .importBatchUndo(done.batch_id)
+      .then((count) => {
+        setUndoneCount(count);
+        setDone(null);
+        setDedupCount(null);
+      })
+      .catch((e) => setError(String(e)));
+  };
+
   return (
     <div className="p-6">
       <h1 className="mb-6 text-2xl font-semibold">CSV Import</h1>
@@ -133,7 +176,8 @@ export function Import() {
           <p className="text-sm text-muted-foreground">
             LinkedIn’den “Verilerinizin bir kopyasını alın” ile indirdiğiniz
             Connections.csv veya benzeri CSV’yi seçin. Sütunlar otomatik eşlenir
-            (First Name, Last Name, Email, Company, Position).
+            (First Name, Last Name, Email, Company, Position). Telefon rehberinden
+            dışa aktarılmış .vcf dosyaları da desteklenir.
           </p>
         </CardHeader>
         <CardContent className="space-y-4">
@@ -142,7 +186,7 @@ export function Import() {
             <input
               id="csv"
               type="file"
-              accept=".csv"
+              accept=".csv,.vcf"
               onChange={onFileChange}
               className="mt-2 block w-full text-sm"
             />
@@ -154,12 +198,39 @@ export function Import() {
           )}
           {done !== null && (
             <p className="text-sm text-green-600">
-              {done} kişi içe aktarıldı.{" "}
+              {done.inserted} kişi içe aktarıldı
+              {done.updated > 0 && `, ${done.updated} mevcut kişi güncellendi`}
+              {done.companies_created > 0 && `, ${done.companies_created} şirket oluşturuldu`}.{" "}
               <Button variant="link" className="h-auto p-0" onClick={() => navigate("/contacts")}>
                 Kişilere git →
               </Button>
+              {done.batch_id && (
+                <>
+                  {" · "}
+                  <Button variant="link" className="h-auto p-0" onClick={undoImport}>
+                    Geri al
+                  </Button>
+                </>
+              )}
+            </p>
+          )}
+          {undoneCount !== null && (
+            <p className="text-sm text-muted-foreground">
+              İçe aktarma geri alındı: {undoneCount} kişi çöp kutusuna taşındı.
             </p>
           )}
+          {done !== null && done.skipped.length > 0 && (
+            <div className="text-sm text-amber-700 dark:text-amber-300">
+              <p>{done.skipped.length} satır atlandı:</p>
+              <ul className="list-inside list-disc">
+                {done.skipped.slice(0, 20).map((s) => (
+                  <li key={s.row_index}>
+                    Satır {s.row_index + 1}: {s.reason}
+                  </li>
+                ))}
+              </ul>
+            </div>
+          )}
           {dedupCount !== null && dedupCount > 0 && (
             <p className="text-sm text-muted-foreground">
               {dedupCount} olası tekrar bulundu.{" "}
@@ -168,11 +239,44 @@ export function Import() {
               </Button>
             </p>
           )}
+          {file && isVcf(file) && (
+            <Button onClick={runImport} disabled={importing}>
+              {importing ? "İçe aktarılıyor…" : "vCard içe aktar"}
+            </Button>
+          )}
           {preview.length > 0 && (
             <>
               <p className="text-sm text-muted-foreground">
                 Önizleme (ilk 10): {preview.map((r) => `${r.first_name ?? ""} ${r.last_name ?? ""}`.trim()).filter(Boolean).join(", ") || "—"}
               </p>
+              {matches && (
+                <p className="text-sm text-muted-foreground">
+                  {matches.filter((m) => m.status === "new").length} yeni ·{" "}
+                  {matches.filter((m) => m.status === "duplicate_of").length} mevcut kişiyle eşleşiyor ·{" "}
+                  {matches.filter((m) => m.status === "invalid").length} geçersiz
+                </p>
+              )}
+              <div>
+                <Label htmlFor="import-mode">Eşleşen kişiler (e-posta / telefon)</Label>
+                <select
+                  id="import-mode"
+                  value={mode}
+                  onChange={(e) => setMode(e.target.value as ImportMode)}
+                  className="mt-2 h-10 w-full rounded-md border border-input bg-background px-3 text-sm"
+                >
+                  <option value="create_all">Hepsini yeni kişi olarak ekle</option>
+                  <option value="skip_duplicates">Eşleşenleri atla</option>
+                  <option value="update_existing">Eşleşenlerin boş alanlarını doldur</option>
+                </select>
+              </div>
+              <label className="flex items-center gap-2 text-sm">
+                <input
+                  type="checkbox"
+                  checked={linkCompanies}
+                  onChange={(e) => setLinkCompanies(e.target.checked)}
+                />
+                Şirket adlarını şirket kartlarına bağla (yoksa oluştur)
+              </label>
               <Button
                 onClick={runImport}
                 disabled={importing}
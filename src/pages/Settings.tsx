@@ -1,5 +1,5 @@
 import { useEffect, useState } from "react";
-import { api, type CustomField } from "@/lib/api";
+import { api, AUTO_LOCK_CHANGED_EVENT, type CustomField } from "@/lib/api";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
@@ -37,6 +37,11 @@ export function Settings() {
   const [backupDir, setBackupDir] = useState("");
   const [backupDirSaving, setBackupDirSaving] = useState(false);
   const [backupDirError, setBackupDirError] = useState<string | null>(null);
+  const [backingUp, setBackingUp] = useState(false);
+  const [keepCount, setKeepCount] = useState("7");
+  const [lastBackupPath, setLastBackupPath] = useState<string | null>(null);
+  const [autoLockMinutes, setAutoLockMinutes] = useState("0");
+  const [lockError, setLockError] = useState<string | null>(null);
 
   useEffect(() => {
     setHealthThresholdsState(getHealthThresholds());
@@ -47,12 +52,21 @@ export function Settings() {
   const [syncFolderError, setSyncFolderError] = useState<string | null>(null);
 
   useEffect(() => {
-    Promise.all([api.customFieldList(), api.attachmentsDirGet(), api.backupDirGet(), api.syncFolderGet()])
-      .then(([fields, dir, backup, sync]) => {
+    Promise.all([
+      api.customFieldList(),
+      api.attachmentsDirGet(),
+      api.backupDirGet(),
+      api.syncFolderGet(),
+      api.backupKeepCountGet(),
+      api.autoLockMinutesGet(),
+    ])
+      .then(([fields, dir, backup, sync, keep, lockMinutes]) => {
         setCustomFields(fields);
         setAttachmentsDir(dir);
         setBackupDir(backup ?? "");
         setSyncFolder(sync ?? "");
+        setKeepCount(String(keep));
+        setAutoLockMinutes(String(lockMinutes));
       })
       .catch(console.error)
       .finally(() => setLoading(false));
@@ -120,6 +134,54 @@ export function Settings() {
           <p className="text-xs text-muted-foreground">
             Açıldığında uygulama çöktüğünde anonim crash log gönderilebilir. Varsayılan: kapalı.
           </p>
+          <div className="space-y-2">
+            <Label>Otomatik kilit (dakika, 0 = kapalı)</Label>
+            <div className="flex gap-2">
+              <Input
+                type="number"
+                min={0}
+                max={1440}
+                className="w-24"
+                value={autoLockMinutes}
+                onChange={(e) => setAutoLockMinutes(e.target.value)}
+              />
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => {
+                  setLockError(null);
+                  api
+                    .autoLockMinutesSet(Number(autoLockMinutes) || 0)
+                    .then(() => window.dispatchEvent(new Event(AUTO_LOCK_CHANGED_EVENT)))
+                    .catch((e) => setLockError(String(e)));
+                }}
+              >
+                Kaydet
+              </Button>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => {
+                  setLockError(null);
+                  // Reload so App shows the unlock screen.
+                  api
+                    .lockNow()
+                    .then(() => window.location.reload())
+                    .catch((e) => setLockError(String(e)));
+                }}
+              >
+                Şimdi kilitle
+              </Button>
+            </div>
+            <p className="text-xs text-muted-foreground">
+              Kilitlenince şifresi çözülmüş geçici veritabanı silinir; passphrase kullanıyorsanız açarken tekrar sorulur.
+            </p>
+            {lockError && (
+              <p className="rounded border border-destructive/50 bg-destructive/10 p-2 text-sm text-destructive">
+                {lockError}
+              </p>
+            )}
+          </div>
         </CardContent>
       </Card>
 
@@ -127,7 +189,7 @@ export function Settings() {
         <CardHeader>
           <CardTitle className="text-base">Yedekleme (F3)</CardTitle>
           <p className="text-sm text-muted-foreground">
-            F3.1: Uygulama kapanırken son hali otomatik yedeklenir; son 7 yedek tutulur (uygulama veri klasöründe backups).
+            F3.1: Uygulama kapanırken son hali otomatik yedeklenir; son {keepCount} yedek tutulur (uygulama veri klasöründe backups).
           </p>
         </CardHeader>
         <CardContent className="space-y-3">
@@ -156,6 +218,16 @@ export function Settings() {
               Boş bırakırsanız sadece uygulama veri klasöründeki yedekler kullanılır.
             </p>
           </div>
+          <div className="space-y-2">
+            <Label>Tutulacak yedek sayısı</Label>
+            <Input
+              type="number"
+              min={1}
+              className="w-24"
+              value={keepCount}
+              onChange={(e) => setKeepCount(e.target.value)}
+            />
+          </div>
           {backupDirError && (
             <p className="rounded border border-destructive/50 bg-destructive/10 p-2 text-sm text-destructive">
               {backupDirError}
@@ -168,11 +240,34 @@ export function Settings() {
             onClick={() => {
               setBackupDirSaving(true);
               setBackupDirError(null);
-              api.backupDirSet(backupDir.trim()).catch((e) => setBackupDirError(String(e))).finally(() => setBackupDirSaving(false));
+              Promise.all([api.backupDirSet(backupDir.trim()), api.backupKeepCountSet(Number(keepCount))])
+                .catch((e) => setBackupDirError(String(e)))
+                .finally(() => setBackupDirSaving(false));
+            }}
+          >
+            {backupDirSaving ? "Kaydediliyor…" : "Yedek ayarlarını kaydet"}
+          </Button>
+          <Button
+            variant="outline"
+            size="sm"
+            className="ml-2"
+            disabled={backingUp}
+            onClick={() => {
+              setBackingUp(true);
+              setBackupDirError(null);
+              setLastBackupPath(null);
+              api
+                .backupNow()
+                .then(setLastBackupPath)
+                .catch((e) => setBackupDirError(String(e)))
+                .finally(() => setBackingUp(false));
             }}
           >
-            {backupDirSaving ? "Kaydediliyor…" : "Yedek klasörünü kaydet"}
+            {backingUp ? "Yedekleniyor…" : "Şimdi yedekle"}
           </Button>
+          {lastBackupPath && (
+            <p className="text-xs text-muted-foreground">Yedek yazıldı: {lastBackupPath}</p>
+          )}
         </CardContent>
       </Card>
 
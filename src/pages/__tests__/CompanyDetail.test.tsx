@@ -9,6 +9,7 @@ const apiMock = vi.hoisted(() => ({
   contactListByCompany: vi.fn(),
   companyUpdate: vi.fn(),
   attachmentList: vi.fn(),
+  attachmentAllowedExtsGet: vi.fn(),
 }));
 
 vi.mock("@/lib/api", () => ({ api: apiMock }));
@@ -51,6 +52,7 @@ describe("CompanyDetail A2", () => {
     ]);
     apiMock.companyUpdate.mockResolvedValue({});
     apiMock.attachmentList.mockResolvedValue([]);
+    apiMock.attachmentAllowedExtsGet.mockResolvedValue(["pdf", "png"]);
   });
 
   it("shows contacts list with link", async () => {
@@ -13,6 +13,7 @@ const apiMock = vi.hoisted(() => ({
   contactUpdate: vi.fn(),
   contactCustomValuesSet: vi.fn(),
   attachmentList: vi.fn(),
+  attachmentAllowedExtsGet: vi.fn(),
 }));
 
 vi.mock("@/lib/api", () => ({ api: apiMock }));
@@ -49,6 +50,7 @@ describe("ContactDetail A1 validation", () => {
     apiMock.contactUpdate.mockResolvedValue({});
     apiMock.contactCustomValuesSet.mockResolvedValue({});
     apiMock.attachmentList.mockResolvedValue([]);
+    apiMock.attachmentAllowedExtsGet.mockResolvedValue(["pdf", "png"]);
   });
 
   it("shows validation errors for invalid email and phone", async () => {